@@ -0,0 +1,318 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use syn::Ident;
+
+use crate::grammar::{Expr, Grammar};
+
+/// A terminal within a lookahead sequence.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Term {
+    /// A token, identified by its variant.
+    Token(String),
+    /// The end of the input.
+    End,
+}
+
+/// A set of the token sequences that can come next, each up to `k` tokens long. A sequence shorter
+/// than `k` can only be followed by the end of the input (which FIRST sets leave implicit, and
+/// FOLLOW sets mark with [Term::End]).
+pub(crate) type Lookahead = BTreeSet<Vec<Term>>;
+
+/// The FIRST and FOLLOW sets of every rule in a [Grammar], computed to `k` tokens of lookahead.
+pub(crate) struct Analysis {
+    pub(crate) k: usize,
+    pub(crate) first: BTreeMap<String, Lookahead>,
+    pub(crate) follow: BTreeMap<String, Lookahead>,
+}
+
+impl Analysis {
+    /// Computes the FIRST and FOLLOW sets of the grammar's rules. Every `pub` rule is an entry
+    /// point, so may be followed by the end of the input.
+    pub(crate) fn new(grammar: &Grammar) -> Self {
+        let mut analysis = Self {
+            k: grammar.lookahead,
+            first: BTreeMap::new(),
+            follow: BTreeMap::new(),
+        };
+        for rule in &grammar.rules {
+            let name = rule.name.to_string();
+            analysis.first.insert(name.clone(), Lookahead::new());
+            let follow = match rule.public {
+                true => Lookahead::from([vec![Term::End]]),
+                false => Lookahead::new(),
+            };
+            analysis.follow.insert(name, follow);
+        }
+
+        // Both sets only ever grow, so this reaches a fixed point.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in &grammar.rules {
+                let first = analysis.first_of(&rule.body);
+                let entry = analysis.first.get_mut(&rule.name.to_string()).unwrap();
+                if *entry != first {
+                    *entry = first;
+                    changed = true;
+                }
+            }
+        }
+        changed = true;
+        while changed {
+            changed = false;
+            let mut additions = BTreeMap::new();
+            for rule in &grammar.rules {
+                let follow = &analysis.follow[&rule.name.to_string()];
+                analysis.follow_within(&rule.body, follow, &mut additions);
+            }
+            for (rule, addition) in additions {
+                let entry = analysis.follow.get_mut(&rule).unwrap();
+                let len = entry.len();
+                entry.extend(addition);
+                changed |= entry.len() != len;
+            }
+        }
+        analysis
+    }
+
+    /// Returns FIRST(expr): every sequence of up to `k` tokens it can begin with.
+    pub(crate) fn first_of(&self, expr: &Expr) -> Lookahead {
+        match expr {
+            Expr::Token(token) => Lookahead::from([vec![Term::Token(token.key.clone())]]),
+            Expr::Rule(rule) => self
+                .first
+                .get(&rule.to_string())
+                .cloned()
+                .unwrap_or_default(),
+            Expr::Seq(items) => items.iter().fold(Lookahead::from([vec![]]), |first, item| {
+                self.concat(&first, &self.first_of(item))
+            }),
+            Expr::Alt(alts, _) => alts.iter().flat_map(|alt| self.first_of(alt)).collect(),
+            Expr::Opt(expr, _) => {
+                let mut first = self.first_of(expr);
+                first.insert(vec![]);
+                first
+            }
+            Expr::Star(expr, _) => self.repeat(&self.first_of(expr)),
+            Expr::Plus(expr, _) => {
+                let first = self.first_of(expr);
+                self.concat(&first, &self.repeat(&first))
+            }
+        }
+    }
+
+    /// Returns if the expression can match without consuming anything.
+    pub(crate) fn nullable(&self, expr: &Expr) -> bool {
+        self.first_of(expr).contains(&vec![])
+    }
+
+    /// Returns every sequence in `a` followed by every sequence in `b`, cut to `k` tokens.
+    pub(crate) fn concat(&self, a: &Lookahead, b: &Lookahead) -> Lookahead {
+        let mut out = Lookahead::new();
+        for prefix in a {
+            if prefix.len() >= self.k || prefix.last() == Some(&Term::End) {
+                out.insert(prefix.clone());
+                continue;
+            }
+            for suffix in b {
+                let mut seq = prefix.clone();
+                seq.extend(suffix.iter().take(self.k - prefix.len()).cloned());
+                out.insert(seq);
+            }
+        }
+        out
+    }
+
+    /// Returns the FIRST set of zero or more repetitions of an expression with the provided one.
+    pub(crate) fn repeat(&self, first: &Lookahead) -> Lookahead {
+        let mut repeated = Lookahead::from([vec![]]);
+        loop {
+            let mut next = self.concat(first, &repeated);
+            next.insert(vec![]);
+            if next == repeated {
+                return repeated;
+            }
+            repeated = next;
+        }
+    }
+
+    /// Adds to `additions` what follows each rule referenced within the expression, given that
+    /// `follow` follows the expression itself.
+    fn follow_within(
+        &self,
+        expr: &Expr,
+        follow: &Lookahead,
+        additions: &mut BTreeMap<String, Lookahead>,
+    ) {
+        match expr {
+            Expr::Token(_) => {}
+            Expr::Rule(rule) => additions
+                .entry(rule.to_string())
+                .or_default()
+                .extend(follow.iter().cloned()),
+            Expr::Seq(items) => {
+                let mut after = follow.clone();
+                for item in items.iter().rev() {
+                    self.follow_within(item, &after, additions);
+                    after = self.concat(&self.first_of(item), &after);
+                }
+            }
+            Expr::Alt(alts, _) => {
+                for alt in alts {
+                    self.follow_within(alt, follow, additions);
+                }
+            }
+            Expr::Opt(expr, _) => self.follow_within(expr, follow, additions),
+            Expr::Star(expr, _) | Expr::Plus(expr, _) => {
+                let after = self.concat(&self.repeat(&self.first_of(expr)), follow);
+                self.follow_within(expr, &after, additions);
+            }
+        }
+    }
+}
+
+/// Checks the parts of a grammar that don't depend on lookahead: that every rule is defined once,
+/// that every referenced rule exists, that every rule is reachable from a `pub` one, and that no
+/// rule is left recursive.
+pub(crate) fn check(grammar: &Grammar) -> Vec<syn::Error> {
+    let mut errors = vec![];
+    let mut rules: HashMap<String, usize> = HashMap::new();
+    for (index, rule) in grammar.rules.iter().enumerate() {
+        if rules.insert(rule.name.to_string(), index).is_some() {
+            let message = format!("the rule `{}` is defined more than once", rule.name);
+            errors.push(syn::Error::new(rule.name.span(), message));
+        }
+    }
+    for rule in &grammar.rules {
+        rule.body.rules(&mut |reference| {
+            if !rules.contains_key(&reference.to_string()) {
+                let message = format!("there is no rule named `{reference}`");
+                errors.push(syn::Error::new(reference.span(), message));
+            }
+        });
+    }
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    if !grammar.rules.iter().any(|rule| rule.public) {
+        let message = "a grammar needs at least one `pub` rule to begin parsing from";
+        return vec![syn::Error::new(grammar.name.span(), message)];
+    }
+    let mut reachable = vec![false; grammar.rules.len()];
+    let mut stack: Vec<_> = (0..grammar.rules.len())
+        .filter(|index| grammar.rules[*index].public)
+        .collect();
+    while let Some(index) = stack.pop() {
+        if std::mem::replace(&mut reachable[index], true) {
+            continue;
+        }
+        grammar.rules[index].body.rules(&mut |reference| {
+            stack.push(rules[&reference.to_string()]);
+        });
+    }
+    for (rule, reachable) in grammar.rules.iter().zip(reachable) {
+        if !reachable {
+            let message = format!("the rule `{}` is not used by any `pub` rule", rule.name);
+            errors.push(syn::Error::new(rule.name.span(), message));
+        }
+    }
+
+    let analysis = Analysis::new(grammar);
+    let left: Vec<Vec<usize>> = grammar
+        .rules
+        .iter()
+        .map(|rule| {
+            let mut left = vec![];
+            analysis.left_corners(&rule.body, &mut |reference| {
+                left.push(rules[&reference.to_string()]);
+            });
+            left
+        })
+        .collect();
+    let mut reported = vec![false; grammar.rules.len()];
+    for (index, rule) in grammar.rules.iter().enumerate() {
+        if reported[index] {
+            continue;
+        }
+        let Some(cycle) = find_cycle(&left, index) else {
+            continue;
+        };
+        let path: Vec<_> = cycle
+            .iter()
+            .chain([&index])
+            .map(|index| grammar.rules[*index].name.to_string())
+            .collect();
+        for index in cycle {
+            reported[index] = true;
+        }
+        let message = format!(
+            "the rule `{}` is left recursive ({}), which recursive descent cannot parse; use \
+             repetition instead, such as `a = b (Op b)*;`",
+            rule.name,
+            path.join(" -> ")
+        );
+        errors.push(syn::Error::new(rule.name.span(), message));
+    }
+    errors
+}
+
+impl Analysis {
+    /// Calls `f` on each rule that can be reached at the start of the expression without consuming
+    /// a token first.
+    fn left_corners(&self, expr: &Expr, f: &mut impl FnMut(&Ident)) {
+        match expr {
+            Expr::Token(_) => {}
+            Expr::Rule(rule) => f(rule),
+            Expr::Seq(items) => {
+                for item in items {
+                    self.left_corners(item, f);
+                    if !self.nullable(item) {
+                        break;
+                    }
+                }
+            }
+            Expr::Alt(alts, _) => alts.iter().for_each(|alt| self.left_corners(alt, f)),
+            Expr::Opt(expr, _) | Expr::Star(expr, _) | Expr::Plus(expr, _) => {
+                self.left_corners(expr, f)
+            }
+        }
+    }
+}
+
+/// Returns a path of rules from `start` that leads back to it, beginning with `start`.
+fn find_cycle(edges: &[Vec<usize>], start: usize) -> Option<Vec<usize>> {
+    let mut visited = vec![false; edges.len()];
+    let mut path = vec![start];
+    let mut stack = vec![edges[start].iter()];
+    while let Some(next) = stack.last_mut() {
+        let Some(&next) = next.next() else {
+            stack.pop();
+            path.pop();
+            continue;
+        };
+        if next == start {
+            return Some(path);
+        }
+        if !std::mem::replace(&mut visited[next], true) {
+            path.push(next);
+            stack.push(edges[next].iter());
+        }
+    }
+    None
+}
+
+/// Writes a lookahead sequence for diagnostics.
+pub(crate) fn describe(seq: &[Term]) -> String {
+    if seq.is_empty() {
+        return "nothing".into();
+    }
+    let terms: Vec<_> = seq
+        .iter()
+        .map(|term| match term {
+            Term::Token(key) => key.as_str(),
+            Term::End => "<end>",
+        })
+        .collect();
+    terms.join(" ")
+}
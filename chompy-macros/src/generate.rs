@@ -0,0 +1,398 @@
+use std::collections::BTreeMap;
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{ToTokens, format_ident, quote};
+use syn::Lifetime;
+
+use crate::{
+    analysis::{Analysis, Lookahead, Term, check, describe},
+    grammar::{Expr, Grammar, Terminal},
+};
+
+/// Expands a `grammar!` invocation.
+pub(crate) fn expand(input: TokenStream) -> syn::Result<TokenStream> {
+    let grammar: Grammar = syn::parse2(input)?;
+    let errors = check(&grammar);
+    if !errors.is_empty() {
+        return Err(combine(errors));
+    }
+    let analysis = Analysis::new(&grammar);
+    let mut terminals = BTreeMap::new();
+    for rule in &grammar.rules {
+        rule.body.tokens(&mut |token| {
+            terminals.entry(token.key.clone()).or_insert(token);
+        });
+    }
+    let mut generator = Generator {
+        analysis: &analysis,
+        terminals,
+        rule: String::new(),
+        errors: vec![],
+    };
+
+    let parser = &grammar.parser;
+    let lifetimes = lifetimes(parser.to_token_stream());
+    let generics = match lifetimes.is_empty() {
+        true => quote!(),
+        false => quote!(<#(#lifetimes),*>),
+    };
+    let mut functions = vec![];
+    let mut variants = vec![];
+    let mut names = vec![];
+    let mut firsts = vec![];
+    let mut follows = vec![];
+    for rule in &grammar.rules {
+        generator.rule = rule.name.to_string();
+        let follow = &analysis.follow[&generator.rule];
+        let body = generator.expr(&rule.body, follow);
+        let name = &rule.name;
+        let name_str = name.to_string();
+        let variant = format_ident!("{}", camel_case(&name_str), span = name.span());
+        let attrs = &rule.attrs;
+        let vis = match rule.public {
+            true => quote!(pub),
+            false => quote!(),
+        };
+        let production = format!(" `{name} = {};`", rule.body);
+        let separator = (!attrs.is_empty()).then(|| quote!(#[doc = ""]));
+        functions.push(quote! {
+            #(#attrs)*
+            #separator
+            #[doc = #production]
+            #vis fn #name #generics (p: &mut #parser) -> ::chompy::diagnostics::Result<()> {
+                p.trace(#name_str, |p| {
+                    p.start_node(Rule::#variant);
+                    // The node is finished even if the rule fails, so that a parser which recovers
+                    // from the failure still records a well formed tree.
+                    let result = (|p: &mut #parser| -> ::chompy::diagnostics::Result<()> {
+                        #body
+                        Ok(())
+                    })(p);
+                    p.finish_node();
+                    result
+                })
+            }
+        });
+        let doc = format!(" The node built by the `{name}` rule.");
+        variants.push(quote!(#[doc = #doc] #variant));
+        names.push(quote!(Rule::#variant => #name_str));
+        let first = sequences(&analysis.first[&name_str]);
+        firsts.push(quote!(Rule::#variant => &[#(#first),*]));
+        let follow = sequences(follow);
+        follows.push(quote!(Rule::#variant => &[#(#follow),*]));
+    }
+    if !generator.errors.is_empty() {
+        return Err(combine(generator.errors));
+    }
+
+    let attrs = &grammar.attrs;
+    let vis = &grammar.vis;
+    let module = &grammar.name;
+    let k = grammar.lookahead;
+    Ok(quote! {
+        #(#attrs)*
+        #vis mod #module {
+            #[allow(unused_imports)]
+            use super::*;
+            use ::chompy::parse::Parse as _;
+
+            /// The kinds of node in the concrete syntax tree built by this grammar, one for each
+            /// of its rules.
+            #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+            pub enum Rule {
+                #(#variants,)*
+            }
+
+            impl Rule {
+                /// The amount of tokens of lookahead the grammar was analyzed with.
+                pub const LOOKAHEAD: usize = #k;
+
+                /// Returns the name of the rule.
+                pub fn name(self) -> &'static str {
+                    match self {
+                        #(#names,)*
+                    }
+                }
+
+                /// Returns the FIRST set of the rule: every sequence of tokens, up to
+                /// [Rule::LOOKAHEAD] long, that it can begin with. An empty sequence means the
+                /// rule can match nothing.
+                pub fn first(self) -> &'static [&'static [&'static str]] {
+                    match self {
+                        #(#firsts,)*
+                    }
+                }
+
+                /// Returns the FOLLOW set of the rule: every sequence of tokens, up to
+                /// [Rule::LOOKAHEAD] long, that can come after it, where `<end>` is the end of the
+                /// input.
+                pub fn follow(self) -> &'static [&'static [&'static str]] {
+                    match self {
+                        #(#follows,)*
+                    }
+                }
+            }
+
+            #(#functions)*
+        }
+    })
+}
+
+struct Generator<'a> {
+    analysis: &'a Analysis,
+    terminals: BTreeMap<String, &'a Terminal>,
+    rule: String,
+    errors: Vec<syn::Error>,
+}
+
+impl Generator<'_> {
+    /// Generates the code parsing an expression which is followed by `follow`.
+    fn expr(&mut self, expr: &Expr, follow: &Lookahead) -> TokenStream {
+        let analysis = self.analysis;
+        match expr {
+            Expr::Token(token) => {
+                let pat = &token.pat;
+                let expect = &token.expect;
+                quote! {
+                    match p.soft_peek()? {
+                        Some(tok) if matches!(::chompy::lex::Token::kind_ref(tok), #pat) => {}
+                        _ => {
+                            p.lexer().#expect;
+                            return Err(p.expected_one_of());
+                        }
+                    }
+                    p.take()?;
+                }
+            }
+            Expr::Rule(rule) => quote!(#rule(p)?;),
+            Expr::Seq(items) => {
+                let mut after = follow.clone();
+                let mut code = vec![];
+                for item in items.iter().rev() {
+                    code.push(self.expr(item, &after));
+                    after = analysis.concat(&analysis.first_of(item), &after);
+                }
+                code.reverse();
+                quote!(#(#code)*)
+            }
+            Expr::Alt(alts, span) => {
+                let lookaheads: Vec<_> = alts
+                    .iter()
+                    .map(|alt| analysis.concat(&analysis.first_of(alt), follow))
+                    .collect();
+                'check: for (i, a) in lookaheads.iter().enumerate() {
+                    for (j, b) in lookaheads.iter().enumerate().skip(i + 1) {
+                        if let Some(seq) = a.intersection(b).next() {
+                            let message = format!(
+                                "alternatives {} and {} of `{}` can both begin with `{}`, so {} \
+                                 of lookahead cannot decide between them",
+                                i + 1,
+                                j + 1,
+                                self.rule,
+                                describe(seq),
+                                self.tokens(),
+                            );
+                            self.errors.push(syn::Error::new(*span, message));
+                            break 'check;
+                        }
+                    }
+                }
+                let branches: Vec<_> = alts
+                    .iter()
+                    .zip(&lookaheads)
+                    .map(|(alt, lookahead)| {
+                        let condition = self.lookahead(lookahead);
+                        let code = self.expr(alt, follow);
+                        quote!(if #condition { #code })
+                    })
+                    .collect();
+                let expected = self.expected(lookaheads.iter().flatten());
+                quote! {
+                    p.soft_peek()?;
+                    #(#branches else)* {
+                        #(p.lexer().#expected;)*
+                        return Err(p.expected_one_of());
+                    }
+                }
+            }
+            Expr::Opt(body, span) => {
+                let enter = analysis.concat(&analysis.first_of(body), follow);
+                self.check_repetition(body, &enter, follow, *span, "`?`");
+                let condition = self.lookahead(&enter);
+                let code = self.expr(body, follow);
+                quote! {
+                    p.soft_peek()?;
+                    if #condition { #code }
+                }
+            }
+            Expr::Star(body, span) | Expr::Plus(body, span) => {
+                let first = analysis.first_of(body);
+                let after = analysis.concat(&analysis.repeat(&first), follow);
+                let enter = analysis.concat(&first, &after);
+                let op = match expr {
+                    Expr::Star(..) => "`*`",
+                    _ => "`+`",
+                };
+                self.check_repetition(body, &enter, follow, *span, op);
+                let condition = self.lookahead(&enter);
+                let code = self.expr(body, &after);
+                match expr {
+                    Expr::Star(..) => quote! {
+                        loop {
+                            p.soft_peek()?;
+                            if !(#condition) { break; }
+                            #code
+                        }
+                    },
+                    _ => quote! {
+                        loop {
+                            #code
+                            p.soft_peek()?;
+                            if !(#condition) { break; }
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    /// Checks that a `?`, `*` or `+` can decide whether to parse its body again.
+    fn check_repetition(
+        &mut self,
+        body: &Expr,
+        enter: &Lookahead,
+        follow: &Lookahead,
+        span: Span,
+        op: &str,
+    ) {
+        let message = if self.analysis.nullable(body) {
+            format!("the body of this {op} in `{}` can match nothing", self.rule)
+        } else if let Some(seq) = enter.intersection(follow).next() {
+            format!(
+                "`{}` can continue with `{}` both inside and after this {op}, so {} of lookahead \
+                 cannot decide whether to parse it",
+                self.rule,
+                describe(seq),
+                self.tokens(),
+            )
+        } else {
+            return;
+        };
+        self.errors.push(syn::Error::new(span, message));
+    }
+
+    /// Generates a condition that is true if the upcoming tokens match one of the sequences.
+    fn lookahead(&self, lookahead: &Lookahead) -> TokenStream {
+        if lookahead.is_empty() {
+            return quote!(false);
+        }
+        let sequences = lookahead.iter().map(|seq| {
+            if seq.is_empty() {
+                return quote!(true);
+            }
+            let terms = seq.iter().enumerate().map(|(n, term)| match term {
+                Term::Token(key) => {
+                    let pat = &self.terminals[key].lookahead;
+                    quote! {
+                        matches!(
+                            p.peek_nth(#n),
+                            Ok(Some(tok)) if matches!(::chompy::lex::Token::kind_ref(tok), #pat)
+                        )
+                    }
+                }
+                Term::End => quote!(matches!(p.peek_nth(#n), Ok(None))),
+            });
+            quote!((#(#terms)&&*))
+        });
+        quote!(#(#sequences)||*)
+    }
+
+    /// Returns the calls recording the tokens the provided sequences begin with as expected.
+    fn expected<'s>(&self, sequences: impl Iterator<Item = &'s Vec<Term>>) -> Vec<&TokenStream> {
+        let mut keys: Vec<&String> = vec![];
+        for seq in sequences {
+            if let Some(Term::Token(key)) = seq.first()
+                && !keys.contains(&key)
+            {
+                keys.push(key);
+            }
+        }
+        keys.into_iter()
+            .map(|key| &self.terminals[key].expect)
+            .collect()
+    }
+
+    fn tokens(&self) -> String {
+        match self.analysis.k {
+            1 => "1 token".into(),
+            k => format!("{k} tokens"),
+        }
+    }
+}
+
+/// Returns the lookahead sequences as arrays of token names.
+fn sequences(lookahead: &Lookahead) -> Vec<TokenStream> {
+    lookahead
+        .iter()
+        .map(|seq| {
+            let terms = seq.iter().map(|term| match term {
+                Term::Token(key) => key.as_str(),
+                Term::End => "<end>",
+            });
+            quote!(&[#(#terms),*])
+        })
+        .collect()
+}
+
+/// Returns the named lifetimes used within a type, such as the `'s` of `Parser<'s>`.
+fn lifetimes(tokens: TokenStream) -> Vec<Lifetime> {
+    let mut lifetimes: Vec<Lifetime> = vec![];
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                if let Some(TokenTree::Ident(ident)) = tokens.peek() {
+                    let lifetime = Lifetime {
+                        apostrophe: punct.span(),
+                        ident: ident.clone(),
+                    };
+                    if lifetime.ident != "static"
+                        && lifetime.ident != "_"
+                        && !lifetimes.contains(&lifetime)
+                    {
+                        lifetimes.push(lifetime);
+                    }
+                }
+            }
+            TokenTree::Group(group) => {
+                for lifetime in self::lifetimes(group.stream()) {
+                    if !lifetimes.contains(&lifetime) {
+                        lifetimes.push(lifetime);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    lifetimes
+}
+
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_uppercase();
+            first.chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+fn combine(errors: Vec<syn::Error>) -> syn::Error {
+    let mut errors = errors.into_iter();
+    let mut error = errors.next().unwrap();
+    for next in errors {
+        error.combine(next);
+    }
+    error
+}
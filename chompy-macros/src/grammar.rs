@@ -0,0 +1,291 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{ToTokens, quote};
+use syn::{
+    Attribute, Ident, LitInt, Pat, Token, Type, Visibility, parenthesized,
+    parse::{Parse, ParseStream},
+    spanned::Spanned,
+};
+
+/// A grammar written in the `grammar!` DSL.
+pub(crate) struct Grammar {
+    pub(crate) attrs: Vec<Attribute>,
+    pub(crate) vis: Visibility,
+    pub(crate) name: Ident,
+    pub(crate) parser: Type,
+    pub(crate) lookahead: usize,
+    pub(crate) rules: Vec<Rule>,
+}
+
+/// A single `name = expr;` rule.
+pub(crate) struct Rule {
+    pub(crate) attrs: Vec<Attribute>,
+    pub(crate) public: bool,
+    pub(crate) name: Ident,
+    pub(crate) body: Expr,
+}
+
+/// The right hand side of a rule.
+pub(crate) enum Expr {
+    /// A token, written as a pattern over the parser's token kind.
+    Token(Box<Terminal>),
+    /// A reference to another rule.
+    Rule(Ident),
+    /// Each expression in order. An empty sequence matches nothing.
+    Seq(Vec<Expr>),
+    /// One of the expressions.
+    Alt(Vec<Expr>, Span),
+    /// `expr?`
+    Opt(Box<Expr>, Span),
+    /// `expr*`
+    Star(Box<Expr>, Span),
+    /// `expr+`
+    Plus(Box<Expr>, Span),
+}
+
+/// A token pattern such as `Plus` or `Ident(_)`.
+pub(crate) struct Terminal {
+    /// The name of the pattern's variant, which identifies the token during analysis. Patterns of
+    /// the same variant are treated as the same token no matter their payload.
+    pub(crate) key: String,
+    /// The pattern as written, which the token must match to be consumed.
+    pub(crate) pat: Pat,
+    /// A pattern matching any token of the same variant, used for lookahead.
+    pub(crate) lookahead: TokenStream,
+    /// The call on the parser's buffer recording that the token was expected, which names it in
+    /// diagnostics.
+    pub(crate) expect: TokenStream,
+}
+
+impl Parse for Grammar {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attrs = input.call(Attribute::parse_outer)?;
+        let mut lookahead = 1;
+        let mut error = None;
+        attrs.retain(|attr| {
+            if !attr.path().is_ident("lookahead") {
+                return true;
+            }
+            match attr
+                .parse_args::<LitInt>()
+                .and_then(|lit| lit.base10_parse())
+            {
+                Ok(0) => error = Some(syn::Error::new(attr.span(), "lookahead must be at least 1")),
+                Ok(k) => lookahead = k,
+                Err(err) => error = Some(err),
+            }
+            false
+        });
+        if let Some(error) = error {
+            return Err(error);
+        }
+        let vis = input.parse()?;
+        input.parse::<Token![mod]>()?;
+        let name = input.parse()?;
+        input.parse::<Token![for]>()?;
+        let parser = input.parse()?;
+        let body;
+        syn::braced!(body in input);
+        let mut rules = vec![];
+        while !body.is_empty() {
+            rules.push(body.parse()?);
+        }
+        Ok(Self {
+            attrs,
+            vis,
+            name,
+            parser,
+            lookahead,
+            rules,
+        })
+    }
+}
+
+impl Parse for Rule {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let public = input.parse::<Option<Token![pub]>>()?.is_some();
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let body = parse_alt(input)?;
+        input.parse::<Token![;]>()?;
+        Ok(Self {
+            attrs,
+            public,
+            name,
+            body,
+        })
+    }
+}
+
+/// `seq ('|' seq)*`
+fn parse_alt(input: ParseStream) -> syn::Result<Expr> {
+    let span = input.span();
+    let mut alts = vec![parse_seq(input)?];
+    while input.parse::<Option<Token![|]>>()?.is_some() {
+        alts.push(parse_seq(input)?);
+    }
+    Ok(match alts.len() {
+        1 => alts.pop().unwrap(),
+        _ => Expr::Alt(alts, span),
+    })
+}
+
+/// `postfix*`, ending at a `|`, a `;`, or the end of a group.
+fn parse_seq(input: ParseStream) -> syn::Result<Expr> {
+    let mut items = vec![];
+    while !(input.is_empty() || input.peek(Token![|]) || input.peek(Token![;])) {
+        items.push(parse_postfix(input)?);
+    }
+    Ok(match items.len() {
+        1 => items.pop().unwrap(),
+        _ => Expr::Seq(items),
+    })
+}
+
+/// `atom ('?' | '*' | '+')*`
+fn parse_postfix(input: ParseStream) -> syn::Result<Expr> {
+    let mut expr = parse_atom(input)?;
+    loop {
+        let span = input.span();
+        expr = if input.parse::<Option<Token![?]>>()?.is_some() {
+            Expr::Opt(Box::new(expr), span)
+        } else if input.parse::<Option<Token![*]>>()?.is_some() {
+            Expr::Star(Box::new(expr), span)
+        } else if input.parse::<Option<Token![+]>>()?.is_some() {
+            Expr::Plus(Box::new(expr), span)
+        } else {
+            return Ok(expr);
+        };
+    }
+}
+
+/// `'(' alt ')' | rule | token`
+fn parse_atom(input: ParseStream) -> syn::Result<Expr> {
+    if input.peek(syn::token::Paren) {
+        let content;
+        parenthesized!(content in input);
+        return parse_alt(&content);
+    }
+    if input.peek(Ident) {
+        let fork = input.fork();
+        let ident: Ident = fork.parse()?;
+        let lowercase = ident.to_string().starts_with(|c: char| c.is_lowercase());
+        if lowercase && !fork.peek(Token![::]) {
+            input.parse::<Ident>()?;
+            return Ok(Expr::Rule(ident));
+        }
+    }
+    let pat = Pat::parse_single(input)?;
+    Terminal::new(pat).map(|token| Expr::Token(Box::new(token)))
+}
+
+impl Terminal {
+    fn new(pat: Pat) -> syn::Result<Self> {
+        let (key, lookahead) = match &pat {
+            Pat::Ident(ident) if ident.by_ref.is_none() && ident.subpat.is_none() => {
+                (ident.ident.to_string(), pat.to_token_stream())
+            }
+            Pat::Path(path) => (path_key(&path.path), pat.to_token_stream()),
+            Pat::TupleStruct(pat) => {
+                let path = &pat.path;
+                (path_key(path), quote!(#path(..)))
+            }
+            Pat::Struct(pat) => {
+                let path = &pat.path;
+                (path_key(path), quote!(#path { .. }))
+            }
+            _ => {
+                return Err(syn::Error::new(
+                    pat.span(),
+                    "expected a rule, a group, or a token kind variant",
+                ));
+            }
+        };
+        // Unit variants can be displayed like any other kind, but the payload of the others isn't
+        // known, so they are named by their variant.
+        let expect = match &pat {
+            Pat::Ident(_) | Pat::Path(_) => quote!(expect(#pat)),
+            _ => quote!(expect_named(#key)),
+        };
+        Ok(Self {
+            key,
+            pat,
+            lookahead,
+            expect,
+        })
+    }
+}
+
+/// A grammar has a single kind of token, so its variants are identified by their last segment.
+fn path_key(path: &syn::Path) -> String {
+    path.segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default()
+}
+
+impl Expr {
+    /// Calls `f` on each rule this expression refers to.
+    pub(crate) fn rules(&self, f: &mut impl FnMut(&Ident)) {
+        match self {
+            Expr::Token(_) => {}
+            Expr::Rule(rule) => f(rule),
+            Expr::Seq(items) | Expr::Alt(items, _) => items.iter().for_each(|item| item.rules(f)),
+            Expr::Opt(expr, _) | Expr::Star(expr, _) | Expr::Plus(expr, _) => expr.rules(f),
+        }
+    }
+
+    /// Calls `f` on each token this expression refers to.
+    pub(crate) fn tokens<'a>(&'a self, f: &mut impl FnMut(&'a Terminal)) {
+        match self {
+            Expr::Token(token) => f(token),
+            Expr::Rule(_) => {}
+            Expr::Seq(items) | Expr::Alt(items, _) => items.iter().for_each(|item| item.tokens(f)),
+            Expr::Opt(expr, _) | Expr::Star(expr, _) | Expr::Plus(expr, _) => expr.tokens(f),
+        }
+    }
+}
+
+/// Writes an expression back out in the DSL's syntax, for documentation.
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Token(token) => {
+                let pat = token.pat.to_token_stream().to_string();
+                f.write_str(&pat.replace(" (", "(").replace("( ", "(").replace(" )", ")"))
+            }
+            Expr::Rule(rule) => write!(f, "{rule}"),
+            Expr::Seq(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(" ")?;
+                    }
+                    match item {
+                        Expr::Alt(..) => write!(f, "({item})")?,
+                        _ => write!(f, "{item}")?,
+                    }
+                }
+                Ok(())
+            }
+            Expr::Alt(alts, _) => {
+                for (index, alt) in alts.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    write!(f, "{alt}")?;
+                }
+                Ok(())
+            }
+            Expr::Opt(expr, _) => write_postfix(f, expr, '?'),
+            Expr::Star(expr, _) => write_postfix(f, expr, '*'),
+            Expr::Plus(expr, _) => write_postfix(f, expr, '+'),
+        }
+    }
+}
+
+fn write_postfix(f: &mut std::fmt::Formatter<'_>, expr: &Expr, op: char) -> std::fmt::Result {
+    match expr {
+        Expr::Seq(_) | Expr::Alt(..) => write!(f, "({expr}){op}"),
+        _ => write!(f, "{expr}{op}"),
+    }
+}
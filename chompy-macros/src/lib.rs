@@ -0,0 +1,96 @@
+//! Procedural macros for chompy. These are re-exported by chompy itself, which is where they
+//! should be used from.
+
+use proc_macro::TokenStream;
+
+mod analysis;
+mod generate;
+mod grammar;
+mod visit;
+
+#[cfg(test)]
+mod tests;
+
+/// The grammar is written as a module, naming the type implementing [chompy's `Parse`] that its
+/// rules run on, followed by its rules, as in `mod calc for Parser<'s, Rule> { .. }`.
+///
+/// Each rule is `name = expression;`, where an expression is built from:
+///
+/// - `rule`: another rule, which must be named in lowercase.
+/// - `Kind`, `Kind(..)`: a token whose kind matches the pattern, such as `Plus` or `Ident(_)`.
+/// - `a b`: `a` followed by `b`.
+/// - `a | b`: either `a` or `b`.
+/// - `a?`, `a*`, `a+`: `a` at most once, any amount of times, or at least once.
+/// - `(a)`: a group.
+///
+/// The module gets a function for each rule, which parses it and records it as a node in the
+/// concrete syntax tree (see `Parse::start_node`), along with a `Rule` enum of the kinds of those
+/// nodes, so the parser type must record nodes of kind `Rule`. Rules marked `pub` are public and
+/// can begin a parse, so they may be followed by the end of the input; every other rule must be
+/// used by one. The module imports everything from its parent, and if the parser type borrows from
+/// the source its lifetime must be named, as in `Parser<'s, Rule>`. Each rule also runs through
+/// `Parse::trace`.
+///
+/// Every choice is made by looking at the upcoming tokens, which is one token by default and can
+/// be raised with `#[lookahead(k)]` on the module. The FIRST and FOLLOW sets of each rule are
+/// computed from the grammar (and are available through `Rule::first` and `Rule::follow`), and a
+/// grammar that cannot be parsed with that much lookahead fails to compile: alternatives that
+/// begin the same way, repetitions that can't decide when to stop, and left recursive rules are
+/// all reported. Tokens are told apart by their variant, so `Int(1)` and `Int(_)` count as the
+/// same token.
+///
+/// When no choice matches, the parse fails with an `ExpectedOneOf` error listing the tokens each
+/// choice begins with. Unit variants are named by their [std::fmt::Display] implementation, and
+/// other variants by their name.
+///
+/// [chompy's `Parse`]: https://docs.rs/chompy/latest/chompy/parse/trait.Parse.html
+#[proc_macro]
+pub fn grammar(input: TokenStream) -> TokenStream {
+    generate::expand(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Every struct and enum in the module that derives [macro@Visit] is a node of the tree, and the
+/// module gets three traits with a method for each of them (the derive only marks the nodes, and
+/// can't be used outside of this attribute):
+///
+/// - `Visit<'ast>`, with `visit_<node>(&mut self, node: &'ast Node)`.
+/// - `VisitMut`, with `visit_<node>_mut(&mut self, node: &mut Node)`.
+/// - `Fold`, with `fold_<node>(&mut self, node: Node) -> Node`.
+///
+/// Each method walks into the node's children by default by calling the matching
+/// `walk_<node>`, `walk_<node>_mut` or `walk_fold_<node>` function, so a pass only overrides the
+/// nodes it cares about, and calls the walk function itself if it wants to continue into the
+/// children. Method names are the node's name in snake case, with a run of capitals kept as one
+/// word (so `HTTPExpr` is visited by `visit_http_expr`), and two nodes whose names give the same
+/// method are a compile error.
+///
+/// Fields are walked based on their type: nodes are visited, `Box`, `Vec` and `Option` are walked
+/// into, and a `Spanned<T>` visits its location (through `visit_location`) before walking into its
+/// value, as does a `Location` field. Every other field is left alone, as is any field marked
+/// `#[visit(skip)]`. Types are recognized by name, so they should not be renamed on import.
+///
+/// Nodes can be generic over lifetimes, such as an `Expr<'s>` borrowing from the source, but not
+/// over types. The traits and walk functions then take those lifetimes as well, as in
+/// `Visit<'ast, 's>`, `VisitMut<'s>` and `Fold<'s>`.
+#[proc_macro_attribute]
+pub fn visitors(args: TokenStream, item: TokenStream) -> TokenStream {
+    visit::expand(args.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Marks a type as a node of the tree within a [macro@visitors] module.
+///
+/// **This derive generates nothing itself**: the visitor traits are generated by the
+/// [macro@visitors] attribute on the module around the type, and deriving `Visit` outside of such
+/// a module is a compile error.
+///
+/// A field can be marked `#[visit(skip)]` to leave it out of the walk.
+#[proc_macro_derive(Visit, attributes(visit))]
+pub fn derive_visit(item: TokenStream) -> TokenStream {
+    visit::derive(item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
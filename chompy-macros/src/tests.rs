@@ -0,0 +1,283 @@
+use quote::quote;
+
+use crate::generate::expand;
+
+/// Expands a grammar, returning the messages of the errors it produced.
+fn errors(input: proc_macro2::TokenStream) -> Vec<String> {
+    match expand(input) {
+        Ok(_) => vec![],
+        Err(err) => err.into_iter().map(|err| err.to_string()).collect(),
+    }
+}
+
+#[test]
+fn valid() {
+    let errors = errors(quote! {
+        mod calc for Parser<'s, Rule> {
+            pub expr = term ((Plus | Minus) term)*;
+            term = factor ((Star | Slash) factor)*;
+            factor = Int(_) | LeftParen expr RightParen | Minus factor;
+        }
+    });
+    assert_eq!(Vec::<String>::new(), errors);
+}
+
+#[test]
+fn alternative_conflict() {
+    let grammar = quote! {
+        mod statements for Parser<'s, Rule> {
+            pub statement = Ident(_) Equal Ident(_) | Ident(_) SemiColon;
+        }
+    };
+    assert_eq!(
+        vec![
+            "alternatives 1 and 2 of `statement` can both begin with `Ident`, so 1 token of \
+             lookahead cannot decide between them"
+        ],
+        errors(grammar.clone())
+    );
+    assert_eq!(
+        Vec::<String>::new(),
+        errors(quote!(#[lookahead(2)] #grammar))
+    );
+}
+
+#[test]
+fn conflict_through_rules() {
+    let errors = errors(quote! {
+        #[lookahead(2)]
+        mod statements for Parser<'s, Rule> {
+            pub statement = call | path;
+            call = name LeftParen RightParen;
+            path = name (LeftParen name)*;
+            name = Ident(_) | Let;
+        }
+    });
+    assert_eq!(
+        vec![
+            "alternatives 1 and 2 of `statement` can both begin with `Ident LeftParen`, so 2 \
+             tokens of lookahead cannot decide between them"
+        ],
+        errors
+    );
+}
+
+#[test]
+fn repetition_conflict() {
+    let errors = errors(quote! {
+        mod list for Parser<'s, Rule> {
+            pub list = (Ident(_) Comma)* Ident(_) Comma?;
+            pub nullable = (Ident(_)?)*;
+        }
+    });
+    assert_eq!(
+        vec![
+            "`list` can continue with `Ident` both inside and after this `*`, so 1 token of \
+             lookahead cannot decide whether to parse it",
+            "the body of this `*` in `nullable` can match nothing",
+            "`nullable` can continue with `Ident` both inside and after this `?`, so 1 token of \
+             lookahead cannot decide whether to parse it",
+        ],
+        errors
+    );
+}
+
+#[test]
+fn left_recursion() {
+    let errors = errors(quote! {
+        mod calc for Parser<'s, Rule> {
+            pub expr = expr Plus term | term;
+            term = prefix? factor;
+            prefix = Minus;
+            factor = Int(_) | group;
+            group = term? LeftParen factor RightParen;
+        }
+    });
+    assert_eq!(
+        vec![
+            "the rule `expr` is left recursive (expr -> expr), which recursive descent cannot \
+             parse; use repetition instead, such as `a = b (Op b)*;`",
+            "the rule `term` is left recursive (term -> factor -> group -> term), which \
+             recursive descent cannot parse; use repetition instead, such as `a = b (Op b)*;`",
+        ],
+        errors
+    );
+}
+
+#[test]
+fn undefined_and_unused_rules() {
+    let errors = errors(quote! {
+        mod calc for Parser<'s, Rule> {
+            pub expr = term;
+            unused = Int(_);
+            expr = Int(_);
+        }
+    });
+    assert_eq!(
+        vec![
+            "the rule `expr` is defined more than once",
+            "there is no rule named `term`",
+        ],
+        errors
+    );
+
+    let errors = self::errors(quote! {
+        mod calc for Parser<'s, Rule> {
+            pub expr = Int(_);
+            unused = Int(_);
+        }
+    });
+    assert_eq!(
+        vec!["the rule `unused` is not used by any `pub` rule"],
+        errors
+    );
+
+    let errors = self::errors(quote! {
+        mod calc for Parser<'s, Rule> {
+            expr = Int(_);
+        }
+    });
+    assert_eq!(
+        vec!["a grammar needs at least one `pub` rule to begin parsing from"],
+        errors
+    );
+}
+
+#[test]
+fn invalid_syntax() {
+    let errors = errors(quote! {
+        #[lookahead(0)]
+        mod calc for Parser<'s, Rule> {
+            pub expr = Int(_);
+        }
+    });
+    assert_eq!(vec!["lookahead must be at least 1"], errors);
+
+    let errors = self::errors(quote! {
+        mod calc for Parser<'s, Rule> {
+            pub expr = 1;
+        }
+    });
+    assert_eq!(
+        vec!["expected a rule, a group, or a token kind variant"],
+        errors
+    );
+}
+
+/// Expands a `visitors` module, returning the messages of the errors it produced.
+fn visitor_errors(input: proc_macro2::TokenStream) -> Vec<String> {
+    match crate::visit::expand(quote!(), input) {
+        Ok(_) => vec![],
+        Err(err) => err.into_iter().map(|err| err.to_string()).collect(),
+    }
+}
+
+#[test]
+fn visitors() {
+    let module = quote! {
+        mod ast {
+            #[derive(Visit)]
+            pub enum Expr {
+                Int(i64),
+                Neg(Box<Spanned<Expr>>),
+                Call { name: String, args: Vec<Spanned<Expr>> },
+            }
+
+            pub struct NotANode(Expr);
+        }
+    };
+    assert_eq!(Vec::<String>::new(), visitor_errors(module.clone()));
+    let expanded = crate::visit::expand(quote!(), module).unwrap().to_string();
+    assert!(expanded.contains("fn visit_expr"));
+    assert!(expanded.contains("fn walk_fold_expr"));
+    assert!(!expanded.contains("not_a_node"));
+}
+
+#[test]
+fn borrowed_visitors() {
+    let module = quote! {
+        mod ast {
+            #[derive(Visit)]
+            pub enum Expr<'s> {
+                Ident(&'s str),
+                Neg(Box<Expr<'s>>),
+            }
+        }
+    };
+    let expanded = crate::visit::expand(quote!(), module).unwrap().to_string();
+    assert!(expanded.contains("pub trait Visit < 'ast , 's : 'ast >"));
+    assert!(expanded.contains("visitor . visit_expr"));
+}
+
+#[test]
+fn visitor_method_names() {
+    let module = quote! {
+        mod ast {
+            #[derive(Visit)]
+            pub struct HTTPExpr;
+            #[derive(Visit)]
+            pub struct IfElse;
+            #[derive(Visit)]
+            pub struct ParseURL;
+        }
+    };
+    let expanded = crate::visit::expand(quote!(), module).unwrap().to_string();
+    assert!(expanded.contains("fn visit_http_expr"));
+    assert!(expanded.contains("fn visit_if_else"));
+    assert!(expanded.contains("fn visit_parse_url"));
+}
+
+#[test]
+fn derive_outside_visitors() {
+    let err = crate::visit::derive(quote!(
+        pub struct Ident;
+    ))
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("within a `#[chompy::visitors]` module")
+    );
+    let marked = crate::visit::derive(quote!(
+        #[visit(node)]
+        pub struct Ident;
+    ));
+    assert!(marked.unwrap().is_empty());
+}
+
+#[test]
+fn invalid_visitors() {
+    assert_eq!(
+        vec!["types deriving `Visit` can only be generic over lifetimes"],
+        visitor_errors(quote! {
+            mod ast {
+                #[derive(Visit)]
+                pub struct Wrapper<T>(T);
+            }
+        })
+    );
+    assert_eq!(
+        vec!["no types in this module derive `Visit`"],
+        visitor_errors(quote!(
+            mod ast {
+                pub struct Ident;
+            }
+        ))
+    );
+    assert_eq!(
+        vec!["`HTTPExpr` and `HttpExpr` would both be visited by `visit_http_expr`"],
+        visitor_errors(quote! {
+            mod ast {
+                #[derive(Visit)]
+                pub struct HttpExpr;
+                #[derive(Visit)]
+                pub struct HTTPExpr;
+            }
+        })
+    );
+    assert_eq!(
+        vec!["`visitors` can only be used on a module with a body"],
+        visitor_errors(quote!(
+            mod ast;
+        ))
+    );
+}
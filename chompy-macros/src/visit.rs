@@ -0,0 +1,525 @@
+use std::collections::BTreeMap;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, DeriveInput, Fields, GenericArgument, GenericParam, Ident, Item, ItemMod, Lifetime,
+    Path, PathArguments, Token, Type, parse_quote, punctuated::Punctuated, spanned::Spanned,
+};
+
+/// A type within the module that derives `Visit`.
+struct Node {
+    name: Ident,
+    /// The node's type with its lifetime parameters, such as `Expr<'s>`.
+    ty: TokenStream,
+    /// The variants of an enum, or a single unnamed variant for a struct.
+    variants: Vec<(Option<Ident>, Fields)>,
+}
+
+/// How a field is walked, based on its type.
+enum Shape {
+    /// Another node.
+    Node(Ident),
+    /// A `Location`.
+    Location,
+    /// A `Box`, `Vec` or `Option` of something that is walked.
+    Container(Container, Box<Shape>),
+    /// A `Spanned`, whose Location is always walked.
+    Spanned(Box<Shape>),
+    /// Anything else, which is not walked.
+    Leaf,
+}
+
+#[derive(Clone, Copy)]
+enum Container {
+    Box,
+    Vec,
+    Option,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Ref,
+    Mut,
+}
+
+/// Expands the `visitors` attribute.
+pub(crate) fn expand(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    if !args.is_empty() {
+        return Err(syn::Error::new(
+            args.span(),
+            "`visitors` takes no arguments",
+        ));
+    }
+    let mut module: ItemMod = syn::parse2(item)?;
+    let Some((_, items)) = &mut module.content else {
+        return Err(syn::Error::new(
+            module.ident.span(),
+            "`visitors` can only be used on a module with a body",
+        ));
+    };
+    let mut nodes = vec![];
+    let mut lifetimes: Vec<Lifetime> = vec![];
+    for item in items.iter_mut() {
+        let (attrs, name, generics, variants) = match item {
+            Item::Struct(item) => (
+                &mut item.attrs,
+                &item.ident,
+                &item.generics,
+                vec![(None, item.fields.clone())],
+            ),
+            Item::Enum(item) => (
+                &mut item.attrs,
+                &item.ident,
+                &item.generics,
+                item.variants
+                    .iter()
+                    .map(|variant| (Some(variant.ident.clone()), variant.fields.clone()))
+                    .collect(),
+            ),
+            _ => continue,
+        };
+        if !derives_visit(attrs)? {
+            continue;
+        }
+        let mut params = vec![];
+        for param in &generics.params {
+            let GenericParam::Lifetime(param) = param else {
+                return Err(syn::Error::new(
+                    param.span(),
+                    "types deriving `Visit` can only be generic over lifetimes",
+                ));
+            };
+            params.push(param.lifetime.clone());
+            if !lifetimes.contains(&param.lifetime) {
+                lifetimes.push(param.lifetime.clone());
+            }
+        }
+        nodes.push(Node {
+            name: name.clone(),
+            ty: match params.is_empty() {
+                true => quote!(#name),
+                false => quote!(#name<#(#params),*>),
+            },
+            variants,
+        });
+        // Lets the derive know it is within this module, as it reports an error otherwise.
+        attrs.push(parse_quote!(#[visit(node)]));
+    }
+    if nodes.is_empty() {
+        return Err(syn::Error::new(
+            module.ident.span(),
+            "no types in this module derive `Visit`",
+        ));
+    }
+    let mut methods: BTreeMap<String, &Ident> = BTreeMap::new();
+    for node in &nodes {
+        let snake = snake_case(&node.name.to_string());
+        if let Some(other) = methods.insert(snake.clone(), &node.name) {
+            return Err(syn::Error::new(
+                node.name.span(),
+                format!(
+                    "`{}` and `{other}` would both be visited by `visit_{snake}`",
+                    node.name
+                ),
+            ));
+        }
+    }
+    let names: Vec<String> = nodes.iter().map(|node| node.name.to_string()).collect();
+    // The traits take every lifetime the nodes are generic over, after the `'ast` of `Visit`.
+    let visit_params = quote!(<'ast #(, #lifetimes: 'ast)*>);
+    let visit_args = quote!(<'ast #(, #lifetimes)*>);
+    let params = match lifetimes.is_empty() {
+        true => quote!(),
+        false => quote!(<#(#lifetimes),*>),
+    };
+
+    let mut visit = vec![];
+    let mut visit_mut = vec![];
+    let mut fold = vec![];
+    let mut walks = vec![];
+    for node in &nodes {
+        let name = &node.name;
+        let ty = &node.ty;
+        let snake = snake_case(&name.to_string());
+        let visit_fn = format_ident!("visit_{snake}");
+        let visit_mut_fn = format_ident!("visit_{snake}_mut");
+        let fold_fn = format_ident!("fold_{snake}");
+        let walk_fn = format_ident!("walk_{snake}");
+        let walk_mut_fn = format_ident!("walk_{snake}_mut");
+        let walk_fold_fn = format_ident!("walk_fold_{snake}");
+
+        let doc = format!(" Visits a [{name}], walking into its children by default.");
+        visit.push(quote! {
+            #[doc = #doc]
+            fn #visit_fn(&mut self, node: &'ast #ty) {
+                #walk_fn(self, node)
+            }
+        });
+        visit_mut.push(quote! {
+            #[doc = #doc]
+            fn #visit_mut_fn(&mut self, node: &mut #ty) {
+                #walk_mut_fn(self, node)
+            }
+        });
+        let doc = format!(" Folds a [{name}], folding its children by default.");
+        fold.push(quote! {
+            #[doc = #doc]
+            fn #fold_fn(&mut self, node: #ty) -> #ty {
+                #walk_fold_fn(self, node)
+            }
+        });
+
+        let arms = |mode| -> Vec<TokenStream> {
+            node.variants
+                .iter()
+                .map(|(variant, fields)| {
+                    let mut body = vec![];
+                    let pattern = pattern(name, variant, fields, |index, ty, skip| {
+                        if skip {
+                            return None;
+                        }
+                        let binding = format_ident!("__{index}");
+                        let code = walk(&shape(ty, &names), quote!(#binding), mode)?;
+                        body.push(code);
+                        Some(binding)
+                    });
+                    quote!(#pattern => { #(#body)* })
+                })
+                .collect()
+        };
+        let walk_arms = arms(Mode::Ref);
+        let walk_mut_arms = arms(Mode::Mut);
+        let fold_arms: Vec<_> = node
+            .variants
+            .iter()
+            .map(|(variant, fields)| {
+                let mut values = vec![];
+                let pattern = pattern(name, variant, fields, |index, ty, skip| {
+                    let binding = format_ident!("__{index}");
+                    let value = match skip {
+                        true => None,
+                        false => fold_value(&shape(ty, &names), quote!(#binding)),
+                    };
+                    values.push(value.unwrap_or_else(|| quote!(#binding)));
+                    Some(binding)
+                });
+                let path = match variant {
+                    Some(variant) => quote!(#name::#variant),
+                    None => quote!(#name),
+                };
+                let rebuilt = match fields {
+                    Fields::Named(fields) => {
+                        let names = fields.named.iter().map(|field| &field.ident);
+                        quote!(#path { #(#names: #values),* })
+                    }
+                    Fields::Unnamed(_) => quote!(#path(#(#values),*)),
+                    Fields::Unit => path,
+                };
+                quote!(#pattern => #rebuilt)
+            })
+            .collect();
+
+        let doc = format!(" Visits the children of a [{name}].");
+        let fold_doc = format!(" Folds the children of a [{name}].");
+        walks.push(quote! {
+            #[doc = #doc]
+            #[allow(unused_variables)]
+            pub fn #walk_fn<'ast, #(#lifetimes: 'ast,)* V: Visit #visit_args + ?Sized>(
+                visitor: &mut V,
+                node: &'ast #ty,
+            ) {
+                match node {
+                    #(#walk_arms)*
+                }
+            }
+
+            #[doc = #doc]
+            #[allow(unused_variables)]
+            pub fn #walk_mut_fn<#(#lifetimes,)* V: VisitMut #params + ?Sized>(
+                visitor: &mut V,
+                node: &mut #ty,
+            ) {
+                match node {
+                    #(#walk_mut_arms)*
+                }
+            }
+
+            #[doc = #fold_doc]
+            #[allow(unused_variables)]
+            pub fn #walk_fold_fn<#(#lifetimes,)* F: Fold #params + ?Sized>(
+                folder: &mut F,
+                node: #ty,
+            ) -> #ty {
+                match node {
+                    #(#fold_arms,)*
+                }
+            }
+        });
+    }
+
+    let generated: TokenStream = quote! {
+        /// Visits the nodes of this module's tree by reference. Each method walks into the node's
+        /// children by default, so a visitor only needs to override the nodes it cares about (and
+        /// can call the matching `walk_` function to continue into the children).
+        pub trait Visit #visit_params {
+            #(#visit)*
+
+            /// Visits the [::chompy::utils::Location] of a `Spanned` node or of a `Location` field.
+            fn visit_location(&mut self, location: &'ast ::chompy::utils::Location) {
+                let _ = location;
+            }
+        }
+
+        /// Visits the nodes of this module's tree by mutable reference. Each method walks into the
+        /// node's children by default, so a visitor only needs to override the nodes it cares about
+        /// (and can call the matching `walk_` function to continue into the children).
+        pub trait VisitMut #params {
+            #(#visit_mut)*
+
+            /// Visits the [::chompy::utils::Location] of a `Spanned` node or of a `Location` field.
+            fn visit_location_mut(&mut self, location: &mut ::chompy::utils::Location) {
+                let _ = location;
+            }
+        }
+
+        /// Rebuilds the nodes of this module's tree by value. Each method folds the node's
+        /// children by default, so a folder only needs to override the nodes it transforms (and
+        /// can call the matching `walk_fold_` function to fold the children).
+        pub trait Fold #params {
+            #(#fold)*
+
+            /// Folds the [::chompy::utils::Location] of a `Spanned` node or of a `Location` field.
+            fn fold_location(
+                &mut self,
+                location: ::chompy::utils::Location,
+            ) -> ::chompy::utils::Location {
+                location
+            }
+        }
+
+        #(#walks)*
+    };
+    let generated: syn::File = syn::parse2(generated)?;
+    items.extend(generated.items);
+    Ok(quote!(#module))
+}
+
+/// Returns if the attributes include `#[derive(Visit)]`.
+fn derives_visit(attrs: &[Attribute]) -> syn::Result<bool> {
+    for attr in attrs {
+        if !attr.path().is_ident("derive") {
+            continue;
+        }
+        let paths = attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)?;
+        if paths.iter().any(|path| {
+            path.segments
+                .last()
+                .is_some_and(|last| last.ident == "Visit")
+        }) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Builds the pattern matching a variant (or a struct), binding each field that `bind` returns a
+/// binding for. `bind` is also told whether the field is marked `#[visit(skip)]`.
+fn pattern(
+    name: &Ident,
+    variant: &Option<Ident>,
+    fields: &Fields,
+    mut bind: impl FnMut(usize, &Type, bool) -> Option<Ident>,
+) -> TokenStream {
+    let path = match variant {
+        Some(variant) => quote!(#name::#variant),
+        None => quote!(#name),
+    };
+    match fields {
+        Fields::Named(named) => {
+            let fields = named.named.iter().enumerate().filter_map(|(index, field)| {
+                let ident = &field.ident;
+                let binding = bind(index, &field.ty, marked(&field.attrs, "skip"))?;
+                Some(quote!(#ident: #binding))
+            });
+            quote!(#path { #(#fields,)* .. })
+        }
+        Fields::Unnamed(unnamed) => {
+            let fields = unnamed.unnamed.iter().enumerate().map(|(index, field)| {
+                match bind(index, &field.ty, marked(&field.attrs, "skip")) {
+                    Some(binding) => quote!(#binding),
+                    None => quote!(_),
+                }
+            });
+            quote!(#path(#(#fields),*))
+        }
+        Fields::Unit => path,
+    }
+}
+
+/// Returns if the attributes include `#[visit(flag)]`, such as `#[visit(skip)]` on a field.
+fn marked(attrs: &[Attribute], flag: &str) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("visit") && attr.parse_args::<Ident>().is_ok_and(|ident| ident == flag)
+    })
+}
+
+/// Works out how a field of the provided type is walked.
+fn shape(ty: &Type, nodes: &[String]) -> Shape {
+    let Type::Path(path) = ty else {
+        return Shape::Leaf;
+    };
+    let Some(last) = path.path.segments.last() else {
+        return Shape::Leaf;
+    };
+    let name = last.ident.to_string();
+    if let PathArguments::AngleBracketed(args) = &last.arguments {
+        let mut types = args.args.iter().filter_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        });
+        let (Some(inner), None) = (types.next(), types.next()) else {
+            // A node generic over lifetimes, such as `Expr<'s>`.
+            let lifetimes_only = args
+                .args
+                .iter()
+                .all(|arg| matches!(arg, GenericArgument::Lifetime(_)));
+            return match lifetimes_only && nodes.contains(&name) {
+                true => Shape::Node(last.ident.clone()),
+                false => Shape::Leaf,
+            };
+        };
+        let inner = Box::new(shape(inner, nodes));
+        let container = match name.as_str() {
+            "Spanned" => return Shape::Spanned(inner),
+            "Box" => Container::Box,
+            "Vec" => Container::Vec,
+            "Option" => Container::Option,
+            _ => return Shape::Leaf,
+        };
+        return match *inner {
+            Shape::Leaf => Shape::Leaf,
+            _ => Shape::Container(container, inner),
+        };
+    }
+    if !last.arguments.is_empty() {
+        Shape::Leaf
+    } else if name == "Location" {
+        Shape::Location
+    } else if nodes.contains(&name) {
+        Shape::Node(last.ident.clone())
+    } else {
+        Shape::Leaf
+    }
+}
+
+/// Generates the code visiting the value that `value` (a reference) points to, or None if there
+/// is nothing to visit.
+fn walk(shape: &Shape, value: TokenStream, mode: Mode) -> Option<TokenStream> {
+    let suffix = match mode {
+        Mode::Ref => "",
+        Mode::Mut => "_mut",
+    };
+    let reference = match mode {
+        Mode::Ref => quote!(&),
+        Mode::Mut => quote!(&mut),
+    };
+    match shape {
+        Shape::Node(node) => {
+            let method = format_ident!("visit_{}{suffix}", snake_case(&node.to_string()));
+            Some(quote!(visitor.#method(#value);))
+        }
+        Shape::Location => {
+            let method = format_ident!("visit_location{suffix}");
+            Some(quote!(visitor.#method(#value);))
+        }
+        Shape::Container(Container::Box, inner) => walk(inner, quote!(#reference **(#value)), mode),
+        Shape::Container(Container::Vec, inner) => {
+            let body = walk(inner, quote!(item), mode)?;
+            Some(quote!(for item in #value { #body }))
+        }
+        Shape::Container(Container::Option, inner) => {
+            let body = walk(inner, quote!(item), mode)?;
+            Some(quote!(if let ::std::option::Option::Some(item) = #value { #body }))
+        }
+        Shape::Spanned(inner) => {
+            let location = walk(&Shape::Location, quote!(#reference (#value).location), mode);
+            let inner = walk(inner, quote!(#reference (#value).value), mode);
+            Some(quote!(#location #inner))
+        }
+        Shape::Leaf => None,
+    }
+}
+
+/// Generates the expression folding the value `value` (owned), or None if nothing in it is folded.
+fn fold_value(shape: &Shape, value: TokenStream) -> Option<TokenStream> {
+    match shape {
+        Shape::Node(node) => {
+            let method = format_ident!("fold_{}", snake_case(&node.to_string()));
+            Some(quote!(folder.#method(#value)))
+        }
+        Shape::Location => Some(quote!(folder.fold_location(#value))),
+        Shape::Container(Container::Box, inner) => {
+            let inner = fold_value(inner, quote!(*(#value)))?;
+            Some(quote!(::std::boxed::Box::new(#inner)))
+        }
+        Shape::Container(Container::Vec, inner) => {
+            let body = fold_value(inner, quote!(item))?;
+            Some(quote! {
+                ::std::iter::IntoIterator::into_iter(#value)
+                    .map(|item| #body)
+                    .collect::<::std::vec::Vec<_>>()
+            })
+        }
+        Shape::Container(Container::Option, inner) => {
+            let body = fold_value(inner, quote!(item))?;
+            Some(quote!((#value).map(|item| #body)))
+        }
+        Shape::Spanned(inner) => {
+            let inner = fold_value(inner, quote!(spanned.value));
+            let inner = inner.unwrap_or_else(|| quote!(spanned.value));
+            Some(quote! {{
+                let spanned = #value;
+                let location = folder.fold_location(spanned.location);
+                ::chompy::utils::Spanned::new(#inner, location)
+            }})
+        }
+        Shape::Leaf => None,
+    }
+}
+
+/// Converts a type name to snake case, keeping runs of capitals together so that `HTTPExpr` becomes
+/// `http_expr`.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (index, char) in chars.iter().enumerate() {
+        if char.is_uppercase() {
+            let previous = index.checked_sub(1).map(|index| chars[index]);
+            let next = chars.get(index + 1);
+            let starts_word = previous.is_some_and(|previous| {
+                !previous.is_uppercase() || next.is_some_and(|next| next.is_lowercase())
+            });
+            if starts_word && previous != Some('_') {
+                snake.push('_');
+            }
+            snake.extend(char.to_lowercase());
+        } else {
+            snake.push(*char);
+        }
+    }
+    snake
+}
+
+/// Expands the `Visit` derive, which only checks that it is used within a `visitors` module.
+pub(crate) fn derive(item: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(item)?;
+    match marked(&input.attrs, "node") {
+        true => Ok(TokenStream::new()),
+        false => Err(syn::Error::new(
+            input.ident.span(),
+            "`Visit` can only be derived within a `#[chompy::visitors]` module, which is where \
+             the visitor traits are generated",
+        )),
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+};
+
+/// An immutable leaf of a green tree, holding the exact source text of a token.
+///
+/// Trivia, the text between tokens that the lexer skipped (such as whitespace), is stored as a
+/// GreenToken without a kind.
+#[derive(Debug, PartialEq)]
+pub struct GreenToken<K> {
+    kind: Option<K>,
+    text: Box<str>,
+}
+
+impl<K> GreenToken<K> {
+    /// Returns the kind of this token, or None if it is trivia.
+    pub fn kind(&self) -> Option<&K> {
+        self.kind.as_ref()
+    }
+
+    /// Returns if this token is trivia.
+    pub fn is_trivia(&self) -> bool {
+        self.kind.is_none()
+    }
+
+    /// Returns the source text of this token.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the length of this token's text in bytes.
+    pub fn text_len(&self) -> usize {
+        self.text.len()
+    }
+}
+
+/// An immutable interior node of a green tree. Green nodes know their length but not their
+/// position, so identical subtrees can be shared between (and within) trees.
+#[derive(Debug, PartialEq)]
+pub struct GreenNode<N, K> {
+    kind: N,
+    text_len: usize,
+    children: Vec<GreenElement<N, K>>,
+}
+
+impl<N, K> GreenNode<N, K> {
+    /// Creates a new GreenNode. Prefer a [GreenBuilder], which shares identical nodes; this is
+    /// meant for replacing parts of an existing tree.
+    pub fn new(kind: N, children: Vec<GreenElement<N, K>>) -> Self {
+        Self {
+            kind,
+            text_len: children.iter().map(GreenElement::text_len).sum(),
+            children,
+        }
+    }
+
+    /// Returns the kind of this node.
+    pub fn kind(&self) -> &N {
+        &self.kind
+    }
+
+    /// Returns the length of this node's text in bytes.
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+
+    /// Returns the children of this node.
+    pub fn children(&self) -> &[GreenElement<N, K>] {
+        &self.children
+    }
+
+    /// Returns the source text this node covers.
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.text_len);
+        self.write_text(&mut text);
+        text
+    }
+
+    fn write_text(&self, text: &mut String) {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => node.write_text(text),
+                GreenElement::Token(token) => text.push_str(token.text()),
+            }
+        }
+    }
+}
+
+/// A child of a [GreenNode].
+#[derive(Debug, PartialEq)]
+pub enum GreenElement<N, K> {
+    /// An interior node.
+    Node(Arc<GreenNode<N, K>>),
+    /// A token or trivia.
+    Token(Arc<GreenToken<K>>),
+}
+
+impl<N, K> GreenElement<N, K> {
+    /// Returns the length of this element's text in bytes.
+    pub fn text_len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.text_len(),
+            GreenElement::Token(token) => token.text_len(),
+        }
+    }
+
+    fn address(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => Arc::as_ptr(node) as *const () as usize,
+            GreenElement::Token(token) => Arc::as_ptr(token) as *const () as usize,
+        }
+    }
+}
+
+impl<N, K> Clone for GreenElement<N, K> {
+    fn clone(&self) -> Self {
+        match self {
+            GreenElement::Node(node) => GreenElement::Node(node.clone()),
+            GreenElement::Token(token) => GreenElement::Token(token.clone()),
+        }
+    }
+}
+
+/// A saved position within a [GreenBuilder], used to start a node which wraps children that have
+/// already been added.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct GreenCheckpoint(usize);
+
+/// Builds a green tree from the top down, deduplicating identical tokens and nodes so that they
+/// share a single allocation.
+///
+/// Kinds only need to be comparable with [PartialEq], so deduplication is keyed on text (for
+/// tokens) and on the identity of already-deduplicated children (for nodes).
+#[derive(Debug)]
+pub struct GreenBuilder<N, K> {
+    parents: Vec<(N, usize)>,
+    children: Vec<GreenElement<N, K>>,
+    tokens: HashMap<Box<str>, Vec<Arc<GreenToken<K>>>>,
+    nodes: HashMap<u64, Vec<Arc<GreenNode<N, K>>>>,
+}
+
+impl<N, K> Default for GreenBuilder<N, K> {
+    fn default() -> Self {
+        Self {
+            parents: vec![],
+            children: vec![],
+            tokens: HashMap::new(),
+            nodes: HashMap::new(),
+        }
+    }
+}
+
+impl<N: PartialEq, K: PartialEq> GreenBuilder<N, K> {
+    /// Creates a new, empty GreenBuilder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new node of the provided kind. Everything added until the matching
+    /// [GreenBuilder::finish_node] becomes its children.
+    pub fn start_node(&mut self, kind: N) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    /// Returns a [GreenCheckpoint] for [GreenBuilder::start_node_at].
+    pub fn checkpoint(&mut self) -> GreenCheckpoint {
+        GreenCheckpoint(self.children.len())
+    }
+
+    /// Starts a new node of the provided kind whose children begin at the provided
+    /// [GreenCheckpoint], wrapping everything added since it was created.
+    ///
+    /// ### Panics
+    /// Panics if the checkpoint is from before the start of the current node.
+    pub fn start_node_at(&mut self, checkpoint: GreenCheckpoint, kind: N) {
+        let GreenCheckpoint(index) = checkpoint;
+        assert!(
+            index <= self.children.len()
+                && self.parents.last().is_none_or(|(_, start)| *start <= index),
+            "checkpoint is no longer valid"
+        );
+        self.parents.push((kind, index));
+    }
+
+    /// Adds a token of the provided kind to the current node.
+    pub fn token(&mut self, kind: K, text: &str) {
+        let token = self.intern_token(Some(kind), text);
+        self.children.push(GreenElement::Token(token));
+    }
+
+    /// Adds trivia to the current node. Empty trivia is ignored.
+    pub fn trivia(&mut self, text: &str) {
+        if !text.is_empty() {
+            let token = self.intern_token(None, text);
+            self.children.push(GreenElement::Token(token));
+        }
+    }
+
+    /// Finishes the current node.
+    ///
+    /// ### Panics
+    /// Panics if there is no node to finish.
+    pub fn finish_node(&mut self) {
+        let (kind, start) = self.parents.pop().expect("no node to finish");
+        let children: Vec<_> = self.children.drain(start..).collect();
+        let node = self.intern_node(kind, children);
+        self.children.push(GreenElement::Node(node));
+    }
+
+    /// Returns the finished tree.
+    ///
+    /// ### Panics
+    /// Panics if a node is still unfinished, or if there is not exactly one root node.
+    pub fn finish(mut self) -> Arc<GreenNode<N, K>> {
+        assert!(self.parents.is_empty(), "a node was never finished");
+        match (self.children.pop(), self.children.is_empty()) {
+            (Some(GreenElement::Node(root)), true) => root,
+            _ => panic!("a green tree must have exactly one root node"),
+        }
+    }
+
+    fn intern_token(&mut self, kind: Option<K>, text: &str) -> Arc<GreenToken<K>> {
+        let bucket = self.tokens.entry(text.into()).or_default();
+        if let Some(token) = bucket.iter().find(|token| token.kind == kind) {
+            return token.clone();
+        }
+        let token = Arc::new(GreenToken {
+            kind,
+            text: text.into(),
+        });
+        bucket.push(token.clone());
+        token
+    }
+
+    fn intern_node(&mut self, kind: N, children: Vec<GreenElement<N, K>>) -> Arc<GreenNode<N, K>> {
+        let mut hasher = DefaultHasher::new();
+        for child in &children {
+            child.address().hash(&mut hasher);
+        }
+        let bucket = self.nodes.entry(hasher.finish()).or_default();
+        let existing = bucket.iter().find(|node| {
+            node.kind == kind
+                && node.children.len() == children.len()
+                && node
+                    .children
+                    .iter()
+                    .zip(&children)
+                    .all(|(a, b)| a.address() == b.address())
+        });
+        if let Some(node) = existing {
+            return node.clone();
+        }
+        let node = Arc::new(GreenNode {
+            kind,
+            text_len: children.iter().map(GreenElement::text_len).sum(),
+            children,
+        });
+        bucket.push(node.clone());
+        node
+    }
+}
@@ -0,0 +1,308 @@
+use std::{rc::Rc, sync::Arc};
+
+use crate::utils::{FileId, Located, Location, Span};
+
+use super::{GreenElement, GreenNode, GreenToken};
+
+/// A node in a red tree: a cursor over a [GreenNode] that knows its absolute position and its
+/// parent. Red nodes are created lazily while navigating and are cheap to clone.
+pub struct SyntaxNode<N, K>(Rc<NodeData<N, K>>);
+
+struct NodeData<N, K> {
+    green: Arc<GreenNode<N, K>>,
+    parent: Option<SyntaxNode<N, K>>,
+    index: usize,
+    offset: usize,
+    file_id: FileId,
+}
+
+/// A token (or trivia) in a red tree, along with its absolute position and its parent.
+pub struct SyntaxToken<N, K> {
+    green: Arc<GreenToken<K>>,
+    parent: SyntaxNode<N, K>,
+    index: usize,
+    offset: usize,
+}
+
+/// A child of a [SyntaxNode].
+pub enum SyntaxElement<N, K> {
+    /// An interior node.
+    Node(SyntaxNode<N, K>),
+    /// A token or trivia.
+    Token(SyntaxToken<N, K>),
+}
+
+impl<N, K> SyntaxNode<N, K> {
+    /// Creates the root of a red tree over the provided green tree, which began at the start of the
+    /// file with the provided [FileId].
+    pub fn new_root(green: Arc<GreenNode<N, K>>, file_id: FileId) -> Self {
+        Self(Rc::new(NodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+            file_id,
+        }))
+    }
+
+    /// Returns the kind of this node.
+    pub fn kind(&self) -> &N {
+        self.0.green.kind()
+    }
+
+    /// Returns the [GreenNode] this node is over.
+    pub fn green(&self) -> &Arc<GreenNode<N, K>> {
+        &self.0.green
+    }
+
+    /// Returns the parent of this node, or None if it is the root.
+    pub fn parent(&self) -> Option<&SyntaxNode<N, K>> {
+        self.0.parent.as_ref()
+    }
+
+    /// Returns the index of this node within its parent's children.
+    pub fn index(&self) -> usize {
+        self.0.index
+    }
+
+    /// Returns the source text this node covers.
+    pub fn text(&self) -> String {
+        self.0.green.text()
+    }
+
+    /// Returns the children of this node, including tokens and trivia.
+    pub fn children(&self) -> impl Iterator<Item = SyntaxElement<N, K>> + '_ {
+        let mut offset = self.0.offset;
+        self.0
+            .green
+            .children()
+            .iter()
+            .enumerate()
+            .map(move |(index, child)| {
+                let element = self.element(index, offset, child);
+                offset += child.text_len();
+                element
+            })
+    }
+
+    /// Returns the child nodes of this node.
+    pub fn child_nodes(&self) -> impl Iterator<Item = SyntaxNode<N, K>> + '_ {
+        self.children().filter_map(SyntaxElement::into_node)
+    }
+
+    /// Returns the child tokens of this node, excluding trivia.
+    pub fn child_tokens(&self) -> impl Iterator<Item = SyntaxToken<N, K>> + '_ {
+        self.children()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|token| !token.is_trivia())
+    }
+
+    /// Returns the sibling after this node, if any.
+    pub fn next_sibling(&self) -> Option<SyntaxElement<N, K>> {
+        self.parent()?.children().nth(self.index() + 1)
+    }
+
+    /// Returns the sibling before this node, if any.
+    pub fn prev_sibling(&self) -> Option<SyntaxElement<N, K>> {
+        let index = self.index().checked_sub(1)?;
+        self.parent()?.children().nth(index)
+    }
+
+    /// Returns this node and each of its ancestors, ending at the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode<N, K>> {
+        std::iter::successors(Some(self.clone()), |node| node.parent().cloned())
+    }
+
+    /// Returns this node and every node beneath it, in preorder.
+    pub fn descendants(&self) -> Vec<SyntaxNode<N, K>> {
+        let mut descendants = vec![];
+        let mut stack = vec![self.clone()];
+        while let Some(node) = stack.pop() {
+            let children: Vec<_> = node.child_nodes().collect();
+            stack.extend(children.into_iter().rev());
+            descendants.push(node);
+        }
+        descendants
+    }
+
+    /// Returns every token and trivia beneath this node, in source order.
+    pub fn tokens(&self) -> Vec<SyntaxToken<N, K>> {
+        let mut tokens = vec![];
+        for child in self.children() {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    fn element(
+        &self,
+        index: usize,
+        offset: usize,
+        green: &GreenElement<N, K>,
+    ) -> SyntaxElement<N, K> {
+        match green {
+            GreenElement::Node(node) => SyntaxElement::Node(Self(Rc::new(NodeData {
+                green: node.clone(),
+                parent: Some(self.clone()),
+                index,
+                offset,
+                file_id: self.0.file_id,
+            }))),
+            GreenElement::Token(token) => SyntaxElement::Token(SyntaxToken {
+                green: token.clone(),
+                parent: self.clone(),
+                index,
+                offset,
+            }),
+        }
+    }
+}
+
+impl<N, K> SyntaxToken<N, K> {
+    /// Returns the kind of this token, or None if it is trivia.
+    pub fn kind(&self) -> Option<&K> {
+        self.green.kind()
+    }
+
+    /// Returns if this token is trivia.
+    pub fn is_trivia(&self) -> bool {
+        self.green.is_trivia()
+    }
+
+    /// Returns the source text of this token.
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    /// Returns the [GreenToken] this token is over.
+    pub fn green(&self) -> &Arc<GreenToken<K>> {
+        &self.green
+    }
+
+    /// Returns the node containing this token.
+    pub fn parent(&self) -> &SyntaxNode<N, K> {
+        &self.parent
+    }
+
+    /// Returns the index of this token within its parent's children.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the sibling after this token, if any.
+    pub fn next_sibling(&self) -> Option<SyntaxElement<N, K>> {
+        self.parent.children().nth(self.index + 1)
+    }
+
+    /// Returns the sibling before this token, if any.
+    pub fn prev_sibling(&self) -> Option<SyntaxElement<N, K>> {
+        self.parent.children().nth(self.index.checked_sub(1)?)
+    }
+}
+
+impl<N, K> SyntaxElement<N, K> {
+    /// Returns the node, if this element is one.
+    pub fn into_node(self) -> Option<SyntaxNode<N, K>> {
+        match self {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        }
+    }
+
+    /// Returns the token, if this element is one.
+    pub fn into_token(self) -> Option<SyntaxToken<N, K>> {
+        match self {
+            SyntaxElement::Node(_) => None,
+            SyntaxElement::Token(token) => Some(token),
+        }
+    }
+}
+
+impl<N, K> Located for SyntaxNode<N, K> {
+    fn location(&self) -> Location {
+        let span = Span::new(self.0.offset, self.0.offset + self.0.green.text_len());
+        Location::new(self.0.file_id, span)
+    }
+}
+
+impl<N, K> Located for SyntaxToken<N, K> {
+    fn location(&self) -> Location {
+        let span = Span::new(self.offset, self.offset + self.green.text_len());
+        Location::new(self.parent.0.file_id, span)
+    }
+}
+
+impl<N, K> Located for SyntaxElement<N, K> {
+    fn location(&self) -> Location {
+        match self {
+            SyntaxElement::Node(node) => node.location(),
+            SyntaxElement::Token(token) => token.location(),
+        }
+    }
+}
+
+impl<N, K> Clone for SyntaxNode<N, K> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<N, K> Clone for SyntaxToken<N, K> {
+    fn clone(&self) -> Self {
+        Self {
+            green: self.green.clone(),
+            parent: self.parent.clone(),
+            index: self.index,
+            offset: self.offset,
+        }
+    }
+}
+
+impl<N, K> Clone for SyntaxElement<N, K> {
+    fn clone(&self) -> Self {
+        match self {
+            SyntaxElement::Node(node) => SyntaxElement::Node(node.clone()),
+            SyntaxElement::Token(token) => SyntaxElement::Token(token.clone()),
+        }
+    }
+}
+
+/// Red nodes are equal if they are the same node of the same tree.
+impl<N, K> PartialEq for SyntaxNode<N, K> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl<N, K> PartialEq for SyntaxToken<N, K> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.green, &other.green) && self.offset == other.offset
+    }
+}
+
+impl<N: std::fmt::Debug, K> std::fmt::Debug for SyntaxNode<N, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}@{:?}", self.kind(), self.span())
+    }
+}
+
+impl<N, K: std::fmt::Debug> std::fmt::Debug for SyntaxToken<N, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind() {
+            Some(kind) => write!(f, "{kind:?}@{:?} {:?}", self.span(), self.text()),
+            None => write!(f, "Trivia@{:?} {:?}", self.span(), self.text()),
+        }
+    }
+}
+
+impl<N: std::fmt::Debug, K: std::fmt::Debug> std::fmt::Debug for SyntaxElement<N, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyntaxElement::Node(node) => node.fmt(f),
+            SyntaxElement::Token(token) => token.fmt(f),
+        }
+    }
+}
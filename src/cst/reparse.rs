@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use crate::{
+    diagnostics::Result,
+    lex::{Relexed, Tok, TokenKind, relex},
+    utils::{Located, Span, TextEdit},
+};
+
+use super::{GreenElement, GreenNode, SyntaxNode};
+
+/// The result of [reparse].
+#[derive(Debug)]
+pub struct Reparsed<N, K> {
+    /// The root of the updated tree.
+    pub tree: SyntaxNode<N, K>,
+    /// The span of the edited source covered by the node that was parsed again. Everything outside
+    /// of it was reused from the previous tree.
+    pub changed: Span,
+}
+
+/// Updates a concrete syntax tree after the provided [TextEdit] is applied to its source by parsing
+/// only the smallest node around the edit again.
+///
+/// Starting from the innermost node whose text strictly contains the edit, `reparse_node` is given
+/// each node's kind and its edited text (a slice of `source`, the edited source), and returns the
+/// node parsed from that text as the same kind, or None if the kind cannot be parsed on its own
+/// (or the text no longer parses as one).
+/// The first node that is parsed again replaces the old one, and every other subtree is shared
+/// with the previous tree.
+///
+/// Returns None if no node could be parsed again, in which case the whole source needs to be. To
+/// update the tokens of the source along with the tree, see [relex_and_reparse].
+///
+/// ### Panics
+/// Panics if `tree` is not the root of its tree.
+pub fn reparse<'s, N, K, F>(
+    tree: &SyntaxNode<N, K>,
+    edit: &TextEdit,
+    source: &'s str,
+    reparse_node: F,
+) -> Option<Reparsed<N, K>>
+where
+    N: PartialEq + Clone,
+    F: FnMut(&N, &'s str) -> Option<Arc<GreenNode<N, K>>>,
+{
+    reparse_around(tree, edit, edit.span, source, reparse_node)
+}
+
+/// The result of [relex_and_reparse].
+#[derive(Debug)]
+pub struct Updated<N, K: TokenKind> {
+    /// The tokens of the edited source, as returned by [relex].
+    pub tokens: Relexed<K>,
+    /// The updated tree, or None if the whole source needs to be parsed again.
+    pub tree: Option<Reparsed<N, K>>,
+}
+
+/// Updates both the tokens and the concrete syntax tree of a source after the provided [TextEdit]
+/// is applied to it, by calling [relex] with `lexer_at` and then [reparse] with `reparse_node`.
+///
+/// Lexing again can change tokens beyond the edit itself (such as when two tokens merge into
+/// one), so the node parsed again is the innermost one which strictly contains every token that
+/// was lexed again, rather than just the edit.
+///
+/// ### Errors
+/// Returns the first error produced by the lexer.
+///
+/// ### Panics
+/// Panics if `tree` is not the root of its tree.
+pub fn relex_and_reparse<'s, N, K, L, F, G>(
+    tree: &SyntaxNode<N, K>,
+    tokens: &[Tok<K>],
+    edit: &TextEdit,
+    source: &'s str,
+    lexer_at: F,
+    reparse_node: G,
+) -> Result<Updated<N, K>>
+where
+    N: PartialEq + Clone,
+    K: TokenKind,
+    L: Iterator<Item = Result<Tok<K>>>,
+    F: FnOnce(usize) -> L,
+    G: FnMut(&N, &'s str) -> Option<Arc<GreenNode<N, K>>>,
+{
+    let relexed = relex(tokens, edit, lexer_at)?;
+    // The changed span is in the edited source, while the tree still has the old offsets.
+    let changed = relexed.changed;
+    let old_end = changed.end() - edit.new_span().end() + edit.span.end();
+    let around = Span::new(changed.start(), old_end);
+    Ok(Updated {
+        tree: reparse_around(tree, edit, around, source, reparse_node),
+        tokens: relexed,
+    })
+}
+
+/// Implements [reparse], parsing again the innermost node whose text strictly contains `around`
+/// (a span of the source before the edit).
+fn reparse_around<'s, N, K, F>(
+    tree: &SyntaxNode<N, K>,
+    edit: &TextEdit,
+    around: Span,
+    source: &'s str,
+    mut reparse_node: F,
+) -> Option<Reparsed<N, K>>
+where
+    N: PartialEq + Clone,
+    F: FnMut(&N, &'s str) -> Option<Arc<GreenNode<N, K>>>,
+{
+    assert!(
+        tree.parent().is_none(),
+        "only the root of a tree can be reparsed"
+    );
+    let mut path = vec![];
+    let mut node = tree.clone();
+    let contains_edit = |node: &SyntaxNode<N, K>| {
+        let span = node.span();
+        span.start() < around.start() && around.end() < span.end()
+    };
+    loop {
+        let Some(child) = node.child_nodes().find(contains_edit) else {
+            break;
+        };
+        path.push(child.clone());
+        node = child;
+    }
+
+    for node in path.iter().rev() {
+        let span = node.span();
+        let span = Span::new(span.start(), edit.map_offset(span.end()));
+        let text = &source[span.start()..span.end()];
+        let Some(mut green) = reparse_node(node.kind(), text) else {
+            continue;
+        };
+        if green.kind() != node.kind() || green.text_len() != text.len() {
+            continue;
+        }
+        let mut child = node.clone();
+        while let Some(parent) = child.parent() {
+            let mut children = parent.green().children().to_vec();
+            children[child.index()] = GreenElement::Node(green);
+            green = Arc::new(GreenNode::new(parent.kind().clone(), children));
+            child = parent.clone();
+        }
+        return Some(Reparsed {
+            tree: SyntaxNode::new_root(green, tree.file_id()),
+            changed: span,
+        });
+    }
+    None
+}
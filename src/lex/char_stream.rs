@@ -1,6 +1,10 @@
 use peekmore::{PeekMore, PeekMoreIterator};
 use std::{ops::RangeBounds, str::Chars};
 
+use crate::utils::LineColumn;
+
+use super::scan::Scan;
+
 /// A wrapper of the needed iterators for [Lex]'s char stream.
 #[derive(Debug, Clone)]
 pub struct CharStream<'s> {
@@ -8,6 +12,7 @@ pub struct CharStream<'s> {
     iter: PeekMoreIterator<Chars<'s>>,
     peek_cursor: usize,
     true_cursor: usize,
+    line_column: Option<LineColumn>,
 }
 impl<'s> CharStream<'s> {
     /// Creates a new CharStream out of the provided source material.
@@ -17,14 +22,25 @@ impl<'s> CharStream<'s> {
             iter: source.chars().peekmore(),
             peek_cursor: 0,
             true_cursor: 0,
+            line_column: None,
+        }
+    }
+
+    /// Creates a new CharStream that also tracks the line and column (in chars) of the true cursor
+    /// as it chomps. See [CharStream::line_column].
+    pub fn with_line_tracking(source: &'s str) -> Self {
+        Self {
+            line_column: Some(LineColumn::default()),
+            ..Self::new(source)
         }
     }
 
     /// Returns the next char in the stream and advances forward.
     pub fn chomp(&mut self) -> Option<char> {
         let next = self.iter.next()?;
-        self.true_cursor += 1;
+        self.true_cursor += next.len_utf8();
         self.peek_cursor = self.true_cursor;
+        self.track(next);
         Some(next)
     }
 
@@ -95,9 +111,24 @@ impl<'s> CharStream<'s> {
         let slice = self.inspect_peeks();
         self.iter.truncate_iterator_to_cursor();
         self.true_cursor = self.peek_cursor;
+        if self.line_column.is_some() {
+            slice.chars().for_each(|chr| self.track(chr));
+        }
         slice
     }
 
+    /// Chomps the match of the provided [super::TokenRegex] if it matches at the true cursor,
+    /// returning a slice of the chomped chars. Any peeks are discarded.
+    #[cfg(feature = "regex")]
+    pub fn chomp_regex(&mut self, regex: &super::TokenRegex) -> Option<&'s str> {
+        self.reset_peeks();
+        let end = regex.match_at(self.source, self.true_cursor)?;
+        while self.peek_cursor < end {
+            self.advance();
+        }
+        Some(self.chomp_peeks())
+    }
+
     /// Resets the peek cursor after returning a slice of everything between the true cursor and
     /// peek cursor.
     pub fn empty_peeks(&mut self) -> &'s str {
@@ -118,17 +149,23 @@ impl<'s> CharStream<'s> {
         self.peek_cursor = self.true_cursor;
     }
 
-    /// Advances the peek cursor forward by one.
+    /// Advances the peek cursor forward by one char.
     pub fn advance(&mut self) {
-        self.peek_cursor += 1;
+        self.peek_cursor += self.iter.peek().map_or(1, |c| c.len_utf8());
         self.iter.advance_cursor();
     }
 
-    /// Returns the position of the true cursor.
+    /// Returns the position of the true cursor, as a byte offset into the source.
     pub fn position(&self) -> usize {
         self.true_cursor
     }
 
+    /// Returns the line and column (in chars) of the true cursor, if this stream was created with
+    /// [CharStream::with_line_tracking].
+    pub fn line_column(&self) -> Option<LineColumn> {
+        self.line_column
+    }
+
     /// Returns the position of the peek cursor.
     pub fn peek_position(&self) -> usize {
         self.peek_cursor
@@ -151,6 +188,52 @@ impl<'s> CharStream<'s> {
     pub fn source(&self) -> &'s str {
         self.source
     }
+
+    /// Moves the tracked line and column past the provided char.
+    fn track(&mut self, chr: char) {
+        if let Some(line_column) = &mut self.line_column {
+            if chr == '\n' {
+                line_column.line += 1;
+                line_column.column = 0;
+            } else {
+                line_column.column += 1;
+            }
+        }
+    }
+}
+
+impl Scan for CharStream<'_> {
+    fn peek(&mut self) -> Option<char> {
+        CharStream::peek(self)
+    }
+
+    fn advance(&mut self) {
+        CharStream::advance(self)
+    }
+
+    fn chomp(&mut self) -> Option<char> {
+        CharStream::chomp(self)
+    }
+
+    fn commit(&mut self) {
+        self.chomp_peeks();
+    }
+
+    fn reset_peeks(&mut self) {
+        CharStream::reset_peeks(self)
+    }
+
+    fn position(&self) -> usize {
+        CharStream::position(self)
+    }
+
+    fn peek_position(&self) -> usize {
+        CharStream::peek_position(self)
+    }
+
+    fn text(&self, range: std::ops::Range<usize>) -> &str {
+        self.slice(range)
+    }
 }
 
 /// Utility for keeping track of the current position of a [Lexer].
@@ -47,3 +47,18 @@ define_error!(
         }
     }
 );
+
+/// Lexing error for a source which could not be read (see [super::ReadStream]).
+pub struct ReadFailed(pub String, pub Location);
+
+define_error!(
+    ReadFailed {
+        fn build(&self, builder: Builder) -> Builder {
+            builder.label(self.1.primary(format!("failed to read the source here: {}", self.0)))
+        }
+
+        fn location(&self) -> Location {
+            self.1
+        }
+    }
+);
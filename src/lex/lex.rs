@@ -1,12 +1,9 @@
 use crate::{
-    diagnostics::Result,
-    utils::{FileId, Location, Span},
+    diagnostics::{DiagBox, Result},
+    utils::{FileId, Interner, Symbol},
 };
 
-use super::{
-    CharStream, Token, TokenKind,
-    errors::{InvalidHex, UnterminatedString},
-};
+use super::{CharStream, Token, TokenKind, scan};
 
 /// A set of utilities to construct a lexer. After providing the neccesary references, the bulk of
 /// your lexer is written within the `lex` function.
@@ -23,6 +20,13 @@ pub trait Lex<'s, T: Token<K>, K: TokenKind> {
     /// The primary function for lexxing the next token in the stream.
     fn lex(&mut self) -> Result<Option<T>>;
 
+    /// Removes and returns the diagnostics this lexer collected rather than returning, such as
+    /// warnings about the input. These are collected by [crate::parse::Parse::run]. Lexers which
+    /// only return errors from [Lex::lex] don't need to implement this.
+    fn take_diagnostics(&mut self) -> Vec<DiagBox> {
+        vec![]
+    }
+
     // todo: this should all really be done via deref
 
     /// Returns the next char in the stream without advancing the lexer.
@@ -46,20 +50,14 @@ pub trait Lex<'s, T: Token<K>, K: TokenKind> {
     /// Chomps every char that is either alphanumeric or an underscore, returning the resulting
     /// slice. If the first char found is not alphanumeric, None is returned.
     fn construct_ident(&mut self) -> Option<&'s str> {
-        let test = |c: char| -> bool { c.is_alphanumeric() || c == '_' };
-        self.char_stream()
-            .match_peek_with(test)
-            .then(|| self.construct(test))
+        let stream = self.char_stream();
+        scan::ident(stream).map(|range| stream.slice(range))
     }
 
     /// Chomps every char which is a digit. Optionally allows underscores to appear within the
     /// number (though they cannot be the first character).
     fn construct_integer(&mut self, allow_underscore: bool) -> Option<i64> {
-        let test = |c: char| -> bool { c.is_ascii_digit() || c == '_' && allow_underscore };
-        self.peek()
-            .is_some_and(|c| c.is_ascii_digit())
-            .then(|| self.construct(test))
-            .and_then(|v| v.replace('_', "").parse::<i64>().ok())
+        scan::integer(self.char_stream(), allow_underscore)
     }
 
     /// Attempts to construct an integer twice with a period inbetween the two. You can optionally
@@ -74,74 +72,11 @@ pub trait Lex<'s, T: Token<K>, K: TokenKind> {
         allow_underscore: bool,
         allow_scientific_notation: bool,
     ) -> Option<f64> {
-        let stream = self.char_stream();
-        if !stream.match_peek_with(|c| c.is_ascii_digit()) {
-            return None;
-        }
-
-        let mut found_dot = false;
-        let mut found_trailing_digit = false;
-        let mantissa_slice = loop {
-            match stream.peek() {
-                Some('_') if allow_underscore => {}
-                Some('.') if !found_dot => {
-                    found_dot = true;
-                }
-                Some(chr) if chr.is_ascii_digit() => {
-                    if found_dot {
-                        found_trailing_digit = true;
-                    }
-                }
-                _ if found_dot && found_trailing_digit => {
-                    break stream.chomp_peeks();
-                }
-                _ => {
-                    stream.reset_peeks();
-                    return None;
-                }
-            }
-            stream.advance();
-        };
-
-        let mantissa = mantissa_slice.replace('_', "").parse::<f64>().ok()?;
-
-        if allow_scientific_notation && stream.peek() == Some('e') {
-            stream.advance();
-
-            let negative = match stream.peek() {
-                Some('+') => {
-                    stream.advance();
-                    false
-                }
-                Some('-') => {
-                    stream.advance();
-                    true
-                }
-                _ => false,
-            };
-
-            let mut found_exp_digit = false;
-            while stream.match_peek_with(|c| c.is_ascii_digit()) {
-                found_exp_digit = true;
-                stream.advance();
-            }
-
-            if found_exp_digit {
-                // chomp_peeks yields "e[+|-]?<digits>"; rely on f64's parser to handle the whole
-                // thing in one shot
-                let suffix = stream.chomp_peeks();
-                if let Ok(exp) = suffix.trim_start_matches(['e', '+', '-']).parse::<i32>() {
-                    let signed = if negative { -exp } else { exp };
-                    return Some(mantissa * 10f64.powi(signed));
-                }
-            }
-
-            // Saw `e` but no valid exponent followed — discard the trial peeks
-            // and fall through with just the mantissa
-            stream.reset_peeks();
-        }
-
-        Some(mantissa)
+        scan::float(
+            self.char_stream(),
+            allow_underscore,
+            allow_scientific_notation,
+        )
     }
 
     /// Chomps chars to create a string literal. You can provide the char's you allow to open/close
@@ -170,30 +105,24 @@ pub trait Lex<'s, T: Token<K>, K: TokenKind> {
     ) -> Option<Result<&'s str>> {
         let file_id = self.file_id();
         let stream = self.char_stream();
-        let start = stream.position();
-        let opening_delim = stream.peek_move()?;
-        if !quote_chars.contains(&opening_delim) {
-            stream.reset_peeks();
-            None
-        } else {
-            let mut in_escape = false;
-            loop {
-                match stream.peek_move() {
-                    None => {
-                        let location =
-                            Location::new(file_id, Span::new(start, stream.peek_position()));
-                        break Some(Err(UnterminatedString(location).into()));
-                    }
-                    Some(chr) if chr == opening_delim && !in_escape => {
-                        let slice = stream.slice((start + 1)..stream.peek_position() - 1);
-                        stream.chomp_peeks();
-                        break Some(Ok(slice));
-                    }
-                    Some(chr) if escape_chars.contains(&chr) && !in_escape => in_escape = true,
-                    _ => in_escape = false,
-                }
-            }
-        }
+        scan::string(stream, file_id, quote_chars, escape_chars)
+            .map(|range| range.map(|range| stream.slice(range)))
+    }
+
+    /// Like [Lex::construct_ident], but interns the identifier, returning its [Symbol].
+    fn construct_ident_symbol(&mut self, interner: &Interner) -> Option<Symbol> {
+        self.construct_ident().map(|ident| interner.intern(ident))
+    }
+
+    /// Like [Lex::construct_string], but interns the string, returning its [Symbol].
+    fn construct_string_symbol(
+        &mut self,
+        interner: &Interner,
+        quote_chars: &[char],
+        escape_chars: &[char],
+    ) -> Option<Result<Symbol>> {
+        self.construct_string(quote_chars, escape_chars)
+            .map(|string| string.map(|string| interner.intern(string)))
     }
 
     /// Chomps every char that is a digit or A through F (case agnostic). You can provide a prefix
@@ -204,87 +133,45 @@ pub trait Lex<'s, T: Token<K>, K: TokenKind> {
     fn construct_hex(&mut self, prefix: &str) -> Option<Result<&'s str>> {
         let file_id = self.file_id();
         let stream = self.char_stream();
-        let start = stream.position();
-        if !prefix.chars().all(|p| stream.match_peek(p)) {
-            stream.reset_peeks();
-            None
-        } else {
-            while stream.match_peek_with(|c| c.is_ascii_hexdigit()) {}
-            let slice = stream.slice((start + 2)..stream.peek_position());
-            stream.chomp_peeks();
-            if slice.is_empty() {
-                let location = Location::new(file_id, Span::new(start, stream.peek_position()));
-                Some(Err(InvalidHex(location).into()))
-            } else {
-                Some(Ok(slice))
-            }
-        }
+        scan::hex(stream, file_id, prefix).map(|range| range.map(|range| stream.slice(range)))
     }
 
     /// Chomps every char that follows the prefix. This will continue across multiple lines, meaning
     /// if you call this with two lines of comments ahead of you, both will be returned in this
     /// one call.
     fn construct_comment(&mut self, prefixes: &[&str]) -> Option<&'s str> {
-        let start = self.char_stream().position();
-        let mut found_any = false;
-        loop {
-            if !prefixes
-                .iter()
-                .any(|prefix| prefix.chars().all(|p| self.char_stream().match_peek(p)))
-            {
-                self.char_stream().reset_peeks();
-                break;
-            } else {
-                found_any = true;
-                self.chomp_line();
-            }
-        }
-        if found_any {
-            let stream = self.char_stream();
-            let forward_position = std::cmp::min(stream.source().len(), stream.position());
-            Some(stream.slice(start..forward_position))
-        } else {
-            None
-        }
+        let stream = self.char_stream();
+        scan::comment(stream, prefixes).map(|range| stream.slice(range))
     }
 
     /// Chomps every char until a newline is reached, returning the resulting slice.
     fn chomp_line(&mut self) -> &'s str {
-        let start = self.char_stream().position();
-        loop {
-            match self.chomp() {
-                Some('\n' | '\r') => {
-                    break;
-                }
-                Some(_) => {}
-                None => break,
-            }
-        }
         let stream = self.char_stream();
-        let forward_position = std::cmp::min(stream.source().len(), stream.position());
-        stream.slice(start..forward_position)
+        let range = scan::line(stream);
+        stream.slice(range)
     }
 
     /// Chomps the next chars if they continously fulfill the pattern str provided.
     fn chomp_pattern(&mut self, pattern: &str) -> bool {
-        let stream = self.char_stream();
-        if pattern.chars().all(|v| stream.match_peek(v)) {
-            stream.chomp_peeks();
-            true
-        } else {
-            stream.reset_peeks();
-            false
-        }
+        scan::pattern(self.char_stream(), pattern)
+    }
+
+    /// Chomps the chars matched by the provided [super::TokenRegex] at the current position,
+    /// returning the resulting slice. If the regex does not match at the current position, None is
+    /// returned. Requires the `regex` feature.
+    #[cfg(feature = "regex")]
+    fn construct_regex(&mut self, regex: &super::TokenRegex) -> Option<&'s str> {
+        self.char_stream().chomp_regex(regex)
     }
 
     /// Chomps every char that fulfills the provided closure, then returns a slice of the chars
     /// from the provided start position to the final position reached.
-    fn construct<F>(&mut self, mut f: F) -> &'s str
+    fn construct<F>(&mut self, f: F) -> &'s str
     where
         F: FnMut(char) -> bool,
     {
         let stream = self.char_stream();
-        while stream.match_peek_with(&mut f) {}
-        stream.chomp_peeks()
+        let range = scan::construct(stream, f);
+        stream.slice(range)
     }
 }
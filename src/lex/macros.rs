@@ -0,0 +1,353 @@
+/// Generates a complete lexer from an annotated [super::TokenKind] enum.
+///
+/// The macro takes the declaration of the lexer struct followed by the token kind enum. Every
+/// variant of the enum is annotated with the rule that produces it:
+///
+/// - `#[literal("==")]` on a unit variant matches the exact text. When multiple literals match, the
+///   longest one wins.
+/// - `#[keyword("let")]` on a unit variant matches an identifier with the exact text. Keywords are
+///   found through the `#[ident]` variant, so declaring a keyword without one is a compile error.
+/// - `#[ident]`, `#[integer]`, `#[float]`, `#[string(..)]`, `#[hex(..)]` and `#[comment(..)]` use
+///   the matching `construct_*` function of [super::Lex], with the result placed in the variant's
+///   payload (via [Into] for the borrowed slices). `#[integer]` and `#[float]` allow underscores
+///   and scientific notation unless given explicit arguments, such as `#[float(false, false)]`.
+///   `#[string]` accepts either a single quote and escape char (`#[string('"', '\\')]`) or lists
+///   of each (`#[string(['"', '\''], ['\\'])]`).
+///
+/// These built-in rules are tried in the order they are declared, followed by the literals, and the
+/// first rule that matches wins. Rules that match a prefix of another must therefore come after it:
+/// `#[float]` has to be declared before `#[integer]`, or `1.5` lexes as `1`, `.` and `5`, and
+/// `#[hex(..)]` before either of them.
+/// Whitespace between tokens is skipped, and any char no rule handles results in an
+/// [super::UnexpectedChar] error.
+///
+/// If the enum borrows from the source, its lifetime must be named `'s`.
+///
+/// The generated lexer has a `new(source, file_id)` constructor, implements [super::Lex] and
+/// [Iterator] over [super::Tok]s, and the enum implements [super::TokenKind] (reporting the
+/// `#[ident]` variant through [super::TokenKind::ident]) and [std::fmt::Display].
+///
+/// ```
+/// chompy::lexer! {
+///     /// Lexes a small language.
+///     pub struct Lexer;
+///
+///     #[derive(Debug, PartialEq, Clone)]
+///     pub enum TokKind<'s> {
+///         #[keyword("let")]
+///         Let,
+///         #[literal("=")]
+///         Equal,
+///         #[literal("==")]
+///         DoubleEqual,
+///         #[float]
+///         Float(f64),
+///         #[integer]
+///         Int(i64),
+///         #[string('"', '\\')]
+///         String(&'s str),
+///         #[ident]
+///         Ident(&'s str),
+///     }
+/// }
+///
+/// let kinds: Vec<_> = Lexer::new("let a == 1", 0).map(|tok| tok.unwrap().kind).collect();
+/// assert_eq!(
+///     vec![TokKind::Let, TokKind::Ident("a"), TokKind::DoubleEqual, TokKind::Int(1)],
+///     kinds
+/// );
+/// ```
+///
+/// ```compile_fail
+/// chompy::lexer! {
+///     /// Has a keyword but nothing to find it through.
+///     pub struct Lexer;
+///
+///     #[derive(Debug, PartialEq, Clone)]
+///     pub enum TokKind {
+///         #[keyword("let")]
+///         Let,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! lexer {
+    (
+        $(#[$lexer_attr:meta])*
+        $lexer_vis:vis struct $lexer:ident;
+
+        $(#[$kind_attr:meta])*
+        $kind_vis:vis enum $kind:ident $(<$lt:lifetime>)? {
+            $($body:tt)*
+        }
+    ) => {
+        $crate::lexer!(
+            @munch
+            [
+                [$(#[$lexer_attr])*] [$lexer_vis] $lexer
+                [$(#[$kind_attr])*] [$kind_vis] $kind [$($lt)?]
+            ]
+            [] [] [] [] []
+            $($body)*
+        );
+    };
+
+    // Every arm below pushes onto the accumulators, which are, in order: the attributes waiting
+    // for their variant, the variants, the built-in rules, the keywords, and the literals.
+    (@munch $header:tt [$($attrs:tt)*] $variants:tt $rules:tt $keywords:tt $literals:tt
+        , $($rest:tt)*
+    ) => {
+        $crate::lexer!(@munch $header [$($attrs)*] $variants $rules $keywords $literals $($rest)*);
+    };
+    (@munch $header:tt [$($attrs:tt)*] [$($variants:tt)*] $rules:tt [$($keywords:tt)*] $literals:tt
+        #[keyword($text:literal)] $variant:ident $($rest:tt)*
+    ) => {
+        $crate::lexer!(
+            @munch $header [] [$($variants)* $($attrs)* $variant,] $rules
+            [$($keywords)* ($text, $variant)] $literals
+            $($rest)*
+        );
+    };
+    (@munch $header:tt [$($attrs:tt)*] [$($variants:tt)*] $rules:tt $keywords:tt [$($literals:tt)*]
+        #[literal($text:literal)] $variant:ident $($rest:tt)*
+    ) => {
+        $crate::lexer!(
+            @munch $header [] [$($variants)* $($attrs)* $variant,] $rules
+            $keywords [$($literals)* ($text, $variant)]
+            $($rest)*
+        );
+    };
+    (@munch $header:tt [$($attrs:tt)*] $variants:tt $rules:tt $keywords:tt $literals:tt
+        #[integer] $($rest:tt)*
+    ) => {
+        $crate::lexer!(
+            @munch $header [$($attrs)*] $variants $rules $keywords $literals
+            #[integer(true)] $($rest)*
+        );
+    };
+    (@munch $header:tt [$($attrs:tt)*] $variants:tt $rules:tt $keywords:tt $literals:tt
+        #[float] $($rest:tt)*
+    ) => {
+        $crate::lexer!(
+            @munch $header [$($attrs)*] $variants $rules $keywords $literals
+            #[float(true, true)] $($rest)*
+        );
+    };
+    (@munch $header:tt [$($attrs:tt)*] $variants:tt $rules:tt $keywords:tt $literals:tt
+        #[string($quote:literal, $escape:literal)] $($rest:tt)*
+    ) => {
+        $crate::lexer!(
+            @munch $header [$($attrs)*] $variants $rules $keywords $literals
+            #[string([$quote], [$escape])] $($rest)*
+        );
+    };
+    (@munch $header:tt [$($attrs:tt)*] [$($variants:tt)*] [$($rules:tt)*] $keywords:tt $literals:tt
+        #[$rule:ident $(($($args:tt)*))?] $variant:ident($payload:ty) $($rest:tt)*
+    ) => {
+        $crate::lexer!(
+            @munch $header [] [$($variants)* $($attrs)* $variant($payload),]
+            [$($rules)* [$rule $variant ($($($args)*)?)]] $keywords $literals
+            $($rest)*
+        );
+    };
+    (@munch $header:tt [$($attrs:tt)*] $variants:tt $rules:tt $keywords:tt $literals:tt
+        #[$attr:meta] $($rest:tt)*
+    ) => {
+        $crate::lexer!(
+            @munch $header [$($attrs)* #[$attr]] $variants $rules $keywords $literals $($rest)*
+        );
+    };
+    (@munch
+        [
+            [$($lexer_attr:tt)*] [$lexer_vis:vis] $lexer:ident
+            [$($kind_attr:tt)*] [$kind_vis:vis] $kind:ident [$($lt:lifetime)?]
+        ]
+        [] [$($variants:tt)*] [$([$rule:ident $variant:ident $args:tt])*]
+        [$(($keyword:literal, $keyword_variant:ident))*]
+        [$(($literal:literal, $literal_variant:ident))*]
+
+    ) => {
+        $crate::lexer!(@check_keywords [$($keyword)*] [$($rule)*]);
+
+        $($kind_attr)*
+        $kind_vis enum $kind $(<$lt>)? {
+            $($variants)*
+        }
+
+        impl $(<$lt>)? $crate::lex::TokenKind for $kind $(<$lt>)? {
+            fn ident(&self) -> Option<&str> {
+                match self {
+                    $(Self::$variant(value) => $crate::lexer!(@ident $rule value),)*
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+        }
+
+        impl $(<$lt>)? std::fmt::Display for $kind $(<$lt>)? {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(Self::$keyword_variant => f.pad($keyword),)*
+                    $(Self::$literal_variant => f.pad($literal),)*
+                    $(Self::$variant(value) => $crate::lexer!(@display $rule f value),)*
+                }
+            }
+        }
+
+        $($lexer_attr)*
+        $lexer_vis struct $lexer<'s> {
+            source: &'s str,
+            char_stream: $crate::lex::CharStream<'s>,
+            file_id: $crate::utils::FileId,
+        }
+
+        impl<'s> $lexer<'s> {
+            /// Creates a new lexer over the provided source, creating tokens within the provided
+            /// file.
+            pub fn new(source: &'s str, file_id: $crate::utils::FileId) -> Self {
+                Self {
+                    source,
+                    char_stream: $crate::lex::CharStream::new(source),
+                    file_id,
+                }
+            }
+
+            /// Returns the keyword the identifier is for, if any.
+            #[allow(unused)]
+            fn keyword(ident: &str) -> Option<$kind $(<$lt>)?> {
+                match ident {
+                    $($keyword => Some(<$kind>::$keyword_variant),)*
+                    _ => None,
+                }
+            }
+
+            /// Chomps the longest literal ahead, returning its kind.
+            #[allow(unused)]
+            fn literal(&mut self) -> Option<$kind $(<$lt>)?> {
+                let rest = &self.source[self.char_stream.position()..];
+                let mut longest: Option<(&str, $kind $(<$lt>)?)> = None;
+                $(
+                    if rest.starts_with($literal)
+                        && longest.as_ref().is_none_or(|(text, _)| $literal.len() > text.len())
+                    {
+                        longest = Some(($literal, <$kind>::$literal_variant));
+                    }
+                )*
+                let (text, kind) = longest?;
+                $crate::lex::Lex::chomp_pattern(self, text);
+                Some(kind)
+            }
+        }
+
+        impl<'s> $crate::lex::Lex<'s, $crate::lex::Tok<$kind $(<$lt>)?>, $kind $(<$lt>)?>
+            for $lexer<'s>
+        {
+            fn source(&self) -> &'s str {
+                self.source
+            }
+
+            fn char_stream(&mut self) -> &mut $crate::lex::CharStream<'s> {
+                &mut self.char_stream
+            }
+
+            fn file_id(&self) -> $crate::utils::FileId {
+                self.file_id
+            }
+
+            fn lex(
+                &mut self,
+            ) -> $crate::diagnostics::Result<Option<$crate::lex::Tok<$kind $(<$lt>)?>>> {
+                while self.char_stream.match_chomp_with(|c| c.is_whitespace()) {}
+                let start = self.char_stream.position();
+                let kind = 'rules: {
+                    $(
+                        if let Some(kind) = $crate::lexer!(@rule self $kind $rule $variant $args) {
+                            break 'rules kind?;
+                        }
+                    )*
+                    if let Some(kind) = self.literal() {
+                        break 'rules kind;
+                    }
+                    if self.char_stream.chomp().is_none() {
+                        return Ok(None);
+                    }
+                    let location = $crate::utils::Location::new(
+                        self.file_id,
+                        start..self.char_stream.position(),
+                    );
+                    return Err($crate::lex::UnexpectedChar(location).into());
+                };
+                let location =
+                    $crate::utils::Location::new(self.file_id, start..self.char_stream.position());
+                Ok(Some($crate::lex::Tok::new(kind, location)))
+            }
+        }
+
+        impl<'s> Iterator for $lexer<'s> {
+            type Item = $crate::diagnostics::Result<$crate::lex::Tok<$kind $(<$lt>)?>>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                $crate::lex::Lex::lex(self).transpose()
+            }
+        }
+    };
+
+    // Keywords are only ever matched by the `#[ident]` rule, so one must exist if any are declared.
+    (@check_keywords [] $rules:tt) => {};
+    (@check_keywords $keywords:tt [$($rule:ident)*]) => {
+        $crate::lexer!(@find_ident $($rule)*);
+    };
+    (@find_ident ident $($rest:ident)*) => {};
+    (@find_ident $rule:ident $($rest:ident)*) => {
+        $crate::lexer!(@find_ident $($rest)*);
+    };
+    (@find_ident) => {
+        compile_error!(concat!(
+            "`#[keyword(..)]` variants are matched through an `#[ident]` variant, ",
+            "so one must be declared"
+        ));
+    };
+
+    // Each built-in rule expands to an `Option<Result<Kind>>`
+    (@rule $lexer:tt $kind:ident ident $variant:ident ()) => {
+        $crate::lex::Lex::construct_ident($lexer).map(|ident| {
+            let kind = Self::keyword(ident).unwrap_or_else(|| <$kind>::$variant(ident.into()));
+            $crate::diagnostics::Result::Ok(kind)
+        })
+    };
+    (@rule $lexer:tt $kind:ident integer $variant:ident ($allow_underscore:expr)) => {
+        $crate::lex::Lex::construct_integer($lexer, $allow_underscore)
+            .map(|int| $crate::diagnostics::Result::Ok(<$kind>::$variant(int.into())))
+    };
+    (@rule $lexer:tt $kind:ident float $variant:ident ($underscore:expr, $sci:expr)) => {
+        $crate::lex::Lex::construct_float($lexer, $underscore, $sci)
+            .map(|float| $crate::diagnostics::Result::Ok(<$kind>::$variant(float.into())))
+    };
+    (@rule $lexer:tt $kind:ident string $variant:ident ([$($q:literal),*], [$($e:literal),*])) => {
+        $crate::lex::Lex::construct_string($lexer, &[$($q),*], &[$($e),*])
+            .map(|string| string.map(|string| <$kind>::$variant(string.into())))
+    };
+    (@rule $lexer:tt $kind:ident hex $variant:ident ($prefix:literal)) => {
+        $crate::lex::Lex::construct_hex($lexer, $prefix)
+            .map(|hex| hex.map(|hex| <$kind>::$variant(hex.into())))
+    };
+    (@rule $lexer:tt $kind:ident comment $variant:ident ($($prefix:literal),+)) => {
+        $crate::lex::Lex::construct_comment($lexer, &[$($prefix),+])
+            .map(|comment| $crate::diagnostics::Result::Ok(<$kind>::$variant(comment.into())))
+    };
+
+    (@display string $f:ident $value:ident) => {
+        $f.pad(&format!("\"{}\"", $value))
+    };
+    (@display $rule:ident $f:ident $value:ident) => {
+        std::fmt::Display::fmt($value, $f)
+    };
+
+    (@ident ident $value:ident) => {
+        Some(::std::convert::AsRef::<str>::as_ref($value))
+    };
+    (@ident $rule:ident $value:ident) => {{
+        let _ = $value;
+        None
+    }};
+}
@@ -0,0 +1,294 @@
+use std::{
+    io::{self, Read},
+    ops::Range,
+};
+
+use super::scan::Scan;
+
+/// The default amount of bytes a [ReadStream] reads at once.
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A counterpart to [super::CharStream] which reads its source from an [io::Read] in chunks rather
+/// than requiring the whole source up front, allowing sources larger than memory to be lexed.
+///
+/// Only a rolling window of the source is held at a time. Chars behind the true cursor are dropped
+/// as new chunks are read, unless they have been held onto with [ReadStream::hold]. Because of
+/// this, slices can only be taken of the window (see [ReadStream::slice]), and [super::StreamLex]
+/// returns owned tokens. All positions are still absolute byte offsets into the full source.
+///
+/// If the reader fails (or produces invalid UTF-8), the stream ends early and the error can be
+/// retrieved with [ReadStream::take_error].
+#[derive(Debug)]
+pub struct ReadStream<R: Read> {
+    reader: R,
+    window: String,
+    window_start: usize,
+    undecoded: Vec<u8>,
+    chunk_size: usize,
+    peek_cursor: usize,
+    true_cursor: usize,
+    held: Option<usize>,
+    exhausted: bool,
+    error: Option<io::Error>,
+}
+impl<R: Read> ReadStream<R> {
+    /// Creates a new ReadStream that reads from the provided reader in chunks of
+    /// [DEFAULT_CHUNK_SIZE].
+    pub fn new(reader: R) -> Self {
+        Self::with_chunk_size(reader, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// Creates a new ReadStream that reads from the provided reader in chunks of the provided
+    /// amount of bytes.
+    pub fn with_chunk_size(reader: R, chunk_size: usize) -> Self {
+        Self {
+            reader,
+            window: String::new(),
+            window_start: 0,
+            undecoded: Vec::new(),
+            chunk_size: chunk_size.max(4),
+            peek_cursor: 0,
+            true_cursor: 0,
+            held: None,
+            exhausted: false,
+            error: None,
+        }
+    }
+
+    /// Returns the next char in the stream and advances forward.
+    pub fn chomp(&mut self) -> Option<char> {
+        let next = self.char_at(self.true_cursor)?;
+        self.true_cursor += next.len_utf8();
+        self.peek_cursor = self.true_cursor;
+        Some(next)
+    }
+
+    /// Chomps the next char if it matches the expected one.
+    pub fn match_chomp(&mut self, expected: char) -> bool {
+        self.match_chomp_with(|c| c == expected)
+    }
+
+    /// Chomps the next char if it satisfies the provided closure.
+    pub fn match_chomp_with<F: FnMut(char) -> bool>(&mut self, mut f: F) -> bool {
+        match self.char_at(self.true_cursor) {
+            Some(c) if f(c) => self.chomp().is_some(),
+            _ => false,
+        }
+    }
+
+    /// Returns the char under the peek cursor without moving it.
+    pub fn peek(&mut self) -> Option<char> {
+        self.char_at(self.peek_cursor)
+    }
+
+    /// Returns if the next char matches the provided one, advancing the peek cursor if so.
+    pub fn match_peek(&mut self, expected: char) -> bool {
+        Scan::match_peek(self, expected)
+    }
+
+    /// Returns if the next char satisfies the provided closure, advancing the peek cursor if so.
+    pub fn match_peek_with<F: FnMut(char) -> bool>(&mut self, f: F) -> bool {
+        Scan::match_peek_with(self, f)
+    }
+
+    /// Continously peeks while the given condition is met. Returns if the condition was satisifed
+    /// on at least one char.
+    pub fn peek_while<F: FnMut(char) -> bool>(&mut self, mut f: F) -> bool {
+        let mut any = false;
+        while self.match_peek_with(&mut f) {
+            any = true
+        }
+        any
+    }
+
+    /// Peeks, then advances the peek cursor by one.
+    pub fn peek_move(&mut self) -> Option<char> {
+        let peek = self.peek();
+        self.advance();
+        peek
+    }
+
+    /// Chomps every char between the true cursor and peek cursor, returning them.
+    pub fn chomp_peeks(&mut self) -> String {
+        let chomped = self.inspect_peeks().to_owned();
+        self.true_cursor = self.peek_cursor;
+        chomped
+    }
+
+    /// Resets the peek cursor after returning everything between the true cursor and peek cursor.
+    pub fn empty_peeks(&mut self) -> String {
+        let peeked = self.inspect_peeks().to_owned();
+        self.reset_peeks();
+        peeked
+    }
+
+    /// Returns a slice of the window between the true cursor and peek cursor.
+    pub fn inspect_peeks(&self) -> &str {
+        let forward_position = std::cmp::min(self.window_end(), self.peek_cursor);
+        self.slice(self.true_cursor..forward_position)
+    }
+
+    /// Resets the peek cursor to the position of the true cursor.
+    pub fn reset_peeks(&mut self) {
+        self.peek_cursor = self.true_cursor;
+    }
+
+    /// Advances the peek cursor forward by one char.
+    pub fn advance(&mut self) {
+        self.peek_cursor += self.peek().map_or(1, |c| c.len_utf8());
+    }
+
+    /// Returns the position of the true cursor, as a byte offset into the full source.
+    pub fn position(&self) -> usize {
+        self.true_cursor
+    }
+
+    /// Returns the position of the peek cursor, as a byte offset into the full source.
+    pub fn peek_position(&self) -> usize {
+        self.peek_cursor
+    }
+
+    /// Returns if the stream has reached the end with nothing more to return.
+    pub fn at_end(&mut self) -> bool {
+        self.char_at(self.true_cursor).is_none()
+    }
+
+    /// Returns a slice of the window within the provided absolute range.
+    ///
+    /// ### Panics
+    /// Panics if any of the range has already been dropped from the window or has not been read
+    /// yet.
+    pub fn slice(&self, range: Range<usize>) -> &str {
+        &self.window[(range.start - self.window_start)..(range.end - self.window_start)]
+    }
+
+    /// Prevents anything from the true cursor onwards from being dropped from the window until
+    /// [ReadStream::release] is called, so that it can later be retrieved with
+    /// [ReadStream::slice].
+    pub fn hold(&mut self) {
+        self.held = Some(self.true_cursor);
+    }
+
+    /// Releases the position held by [ReadStream::hold].
+    pub fn release(&mut self) {
+        self.held = None;
+    }
+
+    /// Returns the error that ended the stream early, if any.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Returns the absolute position of the end of the window.
+    fn window_end(&self) -> usize {
+        self.window_start + self.window.len()
+    }
+
+    /// Returns the char at the given absolute position, reading more of the source if needed.
+    fn char_at(&mut self, position: usize) -> Option<char> {
+        while position >= self.window_end() && !self.exhausted {
+            self.read_chunk();
+        }
+        self.window
+            .get((position - self.window_start)..)?
+            .chars()
+            .next()
+    }
+
+    /// Drops everything no longer needed from the window, then reads the next chunk of the source
+    /// into it.
+    fn read_chunk(&mut self) {
+        let keep_from = self
+            .held
+            .map_or(self.true_cursor, |held| held.min(self.true_cursor));
+        let droppable = keep_from - self.window_start;
+        if droppable >= self.chunk_size {
+            self.window.drain(..droppable);
+            self.window_start = keep_from;
+        }
+
+        let mut buffer = vec![0; self.chunk_size];
+        let read = loop {
+            match self.reader.read(&mut buffer) {
+                Ok(read) => break read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.error = Some(err);
+                    self.exhausted = true;
+                    return;
+                }
+            }
+        };
+
+        if read == 0 {
+            self.exhausted = true;
+            if !self.undecoded.is_empty() {
+                self.error = Some(invalid_utf8());
+            }
+            return;
+        }
+
+        self.undecoded.extend_from_slice(&buffer[..read]);
+        match std::str::from_utf8(&self.undecoded) {
+            Ok(decoded) => {
+                self.window.push_str(decoded);
+                self.undecoded.clear();
+            }
+            Err(err) => {
+                // A chunk can end in the middle of a char, so only the valid prefix is decoded
+                // and the rest waits for the next chunk
+                let valid_up_to = err.valid_up_to();
+                let valid = std::str::from_utf8(&self.undecoded[..valid_up_to])
+                    .expect("valid_up_to is always a char boundary");
+                self.window.push_str(valid);
+                self.undecoded.drain(..valid_up_to);
+                if err.error_len().is_some() {
+                    self.error = Some(invalid_utf8());
+                    self.exhausted = true;
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Scan for ReadStream<R> {
+    fn peek(&mut self) -> Option<char> {
+        ReadStream::peek(self)
+    }
+
+    fn advance(&mut self) {
+        ReadStream::advance(self)
+    }
+
+    fn chomp(&mut self) -> Option<char> {
+        ReadStream::chomp(self)
+    }
+
+    fn commit(&mut self) {
+        self.true_cursor = self.peek_cursor;
+    }
+
+    fn reset_peeks(&mut self) {
+        ReadStream::reset_peeks(self)
+    }
+
+    fn position(&self) -> usize {
+        ReadStream::position(self)
+    }
+
+    fn peek_position(&self) -> usize {
+        ReadStream::peek_position(self)
+    }
+
+    fn text(&self, range: Range<usize>) -> &str {
+        self.slice(range)
+    }
+}
+
+/// Creates the error used when the source is not valid UTF-8.
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
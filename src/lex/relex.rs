@@ -0,0 +1,88 @@
+use crate::{
+    diagnostics::Result,
+    utils::{Located, Location, Span, TextEdit},
+};
+
+use super::{Tok, TokenKind};
+
+/// The result of [relex].
+#[derive(Debug, PartialEq)]
+pub struct Relexed<K: TokenKind> {
+    /// Every token of the edited source.
+    pub tokens: Vec<Tok<K>>,
+    /// The span of the edited source that was lexed again. Tokens outside of it were reused.
+    pub changed: Span,
+    /// The index of the first token that was lexed again.
+    pub first: usize,
+    /// The amount of tokens that were lexed again.
+    pub relexed: usize,
+}
+
+/// Updates the tokens of a source after the provided [TextEdit] is applied to it, lexing only the
+/// tokens around the edit again.
+///
+/// Lexing restarts from the start of the token before the edit, which is assumed to be a safe
+/// place for a lexer to begin. `lexer_at` is given that offset and must return a lexer over the
+/// edited source starting at it; the spans it produces are relative to that offset. Lexing stops
+/// once it produces a token after the edit that matches an old token (in kind and length) at the
+/// same shifted position, after which the old tokens are reused.
+///
+/// ### Errors
+/// Returns the first error produced by the lexer.
+pub fn relex<K, L, F>(tokens: &[Tok<K>], edit: &TextEdit, lexer_at: F) -> Result<Relexed<K>>
+where
+    K: TokenKind,
+    L: Iterator<Item = Result<Tok<K>>>,
+    F: FnOnce(usize) -> L,
+{
+    let old_end = edit.span.end();
+    let new_end = edit.new_span().end();
+    let touching = tokens.partition_point(|tok| tok.span().end() < edit.span.start());
+    let first = touching.saturating_sub(1);
+    let start = tokens.get(first).map_or(edit.span.start(), |tok| {
+        tok.span().start().min(edit.span.start())
+    });
+
+    let mut relexed: Vec<Tok<K>> = tokens[..first].to_vec();
+    let mut reused = tokens.len();
+    for tok in lexer_at(start) {
+        let tok = tok?;
+        let span = Span::new(tok.span().start() + start, tok.span().end() + start);
+        if span.start() >= new_end {
+            let old_start = span.start() - new_end + old_end;
+            let index = tokens.partition_point(|old| old.span().start() < old_start);
+            if let Some(old) = tokens.get(index) {
+                let old_span = old.span();
+                if old_span.start() == old_start
+                    && old_span.end() - old_span.start() == span.end() - span.start()
+                    && old.kind == tok.kind
+                {
+                    reused = index;
+                    break;
+                }
+            }
+        }
+        let location = Location::new(tok.file_id(), span);
+        relexed.push(Tok::new(tok.kind, location));
+    }
+
+    let changed_end = relexed
+        .last()
+        .filter(|_| relexed.len() > first)
+        .map_or(new_end, |tok| tok.span().end().max(new_end));
+    let count = relexed.len() - first;
+    for old in &tokens[reused..] {
+        let span = old.span();
+        let shifted = Span::new(edit.map_offset(span.start()), edit.map_offset(span.end()));
+        relexed.push(Tok::new(
+            old.kind.clone(),
+            Location::new(old.file_id(), shifted),
+        ));
+    }
+    Ok(Relexed {
+        tokens: relexed,
+        changed: Span::new(start, changed_end),
+        first,
+        relexed: count,
+    })
+}
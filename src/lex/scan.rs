@@ -0,0 +1,263 @@
+use std::ops::Range;
+
+use crate::{
+    diagnostics::Result,
+    utils::{FileId, Location, Span},
+};
+
+use super::errors::{InvalidHex, UnterminatedString};
+
+/// The shared cursor operations of [super::CharStream] and [super::ReadStream], used to implement
+/// the `construct_*` functions of [super::Lex] and [super::StreamLex] once.
+///
+/// Every position is an absolute byte offset into the source.
+pub(crate) trait Scan {
+    /// Returns the char under the peek cursor.
+    fn peek(&mut self) -> Option<char>;
+
+    /// Advances the peek cursor by one char.
+    fn advance(&mut self);
+
+    /// Consumes the char under the true cursor.
+    fn chomp(&mut self) -> Option<char>;
+
+    /// Moves the true cursor to the peek cursor.
+    fn commit(&mut self);
+
+    /// Moves the peek cursor back to the true cursor.
+    fn reset_peeks(&mut self);
+
+    /// Returns the position of the true cursor.
+    fn position(&self) -> usize;
+
+    /// Returns the position of the peek cursor.
+    fn peek_position(&self) -> usize;
+
+    /// Returns the text in the provided range. The range must not have been dropped by the stream.
+    fn text(&self, range: Range<usize>) -> &str;
+
+    /// Advances the peek cursor if the next char satisfies the closure.
+    fn match_peek_with<F: FnMut(char) -> bool>(&mut self, mut f: F) -> bool {
+        let Some(c) = self.peek() else { return false };
+        if f(c) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Advances the peek cursor if the next char is the expected one.
+    fn match_peek(&mut self, expected: char) -> bool {
+        self.match_peek_with(|c| c == expected)
+    }
+}
+
+/// See [super::Lex::construct].
+pub(crate) fn construct<S: Scan, F: FnMut(char) -> bool>(stream: &mut S, mut f: F) -> Range<usize> {
+    let start = stream.position();
+    while stream.match_peek_with(&mut f) {}
+    stream.commit();
+    start..stream.position()
+}
+
+/// See [super::Lex::construct_ident].
+pub(crate) fn ident<S: Scan>(stream: &mut S) -> Option<Range<usize>> {
+    let test = |c: char| -> bool { c.is_alphanumeric() || c == '_' };
+    stream
+        .match_peek_with(test)
+        .then(|| construct(stream, test))
+}
+
+/// See [super::Lex::construct_integer].
+pub(crate) fn integer<S: Scan>(stream: &mut S, allow_underscore: bool) -> Option<i64> {
+    let test = |c: char| -> bool { c.is_ascii_digit() || c == '_' && allow_underscore };
+    stream
+        .peek()
+        .is_some_and(|c| c.is_ascii_digit())
+        .then(|| construct(stream, test))
+        .and_then(|range| stream.text(range).replace('_', "").parse::<i64>().ok())
+}
+
+/// See [super::Lex::construct_float].
+pub(crate) fn float<S: Scan>(
+    stream: &mut S,
+    allow_underscore: bool,
+    allow_scientific_notation: bool,
+) -> Option<f64> {
+    if !stream.match_peek_with(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let start = stream.position();
+    let mut found_dot = false;
+    let mut found_trailing_digit = false;
+    loop {
+        match stream.peek() {
+            Some('_') if allow_underscore => {}
+            Some('.') if !found_dot => {
+                found_dot = true;
+            }
+            Some(chr) if chr.is_ascii_digit() => {
+                if found_dot {
+                    found_trailing_digit = true;
+                }
+            }
+            _ if found_dot && found_trailing_digit => {
+                stream.commit();
+                break;
+            }
+            _ => {
+                stream.reset_peeks();
+                return None;
+            }
+        }
+        stream.advance();
+    }
+
+    let mantissa_slice = stream.text(start..stream.position());
+    let mantissa = mantissa_slice.replace('_', "").parse::<f64>().ok()?;
+
+    if allow_scientific_notation && stream.peek() == Some('e') {
+        stream.advance();
+
+        let negative = match stream.peek() {
+            Some('+') => {
+                stream.advance();
+                false
+            }
+            Some('-') => {
+                stream.advance();
+                true
+            }
+            _ => false,
+        };
+
+        let mut found_exp_digit = false;
+        while stream.match_peek_with(|c| c.is_ascii_digit()) {
+            found_exp_digit = true;
+            stream.advance();
+        }
+
+        if found_exp_digit {
+            // the committed range is "e[+|-]?<digits>"; rely on i32's parser to handle the
+            // digits in one shot
+            let start = stream.position();
+            stream.commit();
+            let suffix = stream.text(start..stream.position());
+            if let Ok(exp) = suffix.trim_start_matches(['e', '+', '-']).parse::<i32>() {
+                let signed = if negative { -exp } else { exp };
+                return Some(mantissa * 10f64.powi(signed));
+            }
+        }
+
+        // Saw `e` but no valid exponent followed — discard the trial peeks
+        // and fall through with just the mantissa
+        stream.reset_peeks();
+    }
+
+    Some(mantissa)
+}
+
+/// See [super::Lex::construct_string]. The returned range excludes the quotes.
+pub(crate) fn string<S: Scan>(
+    stream: &mut S,
+    file_id: FileId,
+    quote_chars: &[char],
+    escape_chars: &[char],
+) -> Option<Result<Range<usize>>> {
+    let start = stream.position();
+    let opening_delim = stream.peek()?;
+    stream.advance();
+    if !quote_chars.contains(&opening_delim) {
+        stream.reset_peeks();
+        return None;
+    }
+    let mut in_escape = false;
+    loop {
+        let peek = stream.peek();
+        stream.advance();
+        match peek {
+            None => {
+                let location = Location::new(file_id, Span::new(start, stream.peek_position()));
+                break Some(Err(UnterminatedString(location).into()));
+            }
+            Some(chr) if chr == opening_delim && !in_escape => {
+                let range =
+                    (start + opening_delim.len_utf8())..stream.peek_position() - chr.len_utf8();
+                stream.commit();
+                break Some(Ok(range));
+            }
+            Some(chr) if escape_chars.contains(&chr) && !in_escape => in_escape = true,
+            _ => in_escape = false,
+        }
+    }
+}
+
+/// See [super::Lex::construct_hex]. The returned range excludes the prefix.
+pub(crate) fn hex<S: Scan>(
+    stream: &mut S,
+    file_id: FileId,
+    prefix: &str,
+) -> Option<Result<Range<usize>>> {
+    let start = stream.position();
+    if !prefix.chars().all(|p| stream.match_peek(p)) {
+        stream.reset_peeks();
+        None
+    } else {
+        while stream.match_peek_with(|c| c.is_ascii_hexdigit()) {}
+        let range = (start + prefix.len())..stream.peek_position();
+        stream.commit();
+        if range.is_empty() {
+            let location = Location::new(file_id, Span::new(start, stream.peek_position()));
+            Some(Err(InvalidHex(location).into()))
+        } else {
+            Some(Ok(range))
+        }
+    }
+}
+
+/// See [super::Lex::construct_comment].
+pub(crate) fn comment<S: Scan>(stream: &mut S, prefixes: &[&str]) -> Option<Range<usize>> {
+    let start = stream.position();
+    let mut found_any = false;
+    loop {
+        if !prefixes
+            .iter()
+            .any(|prefix| prefix.chars().all(|p| stream.match_peek(p)))
+        {
+            stream.reset_peeks();
+            break;
+        } else {
+            found_any = true;
+            line(stream);
+        }
+    }
+    found_any.then(|| start..stream.position())
+}
+
+/// See [super::Lex::chomp_line].
+pub(crate) fn line<S: Scan>(stream: &mut S) -> Range<usize> {
+    let start = stream.position();
+    loop {
+        match stream.chomp() {
+            Some('\n' | '\r') => {
+                break;
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    start..stream.position()
+}
+
+/// See [super::Lex::chomp_pattern].
+pub(crate) fn pattern<S: Scan>(stream: &mut S, pattern: &str) -> bool {
+    if pattern.chars().all(|v| stream.match_peek(v)) {
+        stream.commit();
+        true
+    } else {
+        stream.reset_peeks();
+        false
+    }
+}
@@ -0,0 +1,173 @@
+use std::io::Read;
+
+use crate::{
+    diagnostics::Result,
+    utils::{FileId, Interner, Location, Span, Symbol},
+};
+
+use super::{ReadFailed, ReadStream, Token, TokenKind, scan};
+
+/// A counterpart to [super::Lex] for lexers that read their source through a [ReadStream]. Since
+/// the source is never fully in memory, the constructors here return owned values rather than
+/// slices of the source. Spans created from [ReadStream::position] are still absolute offsets into
+/// the full source.
+pub trait StreamLex<R: Read, T: Token<K>, K: TokenKind> {
+    /// Returns a reference to the stream over the characters of the source.
+    fn char_stream(&mut self) -> &mut ReadStream<R>;
+
+    /// Returns the [crate::utils::FileId] of the file this lexer is working within.
+    fn file_id(&self) -> FileId;
+
+    /// The primary function for lexxing the next token in the stream.
+    fn lex(&mut self) -> Result<Option<T>>;
+
+    /// Returns an error if the underlying reader failed. Because a failing reader looks like the
+    /// end of the source to the stream, this should be checked before reporting the end of the
+    /// source from [StreamLex::lex].
+    fn check_read(&mut self) -> Result<()> {
+        let file_id = self.file_id();
+        let stream = self.char_stream();
+        match stream.take_error() {
+            Some(err) => {
+                let position = stream.position();
+                let location = Location::new(file_id, Span::new(position, position));
+                Err(ReadFailed(err.to_string(), location).into())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the next char in the stream without advancing the lexer.
+    fn peek(&mut self) -> Option<char> {
+        self.char_stream().peek()
+    }
+
+    /// Chomps the next char if it matches the one provided.
+    fn match_chomp(&mut self, chr: char) -> bool {
+        self.char_stream().match_chomp(chr)
+    }
+
+    /// Consumes and returns the next char in the stream if any remain.
+    fn chomp(&mut self) -> Option<char> {
+        self.char_stream().chomp()
+    }
+
+    /// See [super::Lex::construct_ident].
+    fn construct_ident(&mut self) -> Option<String> {
+        let stream = self.char_stream();
+        stream.hold();
+        let ident = scan::ident(stream).map(|range| stream.slice(range).to_owned());
+        stream.release();
+        ident
+    }
+
+    /// See [super::Lex::construct_integer].
+    fn construct_integer(&mut self, allow_underscore: bool) -> Option<i64> {
+        let stream = self.char_stream();
+        stream.hold();
+        let integer = scan::integer(stream, allow_underscore);
+        stream.release();
+        integer
+    }
+
+    /// See [super::Lex::construct_float].
+    fn construct_float(
+        &mut self,
+        allow_underscore: bool,
+        allow_scientific_notation: bool,
+    ) -> Option<f64> {
+        let stream = self.char_stream();
+        stream.hold();
+        let float = scan::float(stream, allow_underscore, allow_scientific_notation);
+        stream.release();
+        float
+    }
+
+    /// See [super::Lex::construct_string].
+    fn construct_string(
+        &mut self,
+        quote_chars: &[char],
+        escape_chars: &[char],
+    ) -> Option<Result<String>> {
+        let file_id = self.file_id();
+        let stream = self.char_stream();
+        stream.hold();
+        let string = scan::string(stream, file_id, quote_chars, escape_chars)
+            .map(|range| range.map(|range| stream.slice(range).to_owned()));
+        stream.release();
+        string
+    }
+
+    /// See [super::Lex::construct_ident_symbol].
+    fn construct_ident_symbol(&mut self, interner: &Interner) -> Option<Symbol> {
+        let stream = self.char_stream();
+        stream.hold();
+        let symbol = scan::ident(stream).map(|range| interner.intern(stream.slice(range)));
+        stream.release();
+        symbol
+    }
+
+    /// See [super::Lex::construct_string_symbol].
+    fn construct_string_symbol(
+        &mut self,
+        interner: &Interner,
+        quote_chars: &[char],
+        escape_chars: &[char],
+    ) -> Option<Result<Symbol>> {
+        let file_id = self.file_id();
+        let stream = self.char_stream();
+        stream.hold();
+        let symbol = scan::string(stream, file_id, quote_chars, escape_chars)
+            .map(|range| range.map(|range| interner.intern(stream.slice(range))));
+        stream.release();
+        symbol
+    }
+
+    /// See [super::Lex::construct_hex].
+    fn construct_hex(&mut self, prefix: &str) -> Option<Result<String>> {
+        let file_id = self.file_id();
+        let stream = self.char_stream();
+        stream.hold();
+        let hex = scan::hex(stream, file_id, prefix)
+            .map(|range| range.map(|range| stream.slice(range).to_owned()));
+        stream.release();
+        hex
+    }
+
+    /// See [super::Lex::construct_comment].
+    fn construct_comment(&mut self, prefixes: &[&str]) -> Option<String> {
+        let stream = self.char_stream();
+        stream.hold();
+        let comment = scan::comment(stream, prefixes).map(|range| stream.slice(range).to_owned());
+        stream.release();
+        comment
+    }
+
+    /// See [super::Lex::chomp_line].
+    fn chomp_line(&mut self) -> String {
+        let stream = self.char_stream();
+        stream.hold();
+        let range = scan::line(stream);
+        let line = stream.slice(range).to_owned();
+        stream.release();
+        line
+    }
+
+    /// See [super::Lex::chomp_pattern].
+    fn chomp_pattern(&mut self, pattern: &str) -> bool {
+        scan::pattern(self.char_stream(), pattern)
+    }
+
+    /// See [super::Lex::construct].
+    fn construct<F>(&mut self, f: F) -> String
+    where
+        F: FnMut(char) -> bool,
+    {
+        let stream = self.char_stream();
+        stream.hold();
+        let range = scan::construct(stream, f);
+        let constructed = stream.slice(range).to_owned();
+        stream.release();
+        constructed
+    }
+}
@@ -70,4 +70,11 @@ pub trait Token<K: TokenKind>: std::fmt::Debug + Located + Sized {
 }
 
 /// A [TokenKind] covers individual piece of your language that fits into a [Token].
-pub trait TokenKind: std::fmt::Debug + PartialEq + Sized + Display + Clone + Send + Sync {}
+pub trait TokenKind: std::fmt::Debug + PartialEq + Sized + Display + Clone + Send + Sync {
+    /// Returns the text of this kind if it is an identifier, or None otherwise (which is the
+    /// default). Implementing this lets a parser find contextual keywords, which are identifiers
+    /// with a particular text, through [crate::parse::Parse::match_contextual].
+    fn ident(&self) -> Option<&str> {
+        None
+    }
+}
@@ -0,0 +1,50 @@
+use regex_automata::{Anchored, Input, meta};
+
+/// A regular expression for [super::Lex::construct_regex], using the syntax of the `regex` crate.
+///
+/// A TokenRegex only ever matches at the lexer's current position, so it never searches ahead
+/// through the rest of the source. Assertions still see the source before that position, so `\b`
+/// and `^` behave as they would over the whole source. This means `^` only ever matches at the
+/// start of the source (or of a line with `(?m)`), never at an arbitrary cursor. Requires the
+/// `regex` feature.
+///
+/// ```
+/// use chompy::lex::TokenRegex;
+///
+/// let regex = TokenRegex::new("^foo").unwrap();
+/// assert_eq!(regex.match_at("foo foo", 0), Some(3));
+/// assert_eq!(regex.match_at("foo foo", 4), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TokenRegex(meta::Regex);
+
+impl TokenRegex {
+    /// Compiles the provided pattern.
+    pub fn new(pattern: &str) -> Result<Self, RegexError> {
+        meta::Regex::new(pattern)
+            .map(Self)
+            .map_err(|err| RegexError(err.to_string()))
+    }
+
+    /// Returns the end of the match beginning exactly at `start` within `haystack`, or None if
+    /// there is no such match or it is empty.
+    pub fn match_at(&self, haystack: &str, start: usize) -> Option<usize> {
+        let input = Input::new(haystack).range(start..).anchored(Anchored::Yes);
+        self.0
+            .find(input)
+            .filter(|found| !found.is_empty())
+            .map(|found| found.end())
+    }
+}
+
+/// The error returned when a [TokenRegex] fails to compile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexError(String);
+
+impl std::fmt::Display for RegexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for RegexError {}
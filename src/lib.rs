@@ -7,28 +7,74 @@ pub mod lex {
     mod char_stream;
     mod errors;
     mod lex;
+    mod macros;
+    mod read_stream;
+    mod relex;
+    mod scan;
+    mod stream_lex;
     mod tok;
+    #[cfg(feature = "regex")]
+    mod token_regex;
     pub use char_stream::*;
     pub use errors::*;
     pub use lex::*;
+    pub use read_stream::*;
+    pub use relex::*;
+    pub use stream_lex::*;
     pub use tok::*;
+    #[cfg(feature = "regex")]
+    pub use token_regex::*;
 }
 
 /// Tools for creating parsers.
 pub mod parse {
+    mod balance;
+    mod buffer;
     mod errors;
+    mod list;
+    mod memo;
+    mod output;
     mod parse;
+    mod pratt;
+    mod trace;
+    pub use balance::*;
+    pub use buffer::*;
     pub use errors::*;
+    pub use list::*;
+    pub use memo::*;
+    pub use output::*;
     pub use parse::*;
+    pub use pratt::*;
+    pub use trace::*;
+}
+
+/// Lossless concrete syntax trees, which keep every token and the trivia between them.
+pub mod cst {
+    mod green;
+    mod red;
+    mod reparse;
+    pub use green::*;
+    pub use red::*;
+    pub use reparse::*;
 }
 
 /// Common utilities shared across the different elements of chompy.
 pub mod utils {
+    mod arena;
+    mod edit;
     mod files;
+    mod interner;
+    mod lines;
     mod location;
+    mod spanned;
     mod unescape;
+    pub use arena::*;
+    pub use edit::*;
     pub use files::*;
+    pub use interner::*;
+    pub use lines::*;
     pub use location::*;
+    pub use spanned::*;
     pub use unescape::*;
 }
 
@@ -41,8 +87,164 @@ pub mod diagnostics {
     pub use utils::*;
 }
 
+pub use chompy_macros::Visit;
+
+/// Generates a recursive descent parser from a grammar, on top of [parse::Parse].
+///
+/// For example, a grammar over the tokens of a lexer generated by [lexer!]:
+///
+/// ```
+/// use chompy::{
+///     lex::Tok,
+///     parse::{Parse, TokenBuffer},
+///     utils::FileId,
+/// };
+///
+/// chompy::lexer! {
+///     /// Lexes arithmetic over integers.
+///     pub struct Lexer;
+///
+///     #[derive(Debug, PartialEq, Clone)]
+///     pub enum TokKind {
+///         #[literal("+")]
+///         Plus,
+///         #[literal("-")]
+///         Minus,
+///         #[literal("*")]
+///         Star,
+///         #[literal("/")]
+///         Slash,
+///         #[literal("(")]
+///         LeftParen,
+///         #[literal(")")]
+///         RightParen,
+///         #[integer]
+///         Int(i64),
+///     }
+/// }
+///
+/// use TokKind::*;
+///
+/// /// Parses arithmetic, recording a syntax tree with nodes of kind `N`.
+/// pub struct Parser<'s, N> {
+///     lexer: TokenBuffer<Lexer<'s>, Tok<TokKind>, TokKind, N>,
+///     cursor: usize,
+/// }
+///
+/// impl<'s, N> Parse<'s, Lexer<'s>, Tok<TokKind>, TokKind, N> for Parser<'s, N> {
+///     fn lexer(&mut self) -> &mut TokenBuffer<Lexer<'s>, Tok<TokKind>, TokKind, N> {
+///         &mut self.lexer
+///     }
+///
+///     fn file_id(&self) -> FileId {
+///         0
+///     }
+///
+///     fn cursor(&self) -> usize {
+///         self.cursor
+///     }
+///
+///     fn set_cursor(&mut self, target: usize) {
+///         self.cursor = target;
+///     }
+/// }
+///
+/// chompy::grammar! {
+///     /// Arithmetic over integers.
+///     pub mod calc for Parser<'s, Rule> {
+///         pub expr = term ((Plus | Minus) term)*;
+///         term = factor ((Star | Slash) factor)*;
+///         factor = Int(_) | LeftParen expr RightParen | Minus factor;
+///     }
+/// }
+///
+/// fn main() {
+///     let source = "1 + 2 * (3 - -4)";
+///     let lexer = TokenBuffer::new(Lexer::new(source, 0));
+///     let mut parser = Parser { lexer, cursor: 0 };
+///     calc::expr(&mut parser).unwrap();
+///     let tree = parser.finish_tree(source);
+///     assert_eq!(&calc::Rule::Expr, tree.kind());
+///     assert_eq!(source, tree.text());
+///
+///     let first: &[&[&str]] = &[&["Int"], &["LeftParen"], &["Minus"]];
+///     assert_eq!(first, calc::Rule::Factor.first());
+///
+///     let lexer = TokenBuffer::new(Lexer::new("1 +", 0));
+///     assert!(calc::expr(&mut Parser { lexer, cursor: 0 }).is_err());
+/// }
+/// ```
+pub use chompy_macros::grammar;
+
+/// Generates visitor traits for the tree of AST types within a module.
+///
+/// For example, a pass counting the integers in an expression:
+///
+/// ```
+/// #[chompy::visitors]
+/// mod ast {
+///     use chompy::{Visit, utils::Spanned};
+///
+///     #[derive(Visit)]
+///     pub enum Expr {
+///         Int(i64),
+///         Neg(Box<Spanned<Expr>>),
+///         Call(String, Vec<Spanned<Expr>>),
+///     }
+/// }
+///
+/// use ast::{Expr, Visit};
+/// use chompy::utils::{Location, Spanned};
+///
+/// struct CountInts(usize);
+///
+/// impl<'ast> Visit<'ast> for CountInts {
+///     fn visit_expr(&mut self, node: &'ast Expr) {
+///         if let Expr::Int(_) = node {
+///             self.0 += 1;
+///         }
+///         ast::walk_expr(self, node);
+///     }
+/// }
+///
+/// fn main() {
+///     let spanned = |expr| Spanned::new(expr, Location::new(0, 0..1));
+///     let neg = Expr::Neg(Box::new(spanned(Expr::Int(2))));
+///     let call = Expr::Call("f".into(), vec![spanned(Expr::Int(1)), spanned(neg)]);
+///     let mut count = CountInts(0);
+///     count.visit_expr(&call);
+///     assert_eq!(2, count.0);
+/// }
+/// ```
+pub use chompy_macros::visitors;
+
+// Lets the code generated by the procedural macros refer to `::chompy` from within chompy itself.
+extern crate self as chompy;
+
 #[cfg(test)]
 mod tests {
+    mod arena;
+    mod cst;
+    mod delimited;
+    mod depth;
+    mod grammar;
+    mod incremental;
+    mod interner;
     mod lex;
+    mod lexer_macro;
+    mod lines;
+    mod list;
+    mod memo;
+    mod output;
+    mod parse;
+    mod pratt;
+    mod recovery;
+    #[cfg(feature = "regex")]
+    mod regex;
+    mod spanned;
+    mod split;
+    mod stream;
+    mod trace;
     mod utils;
+    mod visit;
 }
@@ -0,0 +1,66 @@
+use crate::{
+    diagnostics::DiagBox,
+    lex::{Token, TokenKind},
+    utils::{Location, Span},
+};
+
+use super::errors::{MismatchedDelimiter, UnclosedDelimiter, UnmatchedDelimiter};
+
+/// Checks that every delimiter in the provided tokens is balanced, returning a diagnostic for each
+/// problem found. Each pair in `pairs` is an opening kind followed by its closing kind.
+///
+/// This is meant to be run over a file's tokens before parsing it, where it can pinpoint problems
+/// such as `(]` that a parser would only notice once it is lost.
+///
+/// A closing delimiter that matches an opener further out closes it, reporting every opener in
+/// between as unclosed. One that matches no opener at all is reported as a mismatch against the
+/// innermost opener, which it then closes.
+pub fn check_delimiters<'a, T, K>(
+    tokens: impl IntoIterator<Item = &'a T>,
+    pairs: &[(K, K)],
+) -> Vec<DiagBox>
+where
+    T: Token<K> + 'a,
+    K: TokenKind,
+{
+    let mut diagnostics: Vec<DiagBox> = vec![];
+    let mut open: Vec<(&'a T, &K)> = vec![];
+    let mut last: Option<Location> = None;
+    for tok in tokens {
+        last = Some(tok.location());
+        if let Some((_, close)) = pairs.iter().find(|(open, _)| open == tok.kind_ref()) {
+            open.push((tok, close));
+            continue;
+        }
+        if !pairs.iter().any(|(_, close)| close == tok.kind_ref()) {
+            continue;
+        }
+        match open.iter().rposition(|(_, close)| *close == tok.kind_ref()) {
+            Some(index) => {
+                for (opener, close) in open.drain(index..).skip(1).rev() {
+                    let unclosed =
+                        UnclosedDelimiter(close.to_string(), tok.location(), opener.location());
+                    diagnostics.push(unclosed.into());
+                }
+            }
+            None => match open.pop() {
+                Some((opener, close)) => {
+                    let mismatch =
+                        MismatchedDelimiter(close.to_string(), tok.location(), opener.location());
+                    diagnostics.push(mismatch.into());
+                }
+                None => diagnostics.push(UnmatchedDelimiter(tok.location()).into()),
+            },
+        }
+    }
+    if let Some(last) = last {
+        let end = Location::new(
+            last.file_id(),
+            Span::new(last.span().end(), last.span().end()),
+        );
+        for (opener, close) in open.into_iter().rev() {
+            diagnostics.push(UnclosedDelimiter(close.to_string(), end, opener.location()).into());
+        }
+    }
+    diagnostics
+}
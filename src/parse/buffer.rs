@@ -0,0 +1,553 @@
+use std::{borrow::Cow, collections::VecDeque, sync::Arc};
+
+use crate::{
+    cst::{GreenBuilder, SyntaxNode},
+    diagnostics::{Builder, Diag, DiagBox, Result, Severity},
+    lex::{Token, TokenKind},
+    utils::{FileId, Located, Span},
+};
+
+use super::{Memo, Trace, TraceEvent};
+
+/// A lookahead buffer over a lexer, used by [super::Parse] to inspect any number of tokens ahead
+/// of the one it is currently on, and to rewind to earlier positions.
+///
+/// Tokens are only lexed once they are needed. Errors produced by the lexer are kept in order with
+/// the tokens around them and are returned once they are reached.
+///
+/// The buffer tracks the kinds of token (of type `K`) that were expected at the current position,
+/// so that a parser which tries several alternatives can report all of them at once. It collects
+/// the diagnostics a recovering parser reports instead of returning. Once
+/// [TokenBuffer::start_node] is first called, it records the nodes and tokens of a concrete syntax
+/// tree. It holds the [Memo] used by [super::Parse::memoize]. It can also record a [Trace] of the
+/// parse for debugging.
+///
+/// Upcoming tokens can be replaced with [TokenBuffer::replace_next], which lets a parser split a
+/// compound token such as `>>` into the pieces a particular context needs.
+///
+/// Consumed tokens are dropped immediately unless a [Checkpoint] is outstanding, in which case they
+/// are kept (and cloned out when consumed) so that [TokenBuffer::rewind] can return to them. This
+/// is why the token type must implement [Clone] to be buffered.
+///
+/// The nodes of the syntax tree are all of kind `N`, which can be left as `()` by parsers that
+/// never build one.
+#[derive(Debug)]
+pub struct TokenBuffer<L, T, K, N = ()> {
+    lexer: L,
+    tokens: VecDeque<std::result::Result<T, Arc<DiagBox>>>,
+    offset: usize,
+    position: usize,
+    previous_end: usize,
+    checkpoints: usize,
+    finished: bool,
+    expected: Vec<Expected<K>>,
+    expected_at: usize,
+    diagnostics: Vec<DiagBox>,
+    events: Vec<Event<T, N>>,
+    recording: bool,
+    memo: Memo,
+    depth: usize,
+    depth_limit: usize,
+    trace: Option<Trace>,
+    replacements: Vec<Replacement<T>>,
+}
+
+/// A saved position within a [TokenBuffer]. Every Checkpoint must be handed back to the buffer
+/// with either [TokenBuffer::rewind] or [TokenBuffer::release].
+#[derive(Debug)]
+#[must_use = "the buffer retains every token until the checkpoint is rewound or released"]
+pub struct Checkpoint {
+    position: usize,
+    previous_end: usize,
+    diagnostics: usize,
+    events: usize,
+    depth: usize,
+    replacements: usize,
+}
+
+/// A saved position within the concrete syntax tree being recorded by a [TokenBuffer], used to
+/// start a node which wraps children that have already been recorded.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct NodeCheckpoint(usize);
+
+/// Tokens replaced by [TokenBuffer::replace_next] while a [Checkpoint] was outstanding, kept so
+/// that rewinding past the replacement restores them.
+#[derive(Debug)]
+struct Replacement<T> {
+    position: usize,
+    removed: Vec<T>,
+    inserted: usize,
+}
+
+/// Something a parser expected to find, kept as it was given until an error needs its text.
+#[derive(Debug, PartialEq)]
+enum Expected<K> {
+    Kind(K),
+    Named(Cow<'static, str>),
+}
+
+/// A step in building a concrete syntax tree, replayed by [TokenBuffer::finish_tree].
+#[derive(Debug)]
+enum Event<T, N> {
+    Start(N),
+    Token(T),
+    Finish,
+}
+
+impl<L, T, K, N> TokenBuffer<L, T, K, N>
+where
+    L: Iterator<Item = Result<T>>,
+    T: Located + Clone,
+    K: TokenKind,
+{
+    /// The nesting depth a new TokenBuffer allows, which is shallow enough to be safe on small
+    /// thread stacks.
+    pub const DEFAULT_DEPTH_LIMIT: usize = 256;
+
+    /// Creates a new TokenBuffer over the provided lexer.
+    pub fn new(lexer: L) -> Self {
+        Self {
+            lexer,
+            tokens: VecDeque::new(),
+            offset: 0,
+            position: 0,
+            previous_end: 0,
+            checkpoints: 0,
+            finished: false,
+            expected: vec![],
+            expected_at: 0,
+            diagnostics: vec![],
+            events: vec![],
+            recording: false,
+            memo: Memo::default(),
+            depth: 0,
+            depth_limit: Self::DEFAULT_DEPTH_LIMIT,
+            trace: None,
+            replacements: vec![],
+        }
+    }
+
+    /// Returns a reference to the underlying lexer.
+    pub fn lexer(&self) -> &L {
+        &self.lexer
+    }
+
+    /// Returns a mutable reference to the underlying lexer. Tokens that have already been buffered
+    /// are unaffected by any changes made to it.
+    pub fn lexer_mut(&mut self) -> &mut L {
+        &mut self.lexer
+    }
+
+    /// Returns the amount of tokens (and lexing errors) consumed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the position the last consumed token ended at, or 0 if none have been consumed.
+    pub fn previous_end(&self) -> usize {
+        self.previous_end
+    }
+
+    /// Records that a token of the provided kind was expected at the current position. Anything
+    /// recorded at a different position is discarded first. The kind is only turned into text if
+    /// an error lists it.
+    pub fn expect(&mut self, kind: K) {
+        self.push_expected(Expected::Kind(kind));
+    }
+
+    /// Records that something described by the provided name, such as a contextual keyword, was
+    /// expected at the current position. See [TokenBuffer::expect].
+    pub fn expect_named(&mut self, name: impl Into<Cow<'static, str>>) {
+        self.push_expected(Expected::Named(name.into()));
+    }
+
+    fn push_expected(&mut self, expected: Expected<K>) {
+        if self.expected_at != self.position {
+            self.expected.clear();
+            self.expected_at = self.position;
+        }
+        if !self.expected.contains(&expected) {
+            self.expected.push(expected);
+        }
+    }
+
+    /// Returns the text of everything that has been expected at the current position, in the
+    /// order it was first recorded.
+    pub fn expected(&self) -> Vec<String> {
+        if self.expected_at != self.position {
+            return vec![];
+        }
+        let mut expected: Vec<String> = vec![];
+        for text in self.expected.iter().map(|expected| match expected {
+            Expected::Kind(kind) => kind.to_string(),
+            Expected::Named(name) => name.to_string(),
+        }) {
+            if !expected.contains(&text) {
+                expected.push(text);
+            }
+        }
+        expected
+    }
+
+    /// Returns how many levels of nesting the parser is currently within.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the deepest level of nesting allowed.
+    pub fn depth_limit(&self) -> usize {
+        self.depth_limit
+    }
+
+    /// Sets the deepest level of nesting allowed. Without the `stacker` feature, a limit that is
+    /// too high for the thread's stack lets deeply nested input overflow it.
+    pub fn set_depth_limit(&mut self, limit: usize) {
+        self.depth_limit = limit;
+    }
+
+    /// Enters one more level of nesting, returning false instead if that would exceed the depth
+    /// limit.
+    pub fn enter(&mut self) -> bool {
+        if self.depth >= self.depth_limit {
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    /// Exits a level of nesting.
+    pub fn exit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// Returns the [Memo] of the results of memoized rules.
+    pub fn memo(&self) -> &Memo {
+        &self.memo
+    }
+
+    /// Returns a mutable reference to the [Memo] of the results of memoized rules.
+    pub fn memo_mut(&mut self) -> &mut Memo {
+        &mut self.memo
+    }
+
+    /// Starts recording a [Trace] of the rules entered, tokens consumed, backtracks and
+    /// diagnostics reported from here on. Tracing is off until this is called.
+    pub fn enable_trace(&mut self) {
+        self.trace.get_or_insert_with(Trace::new);
+    }
+
+    /// Returns if a [Trace] is being recorded.
+    pub fn is_tracing(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// Returns the [Trace] recorded so far, if tracing is enabled.
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    /// Stops tracing, returning the [Trace] that was recorded.
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    /// Adds an event to the [Trace] if tracing is enabled. Events are kept when rewinding, so the
+    /// Trace shows every path that was tried.
+    pub fn record(&mut self, event: TraceEvent) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(event);
+        }
+    }
+
+    /// Adds a diagnostic to the ones collected by this buffer.
+    pub fn report(&mut self, diag: DiagBox) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceEvent::diagnostic(&**diag));
+        }
+        self.diagnostics.push(diag);
+    }
+
+    /// Returns the diagnostics collected so far.
+    pub fn diagnostics(&self) -> &[DiagBox] {
+        &self.diagnostics
+    }
+
+    /// Removes and returns the diagnostics collected so far.
+    pub fn take_diagnostics(&mut self) -> Vec<DiagBox> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Starts a new node of the provided kind in the concrete syntax tree. Every token consumed
+    /// until the matching [TokenBuffer::finish_node] is attached to it.
+    ///
+    /// Recording begins with the first call to this, so the first node started should be the root.
+    pub fn start_node(&mut self, kind: N) {
+        self.recording = true;
+        self.events.push(Event::Start(kind));
+    }
+
+    /// Returns a [NodeCheckpoint] for [TokenBuffer::start_node_at].
+    pub fn node_checkpoint(&self) -> NodeCheckpoint {
+        NodeCheckpoint(self.events.len())
+    }
+
+    /// Starts a new node of the provided kind which wraps everything recorded since the provided
+    /// [NodeCheckpoint] was created. This is useful when a node's kind is only known after its
+    /// first children have been parsed, such as the left side of a binary expression.
+    pub fn start_node_at(&mut self, checkpoint: NodeCheckpoint, kind: N) {
+        let NodeCheckpoint(index) = checkpoint;
+        self.recording = true;
+        self.events.insert(index, Event::Start(kind));
+    }
+
+    /// Finishes the current node in the concrete syntax tree.
+    pub fn finish_node(&mut self) {
+        self.events.push(Event::Finish);
+    }
+
+    /// Builds the recorded concrete syntax tree and stops recording. The text between consumed
+    /// tokens (including any lexing errors and anything left unconsumed) is kept as trivia, so
+    /// the text of the tree is exactly `source`. Trivia is attached to the outermost node it
+    /// falls between tokens of, so only the root begins or ends with trivia.
+    ///
+    /// ### Panics
+    /// Panics if the nodes were not started and finished as a single root.
+    pub fn finish_tree(&mut self, source: &str, file_id: FileId) -> SyntaxNode<N, K>
+    where
+        N: PartialEq,
+        T: Token<K>,
+    {
+        self.recording = false;
+        // Trivia before a node's first token belongs to the node's parent, so each node needs to
+        // know where the next token starts.
+        let mut next_starts = vec![source.len(); self.events.len()];
+        let mut next_start = source.len();
+        for (index, event) in self.events.iter().enumerate().rev() {
+            if let Event::Token(tok) = event {
+                next_start = tok.span().start();
+            }
+            next_starts[index] = next_start;
+        }
+        let mut builder = GreenBuilder::new();
+        let mut cursor = 0;
+        let mut depth = 0;
+        for (event, next_start) in self.events.drain(..).zip(next_starts) {
+            match event {
+                Event::Start(kind) => {
+                    if depth > 0 && cursor < next_start {
+                        builder.trivia(&source[cursor..next_start]);
+                        cursor = next_start;
+                    }
+                    builder.start_node(kind);
+                    depth += 1;
+                }
+                Event::Token(tok) => {
+                    let span = tok.span();
+                    builder.trivia(&source[cursor..span.start()]);
+                    builder.token(tok.kind_ref().clone(), &source[span.start()..span.end()]);
+                    cursor = span.end();
+                }
+                Event::Finish => {
+                    depth -= 1;
+                    if depth == 0 {
+                        builder.trivia(&source[cursor..]);
+                        cursor = source.len();
+                    }
+                    builder.finish_node();
+                }
+            }
+        }
+        SyntaxNode::new_root(builder.finish(), file_id)
+    }
+
+    /// Consumes and returns the next token, or the lexing error in its place.
+    pub fn take(&mut self) -> Option<Result<T>> {
+        self.entry(0)?;
+        let entry = if self.checkpoints == 0 {
+            let entry = self.tokens.pop_front()?;
+            self.offset += 1;
+            entry.map_err(|err| Arc::try_unwrap(err).unwrap_or_else(|err| Shared(err).into()))
+        } else {
+            match self.tokens.get(self.position - self.offset)? {
+                Ok(tok) => Ok(tok.clone()),
+                Err(err) => Err(Shared(err.clone()).into()),
+            }
+        };
+        if let Ok(tok) = &entry {
+            self.previous_end = tok.span().end();
+            if self.recording {
+                self.events.push(Event::Token(tok.clone()));
+            }
+        }
+        self.position += 1;
+        Some(entry)
+    }
+
+    /// Consumes the rest of the input without recording it in the concrete syntax tree, returning
+    /// the lexing errors found along the way.
+    pub fn drain_errors(&mut self) -> Vec<DiagBox> {
+        let recording = std::mem::replace(&mut self.recording, false);
+        let mut errors = vec![];
+        while let Some(entry) = self.take() {
+            if let Err(err) = entry {
+                errors.push(err);
+            }
+        }
+        self.recording = recording;
+        errors
+    }
+
+    /// Returns the token `n` tokens ahead (where 0 is the next token) without consuming anything.
+    ///
+    /// If the next entry in the buffer is a lexing error, it is consumed and returned. A lexing
+    /// error further ahead but before the nth token is returned without being consumed, so that
+    /// lookahead never matches across it, and will be returned again once it is reached.
+    pub fn peek_nth(&mut self, n: usize) -> Result<Option<&T>> {
+        if let Some(err) = self.take_error() {
+            return Err(err);
+        }
+        let mut index = 0;
+        let mut remaining = n;
+        loop {
+            match self.entry(index) {
+                Some(Ok(_)) if remaining == 0 => break,
+                Some(Ok(_)) => remaining -= 1,
+                Some(Err(err)) => return Err(Shared(err.clone()).into()),
+                None => return Ok(None),
+            }
+            index += 1;
+        }
+        Ok(self.entry(index).and_then(|tok| tok.as_ref().ok()))
+    }
+
+    /// Replaces the next `count` tokens with the provided ones, such as to split a `>>` into two
+    /// `>`s or to glue them back into one. The new tokens should cover the same text as the ones
+    /// they replace. Returns false without replacing anything if there are fewer than `count`
+    /// tokens ahead, or if a lexing error is among them.
+    ///
+    /// Rewinding to a [Checkpoint] created before the replacement restores the original tokens.
+    /// Since memoized results may have been produced from the original tokens, they are cleared.
+    pub fn replace_next(&mut self, count: usize, tokens: impl IntoIterator<Item = T>) -> bool {
+        for n in 0..count {
+            if !matches!(self.entry(n), Some(Ok(_))) {
+                return false;
+            }
+        }
+        let index = self.position - self.offset;
+        let removed: Vec<T> = self
+            .tokens
+            .drain(index..index + count)
+            .filter_map(std::result::Result::ok)
+            .collect();
+        let mut inserted = 0;
+        for tok in tokens {
+            self.tokens.insert(index + inserted, Ok(tok));
+            inserted += 1;
+        }
+        if self.checkpoints > 0 {
+            self.replacements.push(Replacement {
+                position: self.position,
+                removed,
+                inserted,
+            });
+        }
+        self.memo.clear_results();
+        true
+    }
+
+    /// Returns the [Span] of the next token, if the next entry is a token.
+    pub fn next_span(&mut self) -> Option<Span> {
+        match self.entry(0) {
+            Some(Ok(tok)) => Some(tok.span()),
+            _ => None,
+        }
+    }
+
+    /// Saves the current position so that it can later be returned to with
+    /// [TokenBuffer::rewind].
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.checkpoints += 1;
+        Checkpoint {
+            position: self.position,
+            previous_end: self.previous_end,
+            diagnostics: self.diagnostics.len(),
+            events: self.events.len(),
+            depth: self.depth,
+            replacements: self.replacements.len(),
+        }
+    }
+
+    /// Returns to the position saved by the provided [Checkpoint]. Any lexing errors consumed
+    /// since then will be returned again once they are reached, any diagnostics reported since
+    /// then are discarded, and any tokens replaced since then are restored.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        let replacements: Vec<_> = self.replacements.drain(checkpoint.replacements..).collect();
+        if !replacements.is_empty() {
+            self.memo.clear_results();
+        }
+        for replacement in replacements.into_iter().rev() {
+            let index = replacement.position - self.offset;
+            self.tokens.drain(index..index + replacement.inserted);
+            for (n, tok) in replacement.removed.into_iter().enumerate() {
+                self.tokens.insert(index + n, Ok(tok));
+            }
+        }
+        self.position = checkpoint.position;
+        self.previous_end = checkpoint.previous_end;
+        self.diagnostics.truncate(checkpoint.diagnostics);
+        self.events.truncate(checkpoint.events);
+        self.depth = checkpoint.depth;
+        self.release(checkpoint);
+    }
+
+    /// Discards the provided [Checkpoint] without moving.
+    pub fn release(&mut self, checkpoint: Checkpoint) {
+        let Checkpoint { .. } = checkpoint;
+        self.checkpoints -= 1;
+        if self.checkpoints == 0 {
+            self.tokens.drain(..(self.position - self.offset));
+            self.offset = self.position;
+            self.replacements.clear();
+        }
+    }
+
+    /// Returns the entry `n` entries after the current position, lexing it if needed.
+    fn entry(&mut self, n: usize) -> Option<&std::result::Result<T, Arc<DiagBox>>> {
+        let index = self.position - self.offset + n;
+        while self.tokens.len() <= index && !self.finished {
+            match self.lexer.next() {
+                Some(tok) => self.tokens.push_back(tok.map_err(Arc::new)),
+                None => self.finished = true,
+            }
+        }
+        self.tokens.get(index)
+    }
+
+    /// Consumes and returns the next entry if it is a lexing error.
+    fn take_error(&mut self) -> Option<DiagBox> {
+        if !matches!(self.entry(0), Some(Err(_))) {
+            return None;
+        }
+        self.take()?.err()
+    }
+}
+
+/// A diagnostic which may be handed out more than once, such as a lexing error handed out while a
+/// [Checkpoint] was outstanding, or a failure stored in the [Memo].
+pub(super) struct Shared(pub(super) Arc<DiagBox>);
+
+impl Diag for Shared {
+    fn severity(&self) -> Severity {
+        self.0.severity()
+    }
+
+    fn build(&self, builder: Builder) -> Builder {
+        self.0.build(builder)
+    }
+}
+
+impl std::fmt::Debug for Shared {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
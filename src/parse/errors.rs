@@ -1,5 +1,5 @@
 use crate::{
-    define_error,
+    define_error, define_warning,
     diagnostics::Builder,
     utils::{Located, Location},
 };
@@ -32,3 +32,186 @@ define_error!(
         }
     }
 );
+
+/// Parsing error when a contextual keyword (an identifier with a particular text) was expected
+/// but something else was found.
+pub struct ExpectedKeyword(pub String, pub Location);
+
+define_error!(
+    ExpectedKeyword {
+        fn build(&self, builder: Builder) -> Builder {
+            builder.label(self.1.primary(format!("expected the keyword '{}' here", self.0)))
+        }
+
+        fn location(&self) -> Location {
+            self.1
+        }
+    }
+);
+
+/// Parsing error when an expression was expected but something else was found.
+pub struct ExpectedExpression(pub Location);
+
+define_error!(
+    ExpectedExpression {
+        fn build(&self, builder: Builder) -> Builder {
+            builder.label(self.0.primary("expected an expression here"))
+        }
+
+        fn location(&self) -> Location {
+            self.0
+        }
+    }
+);
+
+/// Parsing error when none of the tokens that could have appeared at a position were found. The
+/// first field lists the alternatives that were tried and the second is what was found instead,
+/// or None if the end of the file was reached.
+pub struct ExpectedOneOf(pub Vec<String>, pub Option<String>, pub Location);
+
+define_error!(
+    ExpectedOneOf {
+        fn build(&self, builder: Builder) -> Builder {
+            let found = match &self.1 {
+                Some(found) => format!("'{found}'"),
+                None => "the end of the file".into(),
+            };
+            let message = match self.0.as_slice() {
+                [] => format!("unexpected {found}"),
+                [expected] => format!("expected '{expected}', found {found}"),
+                expected => {
+                    let expected: Vec<String> =
+                        expected.iter().map(|kind| format!("'{kind}'")).collect();
+                    format!("expected one of {}, found {found}", expected.join(", "))
+                }
+            };
+            builder.label(self.2.primary(message))
+        }
+
+        fn location(&self) -> Location {
+            self.2
+        }
+    }
+);
+
+/// Parsing error when a delimited group is never closed. The first field is the expected closing
+/// delimiter, the second is where it was expected and the third is the opening delimiter.
+pub struct UnclosedDelimiter(pub String, pub Location, pub Location);
+
+define_error!(
+    UnclosedDelimiter {
+        fn build(&self, builder: Builder) -> Builder {
+            builder
+                .label(self.1.primary(format!("expected '{}' to close the delimiter", self.0)))
+                .label(self.2.secondary("unclosed delimiter opened here"))
+        }
+
+        fn location(&self) -> Location {
+            self.1
+        }
+    }
+);
+
+/// Parsing error when a closing delimiter does not match the opening delimiter before it. The
+/// first field is the closing delimiter that was expected, the second is the one found and the
+/// third is the opening delimiter.
+pub struct MismatchedDelimiter(pub String, pub Location, pub Location);
+
+define_error!(
+    MismatchedDelimiter {
+        fn build(&self, builder: Builder) -> Builder {
+            builder
+                .label(self.1.primary(format!("expected '{}' to close the delimiter", self.0)))
+                .label(self.2.secondary("opened here"))
+        }
+
+        fn location(&self) -> Location {
+            self.1
+        }
+    }
+);
+
+/// Parsing error when a closing delimiter has no opening delimiter.
+pub struct UnmatchedDelimiter(pub Location);
+
+define_error!(
+    UnmatchedDelimiter {
+        fn build(&self, builder: Builder) -> Builder {
+            builder.label(self.0.primary("this delimiter was never opened"))
+        }
+
+        fn location(&self) -> Location {
+            self.0
+        }
+    }
+);
+
+/// Parsing warning for a separator at the end of a list that does not allow one.
+pub struct TrailingSeparator(pub Location);
+
+define_warning!(
+    TrailingSeparator {
+        fn build(&self, builder: Builder) -> Builder {
+            builder.label(self.0.primary("trailing separator is not allowed here"))
+        }
+
+        fn location(&self) -> Location {
+            self.0
+        }
+    }
+);
+
+/// Parsing error when a memoized rule is reached again at the same position while it is still
+/// running, and so can only succeed through one of its other alternatives.
+pub struct LeftRecursion(pub String, pub Location);
+
+define_error!(
+    LeftRecursion {
+        fn build(&self, builder: Builder) -> Builder {
+            let message =
+                format!("'{}' recursed into itself here without consuming anything", self.0);
+            builder.label(self.1.primary(message))
+        }
+
+        fn location(&self) -> Location {
+            self.1
+        }
+    }
+);
+
+/// Parsing error when the input is nested deeper than the parser allows. The first field is the
+/// depth limit.
+pub struct NestingTooDeep(pub usize, pub Location);
+
+define_error!(
+    NestingTooDeep {
+        fn build(&self, builder: Builder) -> Builder {
+            let message = format!("this is nested more than {} levels deep", self.0);
+            builder.label(self.1.primary(message))
+        }
+
+        fn location(&self) -> Location {
+            self.1
+        }
+    }
+);
+
+/// Parsing error when a non-associative operator, such as a comparison, follows another operator
+/// of the same precedence, as in `a < b < c`. The fields are the operator, its location, and the
+/// location of the expression before it.
+pub struct ChainedComparison(pub String, pub Location, pub Location);
+
+define_error!(
+    ChainedComparison {
+        fn build(&self, builder: Builder) -> Builder {
+            let message = format!("'{}' cannot be chained with the operator before it", self.0);
+            builder
+                .label(self.1.primary(message))
+                .label(self.2.secondary("wrap this in parentheses to use it as the operand"))
+        }
+
+        fn location(&self) -> Location {
+            self.1
+        }
+    }
+);
@@ -0,0 +1,30 @@
+use crate::utils::{Located, Location};
+
+/// How a separated list treats a separator after its final item.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum Trailing {
+    /// A trailing separator is reported with a [super::TrailingSeparator] warning.
+    Forbid,
+    /// A trailing separator may or may not be present.
+    #[default]
+    Allow,
+    /// Every item, including the final one, must be followed by a separator.
+    Require,
+}
+
+/// The items parsed by one of the list helpers on [super::Parse], along with the [Location] of the
+/// entire list.
+#[derive(Debug, PartialEq, Clone)]
+pub struct List<R> {
+    /// The parsed items, in order.
+    pub items: Vec<R>,
+    /// The [Location] from the start of the first item to the end of the list's final Token. An
+    /// empty list has an empty Location where it would have started.
+    pub location: Location,
+}
+
+impl<R> Located for List<R> {
+    fn location(&self) -> Location {
+        self.location
+    }
+}
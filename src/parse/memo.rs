@@ -0,0 +1,173 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::Arc,
+};
+
+use crate::diagnostics::{DiagBox, Result};
+
+use super::buffer::Shared;
+
+/// A packrat memo table storing the results of rules run through [super::Parse::memoize], keyed
+/// by the rule's name, its result type and the token position it started at, along with statistics
+/// on how often those results were reused.
+#[derive(Debug, Default)]
+pub struct Memo {
+    entries: HashMap<(&'static str, TypeId, usize), Entry>,
+    stats: HashMap<&'static str, RuleStats>,
+}
+
+/// Statistics for a single memoized rule.
+#[derive(Debug, PartialEq, Eq, Default, Copy, Clone)]
+pub struct RuleStats {
+    /// The amount of times a stored result was reused.
+    pub hits: usize,
+    /// The amount of times the rule had to be run.
+    pub misses: usize,
+    /// The amount of times a left recursive result was grown by running the rule again.
+    pub grown: usize,
+}
+
+impl RuleStats {
+    /// Returns the fraction of lookups that reused a stored result, or 0 if there were none.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+/// Where the parser ended up after a memoized rule ran.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub(super) struct End {
+    pub(super) position: usize,
+    pub(super) cursor: usize,
+}
+
+#[derive(Debug)]
+pub(super) enum Entry {
+    /// The rule is currently running at this position. `recursed` is set if it was looked up again
+    /// at the same position before finishing, which means it is left recursive.
+    Pending { recursed: bool },
+    Done {
+        result: std::result::Result<Box<dyn Any>, Arc<DiagBox>>,
+        end: End,
+    },
+}
+
+impl Memo {
+    /// Returns the statistics for the rule with the provided name, if it has been run.
+    pub fn stats(&self, rule: &str) -> Option<&RuleStats> {
+        self.stats.get(rule)
+    }
+
+    /// Returns the statistics for every rule that has been run.
+    pub fn all_stats(&self) -> impl Iterator<Item = (&'static str, &RuleStats)> {
+        self.stats.iter().map(|(rule, stats)| (*rule, stats))
+    }
+
+    /// Returns the statistics of every rule combined.
+    pub fn total_stats(&self) -> RuleStats {
+        self.stats
+            .values()
+            .fold(RuleStats::default(), |total, stats| RuleStats {
+                hits: total.hits + stats.hits,
+                misses: total.misses + stats.misses,
+                grown: total.grown + stats.grown,
+            })
+    }
+
+    /// Returns the amount of stored results.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns if no results are stored.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes every stored result, keeping the statistics. This must be done if the tokens the
+    /// results were produced from change.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Removes every finished result, keeping the rules that are still being parsed and the
+    /// statistics. Used when the buffered tokens are replaced.
+    pub(super) fn clear_results(&mut self) {
+        self.entries
+            .retain(|_, entry| matches!(entry, Entry::Pending { .. }));
+    }
+
+    pub(super) fn get_mut<R: 'static>(
+        &mut self,
+        rule: &'static str,
+        position: usize,
+    ) -> Option<&mut Entry> {
+        self.entries.get_mut(&(rule, TypeId::of::<R>(), position))
+    }
+
+    pub(super) fn insert<R: 'static>(&mut self, rule: &'static str, position: usize, entry: Entry) {
+        self.entries
+            .insert((rule, TypeId::of::<R>(), position), entry);
+    }
+
+    pub(super) fn rule_stats(&mut self, rule: &'static str) -> &mut RuleStats {
+        self.stats.entry(rule).or_default()
+    }
+
+    /// Stores a finished result, returning a copy of it.
+    pub(super) fn finish<R: Clone + 'static>(
+        &mut self,
+        rule: &'static str,
+        position: usize,
+        result: Result<R>,
+        end: End,
+    ) -> Result<R> {
+        let (stored, result) = match result {
+            Ok(value) => (Ok(Box::new(value.clone()) as Box<dyn Any>), Ok(value)),
+            Err(err) => {
+                let err = Arc::new(err);
+                (Err(err.clone()), Err(Shared(err).into()))
+            }
+        };
+        self.insert::<R>(
+            rule,
+            position,
+            Entry::Done {
+                result: stored,
+                end,
+            },
+        );
+        result
+    }
+}
+
+impl Entry {
+    /// Returns where the rule finished along with a copy of the stored result.
+    ///
+    /// ### Panics
+    /// Panics if the rule is still pending, or if the stored result is not an `R` (which can't
+    /// happen for an entry found by [Memo::get_mut] with the same `R`).
+    pub(super) fn result<R: Clone + 'static>(&self) -> (End, Result<R>) {
+        match self {
+            Entry::Done {
+                result: Ok(value),
+                end,
+            } => (
+                *end,
+                Ok(value
+                    .downcast_ref::<R>()
+                    .expect("entries are keyed by their result type")
+                    .clone()),
+            ),
+            Entry::Done {
+                result: Err(err),
+                end,
+            } => (*end, Err(Shared(err.clone()).into())),
+            Entry::Pending { .. } => unreachable!("pending rules have no result"),
+        }
+    }
+}
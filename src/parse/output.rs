@@ -0,0 +1,61 @@
+use crate::diagnostics::{DiagBox, Severity};
+
+/// The result of an entire parse, as returned by [super::Parse::run]. Unlike a
+/// [crate::diagnostics::Result], a ParseOutput keeps whatever was built alongside every diagnostic
+/// reported, so tooling can still work with a file that has errors in it.
+///
+/// `value` is None only if the parse failed outright. Rules that recover from errors with
+/// [super::Parse::recover] or [super::Parse::recover_list] leave placeholders in the value
+/// instead, so it may be Some even when [ParseOutput::has_errors] is true.
+#[derive(Debug)]
+pub struct ParseOutput<T> {
+    /// The value that was parsed, or None if the parse failed.
+    pub value: Option<T>,
+    /// Every error and warning from both the lexer and the parser. Those reported while parsing
+    /// come first, in the order they were reported, followed by the ones the lexer collected
+    /// itself, so the diagnostics are not necessarily in the order of their locations.
+    pub diagnostics: Vec<DiagBox>,
+}
+
+impl<T> ParseOutput<T> {
+    /// Creates a new ParseOutput.
+    pub fn new(value: Option<T>, diagnostics: Vec<DiagBox>) -> Self {
+        Self { value, diagnostics }
+    }
+
+    /// Returns if any of the diagnostics are errors or bugs rather than warnings.
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    /// Returns an iterator over the diagnostics that are errors or bugs.
+    pub fn errors(&self) -> impl Iterator<Item = &DiagBox> {
+        self.diagnostics
+            .iter()
+            .filter(|diag| diag.severity() != Severity::Warning)
+    }
+
+    /// Returns an iterator over the diagnostics that are warnings.
+    pub fn warnings(&self) -> impl Iterator<Item = &DiagBox> {
+        self.diagnostics
+            .iter()
+            .filter(|diag| diag.severity() == Severity::Warning)
+    }
+
+    /// Maps the parsed value, keeping the diagnostics as they are.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> ParseOutput<U> {
+        ParseOutput {
+            value: self.value.map(f),
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    /// Returns the parsed value if there were no errors, or the diagnostics otherwise. Warnings
+    /// are discarded when the parse succeeded.
+    pub fn into_result(self) -> Result<T, Vec<DiagBox>> {
+        match self.value {
+            Some(value) if !self.has_errors() => Ok(value),
+            _ => Err(self.diagnostics),
+        }
+    }
+}
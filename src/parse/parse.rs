@@ -1,22 +1,35 @@
-use std::iter::Peekable;
-
 use crate::{
-    diagnostics::Result,
-    lex::{Lex, Token, TokenKind},
-    utils::{FileId, Location, Span},
+    cst::SyntaxNode,
+    diagnostics::{DiagBox, Result},
+    lex::{Lex, Tok, Token, TokenKind},
+    utils::{FileId, Location, Span, Spanned},
 };
 
-use super::errors::{ExpectedToken, UnexpectedEnd};
+use super::{
+    Assoc, Checkpoint, List, NodeCheckpoint, ParseOutput, Precedence, TokenBuffer, TraceEvent,
+    Trailing,
+    errors::{
+        ChainedComparison, ExpectedKeyword, ExpectedOneOf, ExpectedToken, LeftRecursion,
+        NestingTooDeep, TrailingSeparator, UnclosedDelimiter, UnexpectedEnd,
+    },
+    memo::{End, Entry},
+};
 
 /// A set of utilities to create a parser.
-pub trait Parse<'s, L, T, K>
+///
+/// The Token type must implement [Clone], as the [TokenBuffer] clones tokens out of its buffer
+/// when a parser may still rewind to before them.
+///
+/// The nodes of the concrete syntax tree a parser records are all of kind `N`, which can be left
+/// as `()` by parsers that never build one.
+pub trait Parse<'s, L, T, K, N = ()>
 where
     L: Lex<'s, T, K> + Iterator<Item = Result<T>>,
-    T: Token<K>,
+    T: Token<K> + Clone,
     K: TokenKind + 's,
 {
-    /// Returns a mutable reference to the internal lexer.
-    fn lexer(&mut self) -> &mut Peekable<L>;
+    /// Returns a mutable reference to the [TokenBuffer] over the internal lexer.
+    fn lexer(&mut self) -> &mut TokenBuffer<L, T, K, N>;
 
     /// Returns the current [FileId].
     fn file_id(&self) -> FileId;
@@ -30,49 +43,114 @@ where
 
     /// Consumes and returns the next tok if it is the given type.
     fn match_take(&mut self, tok_kind: K) -> Option<T> {
-        match self.peek() {
-            Ok(peek) if peek.kind_ref() == &tok_kind => Some(self.take().unwrap()),
-            Err(_) => None,
-            _ => None,
-        }
+        let matched = matches!(self.peek(), Ok(peek) if peek.kind_ref() == &tok_kind);
+        self.lexer().expect(tok_kind);
+        matched.then(|| self.take().unwrap())
     }
 
     /// Returns the next Token, returning an error if there is none, or if it is
-    /// not of the required type.
+    /// not of the required type. If other kinds were already tried at this position, the error
+    /// lists all of them.
     fn require(&mut self, expected_type: K) -> Result<T> {
+        self.lexer().expect(expected_type.clone());
+        let expected = self.lexer().expected();
         let found_tok = self.take()?;
         if found_tok.kind_ref() == &expected_type {
             Ok(found_tok)
+        } else if expected.len() > 1 {
+            let found = Some(found_tok.kind_ref().to_string());
+            Err(ExpectedOneOf(expected, found, found_tok.location()).into())
         } else {
             Err(ExpectedToken(expected_type.to_string(), found_tok.location()).into())
         }
     }
 
+    /// Returns if the next Token is of the given type without advancing the cursor. The kind is
+    /// recorded as expected here, as with [Parse::match_take].
+    fn check(&mut self, tok_kind: K) -> bool {
+        let matched = self
+            .soft_peek()
+            .is_ok_and(|tok| tok.is_some_and(|tok| tok.kind_ref() == &tok_kind));
+        self.lexer().expect(tok_kind);
+        matched
+    }
+
+    /// Consumes and returns the next tok if it is an identifier with the provided text, such as a
+    /// contextual keyword that is otherwise a valid identifier. Identifiers are found through
+    /// [TokenKind::ident]. The keyword is recorded as expected here, as with [Parse::match_take].
+    fn match_contextual(&mut self, keyword: &str) -> Option<T> {
+        self.check_contextual(keyword).then(|| self.take().unwrap())
+    }
+
+    /// Returns the next Token if it is an identifier with the provided text, returning an error
+    /// mentioning the keyword if it is not (or if there is none). As with [Parse::require], if
+    /// other kinds were already tried at this position, the error lists all of them.
+    fn require_contextual(&mut self, keyword: &str) -> Result<T> {
+        self.lexer().expect_named(keyword.to_string());
+        let expected = self.lexer().expected();
+        let found_tok = self.take()?;
+        if found_tok.kind_ref().ident() == Some(keyword) {
+            Ok(found_tok)
+        } else if expected.len() > 1 {
+            let found = Some(found_tok.kind_ref().to_string());
+            Err(ExpectedOneOf(expected, found, found_tok.location()).into())
+        } else {
+            Err(ExpectedKeyword(keyword.to_string(), found_tok.location()).into())
+        }
+    }
+
+    /// Returns if the next Token is an identifier with the provided text without advancing the
+    /// cursor. The keyword is recorded as expected here, as with [Parse::match_take].
+    fn check_contextual(&mut self, keyword: &str) -> bool {
+        let matched = self
+            .soft_peek()
+            .is_ok_and(|tok| tok.is_some_and(|tok| tok.kind_ref().ident() == Some(keyword)));
+        // The keyword's text is only copied when it could end up in an error.
+        if !matched {
+            self.lexer().expect_named(keyword.to_string());
+        }
+        matched
+    }
+
+    /// Creates an [ExpectedOneOf] error listing every kind of Token that was tried at the current
+    /// position (through [Parse::match_take], [Parse::require], [Parse::check] and their contextual
+    /// keyword counterparts) along with the Token that was found instead. Returns the lexing error
+    /// instead if the next Token could not be lexed.
+    fn expected_one_of(&mut self) -> DiagBox {
+        let expected = self.lexer().expected();
+        let cursor = self.cursor();
+        let file_id = self.file_id();
+        match self.soft_peek() {
+            Ok(Some(tok)) => {
+                let found = Some(tok.kind_ref().to_string());
+                ExpectedOneOf(expected, found, tok.location()).into()
+            }
+            Ok(None) => {
+                let location = Location::new(file_id, Span::new(cursor, cursor));
+                ExpectedOneOf(expected, None, location).into()
+            }
+            Err(err) => err,
+        }
+    }
+
     /// Get the fog toks's cursor.
     fn next_tok_boundary(&mut self) -> usize {
         let cursor = self.cursor();
-        self.lexer().peek().map_or(cursor, |tok| {
-            tok.as_ref().map_or(cursor, |tok| tok.span().start())
-        })
+        self.lexer().next_span().map_or(cursor, |span| span.start())
     }
 
     /// Returns the the next Token (or an error if there is none) without advancing the cursor.
     fn peek<'a>(&'a mut self) -> Result<&'a T>
     where
         L: 'a,
+        N: 'a,
         's: 'a,
     {
         let next = self.next_tok_boundary();
         let location = self.location(next);
-
-        if self.lexer().peek().is_some_and(|v| v.is_err()) {
-            return Err(self.take().unwrap_err());
-        }
-
-        match self.lexer().peek() {
-            Some(Ok(tok)) => Ok(tok),
+        match self.lexer().peek_nth(0)? {
+            Some(tok) => Ok(tok),
             None => Err(UnexpectedEnd(location).into()),
-            _ => unreachable!(),
         }
     }
 
@@ -80,27 +158,128 @@ where
     fn soft_peek<'a>(&'a mut self) -> Result<Option<&'a T>>
     where
         L: 'a,
+        N: 'a,
         's: 'a,
     {
-        if self.lexer().peek().is_some_and(|v| v.is_err()) {
-            return Err(self.take().unwrap_err());
-        }
+        self.lexer().peek_nth(0)
+    }
+
+    /// Returns the Token `n` tokens ahead (where 0 is the next Token) or None without advancing
+    /// the cursor. A lexing error before that Token is returned instead. See
+    /// [TokenBuffer::peek_nth].
+    fn peek_nth<'a>(&'a mut self, n: usize) -> Result<Option<&'a T>>
+    where
+        L: 'a,
+        N: 'a,
+        's: 'a,
+    {
+        self.lexer().peek_nth(n)
+    }
+
+    /// Returns if the upcoming Tokens are of the provided kinds, in order, without advancing the
+    /// cursor. A lexing error among them is a mismatch.
+    fn match_sequence(&mut self, kinds: &[K]) -> bool {
+        kinds.iter().enumerate().all(|(n, kind)| {
+            self.peek_nth(n)
+                .is_ok_and(|tok| tok.is_some_and(|tok| tok.kind_ref() == kind))
+        })
+    }
 
-        match self.lexer().peek() {
-            Some(Ok(tok)) => Ok(Some(tok)),
-            None => Ok(None),
-            _ => unreachable!(),
+    /// Returns if the tokens `n` and `n + 1` tokens ahead are joint, with nothing (not even
+    /// whitespace) between them, such as the two `>`s of `>>` but not of `> >`. Tokens with a
+    /// lexing error before or between them are not joint, and only a lexing error in place of the
+    /// next Token is returned.
+    fn is_joint(&mut self, n: usize) -> Result<bool> {
+        self.soft_peek()?;
+        let end = match self.peek_nth(n) {
+            Ok(Some(tok)) => tok.span().end(),
+            _ => return Ok(false),
+        };
+        Ok(matches!(self.peek_nth(n + 1), Ok(Some(tok)) if tok.span().start() == end))
+    }
+
+    /// Splits the next token into tokens of the provided kinds and lengths, such as a `>>` into two
+    /// `>`s when it closes nested generics. Each part gets the sub-[Span] of the token at its
+    /// position. Returns false without changing anything if there is no next token.
+    ///
+    /// The parts are built as [Tok]s, so a custom [Token] type must implement `From<Tok<K>>`.
+    /// Like every replacement made by [TokenBuffer::replace_next], the split is undone if the
+    /// parser rewinds to before it.
+    ///
+    /// ### Panics
+    /// Panics if the lengths of the parts do not add up to the length of the token.
+    fn split_next(&mut self, parts: &[(K, usize)]) -> Result<bool>
+    where
+        T: From<Tok<K>>,
+    {
+        let Some(tok) = self.soft_peek()? else {
+            return Ok(false);
+        };
+        let location = tok.location();
+        let span = location.span();
+        assert_eq!(
+            span.end() - span.start(),
+            parts.iter().map(|(_, len)| len).sum::<usize>(),
+            "the parts of a split token must cover all of it"
+        );
+        let mut start = span.start();
+        let tokens: Vec<T> = parts
+            .iter()
+            .map(|(kind, len)| {
+                let location = Location::new(location.file_id(), Span::new(start, start + len));
+                start += len;
+                Tok::new(kind.clone(), location).into()
+            })
+            .collect();
+        Ok(self.lexer().replace_next(1, tokens))
+    }
+
+    /// Glues the next tokens into a single token of the provided kind if they are of the provided
+    /// kinds and are all joint (see [Parse::is_joint]), such as two `>`s into a `>>`. Returns if
+    /// the tokens were glued, which they never are if `kinds` is empty.
+    ///
+    /// As with [Parse::split_next], a custom [Token] type must implement `From<Tok<K>>`, and the
+    /// tokens are restored if the parser rewinds to before they were glued.
+    fn glue_next(&mut self, kinds: &[K], kind: K) -> Result<bool>
+    where
+        T: From<Tok<K>>,
+    {
+        let Some(first) = self.soft_peek()? else {
+            return Ok(false);
+        };
+        if kinds.is_empty() {
+            return Ok(false);
+        }
+        let file_id = first.location().file_id();
+        let start = first.span().start();
+        let mut end = start;
+        for (n, expected) in kinds.iter().enumerate() {
+            match self.peek_nth(n) {
+                Ok(Some(tok)) if tok.kind_ref() == expected && tok.span().start() == end => {
+                    end = tok.span().end();
+                }
+                _ => return Ok(false),
+            }
         }
+        let glued = Tok::new(kind, Location::new(file_id, Span::new(start, end)));
+        Ok(self.lexer().replace_next(kinds.len(), [glued.into()]))
     }
 
     /// Returns the next Token, returning an error if there is none.
     fn take(&mut self) -> Result<T> {
         let next = self.next_tok_boundary();
         let location = self.location(next);
-        match self.lexer().next() {
+        match self.lexer().take() {
             Some(Ok(tok)) => {
-                let boundary = self.next_tok_boundary();
+                let end = tok.span().end();
+                let boundary = self.lexer().next_span().map_or(end, |span| span.start());
                 self.set_cursor(boundary);
+                if self.lexer().is_tracing() {
+                    self.lexer().record(TraceEvent::Token {
+                        kind: tok.kind_ref().to_string(),
+                        location: tok.location(),
+                    });
+                }
                 Ok(tok)
             }
             Some(Err(err)) => Err(err),
@@ -108,6 +287,499 @@ where
         }
     }
 
+    /// Runs the provided sub-parse speculatively. If it returns an error, the lexer and cursor are
+    /// restored to where they were before it ran and the error is returned, allowing another
+    /// production to be tried from the same position.
+    fn attempt<R, F>(&mut self, f: F) -> Result<R>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        let checkpoint = self.lexer().checkpoint();
+        let cursor = self.cursor();
+        match f(self) {
+            Ok(value) => {
+                self.lexer().release(checkpoint);
+                Ok(value)
+            }
+            Err(err) => {
+                backtrack(self, checkpoint, cursor);
+                Err(err)
+            }
+        }
+    }
+
+    /// Runs the provided sub-parse as the rule with the provided name. While the [TokenBuffer] is
+    /// recording a [super::Trace], entering and leaving the rule are recorded, so that everything
+    /// recorded within it is nested beneath it.
+    fn trace<R, F>(&mut self, rule: &'static str, f: F) -> Result<R>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        if !self.lexer().is_tracing() {
+            return f(self);
+        }
+        let start = self.next_tok_boundary();
+        let file_id = self.file_id();
+        let location = Location::new(file_id, Span::new(start, start));
+        self.lexer().record(TraceEvent::Enter { rule, location });
+        let result = f(self);
+        let end = self.lexer().previous_end().max(start);
+        self.lexer().record(TraceEvent::Exit {
+            rule,
+            location: Location::new(file_id, Span::new(start, end)),
+            error: result
+                .as_ref()
+                .err()
+                .map(|err| TraceEvent::message(&***err)),
+        });
+        result
+    }
+
+    /// Runs the provided sub-parse, wrapping its result in a [Spanned] whose [Location] covers
+    /// everything it consumed: from the start of its first Token to the end of its last. A
+    /// sub-parse that consumes nothing gets an empty Location where its first Token would have
+    /// been.
+    fn spanned<R, F>(&mut self, f: F) -> Result<Spanned<R>>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        let start = self.next_tok_boundary();
+        let value = f(self)?;
+        let end = self.lexer().previous_end().max(start);
+        Ok(Spanned::new(
+            value,
+            Location::new(self.file_id(), Span::new(start, end)),
+        ))
+    }
+
+    /// Parses a group surrounded by the `open` and `close` delimiters, running the provided
+    /// sub-parse between them. If the closing delimiter is not found after the sub-parse, an
+    /// [UnclosedDelimiter] error points at where it was expected as well as at the opening
+    /// delimiter. The unexpected Token is not consumed.
+    ///
+    /// If the sub-parse fails because the file ended within the group, that is reported as an
+    /// [UnclosedDelimiter] as well. When groups are nested, the outermost one is reported.
+    fn delimited<R, F>(&mut self, open: K, close: K, f: F) -> Result<R>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        let open_tok = self.require(open)?;
+        let unclosed = |p: &mut Self| {
+            let end = p.lexer().previous_end();
+            let location = Location::new(p.file_id(), Span::new(end, end));
+            UnclosedDelimiter(close.to_string(), location, open_tok.location())
+        };
+        let value = match f(self) {
+            Ok(value) => value,
+            Err(_) if matches!(self.soft_peek(), Ok(None)) => return Err(unclosed(self).into()),
+            Err(err) => return Err(err),
+        };
+        if self.match_take(close.clone()).is_some() {
+            return Ok(value);
+        }
+        match self.soft_peek()? {
+            Some(tok) => {
+                let location = tok.location();
+                Err(UnclosedDelimiter(close.to_string(), location, open_tok.location()).into())
+            }
+            None => Err(unclosed(self).into()),
+        }
+    }
+
+    /// Parses zero or more items separated by `separator`, stopping before `end` (which is not
+    /// consumed) or the end of the file. The `trailing` policy decides how a separator after the
+    /// final item is treated.
+    fn sep_by<R, F>(&mut self, separator: K, end: K, trailing: Trailing, f: F) -> Result<List<R>>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self) -> Result<R>,
+    {
+        let start = self.next_tok_boundary();
+        if self.check(end.clone()) || self.soft_peek()?.is_none() {
+            return Ok(List {
+                items: vec![],
+                location: Location::new(self.file_id(), Span::new(start, start)),
+            });
+        }
+        self.sep_by1(separator, end, trailing, f)
+    }
+
+    /// Parses one or more items separated by `separator`, stopping before `end` (which is not
+    /// consumed) or the end of the file. The `trailing` policy decides how a separator after the
+    /// final item is treated.
+    fn sep_by1<R, F>(
+        &mut self,
+        separator: K,
+        end: K,
+        trailing: Trailing,
+        mut f: F,
+    ) -> Result<List<R>>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self) -> Result<R>,
+    {
+        let start = self.next_tok_boundary();
+        let mut items = vec![];
+        loop {
+            items.push(f(self)?);
+            match self.match_take(separator.clone()) {
+                Some(tok) => {
+                    if self.check(end.clone()) || self.soft_peek()?.is_none() {
+                        if trailing == Trailing::Forbid {
+                            self.report(TrailingSeparator(tok.location()));
+                        }
+                        break;
+                    }
+                }
+                None if trailing == Trailing::Require => return Err(self.expected_one_of()),
+                None => break,
+            }
+        }
+        let end = self.lexer().previous_end();
+        Ok(List {
+            items,
+            location: Location::new(self.file_id(), Span::new(start, end)),
+        })
+    }
+
+    /// Parses zero or more items that are each followed by `terminator`, such as statements ending
+    /// in `;`, stopping before `end` (which is not consumed) or the end of the file.
+    fn terminated_by<R, F>(&mut self, terminator: K, end: K, f: F) -> Result<List<R>>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self) -> Result<R>,
+    {
+        self.sep_by(terminator, end, Trailing::Require, f)
+    }
+
+    /// Parses zero or more items with nothing between them, stopping before `end` (which is not
+    /// consumed) or the end of the file.
+    fn many_until<R, F>(&mut self, end: K, mut f: F) -> Result<List<R>>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self) -> Result<R>,
+    {
+        let start = self.next_tok_boundary();
+        let mut items = vec![];
+        while !self.check(end.clone()) && self.soft_peek()?.is_some() {
+            items.push(f(self)?);
+        }
+        let end = self.lexer().previous_end().max(start);
+        Ok(List {
+            items,
+            location: Location::new(self.file_id(), Span::new(start, end)),
+        })
+    }
+
+    /// Parses a binary expression using a table of operator precedences, ordered from the loosest
+    /// binding level to the tightest (such as `||`, then `&&`, then `==`, then `+`, then `*`).
+    /// `operand` parses what sits between the operators, and `build` combines two operands and the
+    /// operator between them, given the [Location] spanning the whole expression it builds.
+    ///
+    /// Each level is grouped according to its [Assoc]. A second operator on an [Assoc::Neither]
+    /// level, as in `a < b < c`, is reported as a [ChainedComparison] error, and the chain is
+    /// parsed as if the level were left-associative.
+    ///
+    /// For anything more involved, such as prefix or postfix operators, see [super::Pratt].
+    fn binary<V, F, B>(
+        &mut self,
+        table: &[Precedence<K>],
+        mut operand: F,
+        mut build: B,
+    ) -> Result<V>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self) -> Result<V>,
+        B: FnMut(&mut Self, V, T, V, Location) -> Result<V>,
+    {
+        self.nested(|parser| binary_level(parser, table, &mut operand, &mut build))
+    }
+
+    /// Enters one more level of nesting, returning a [NestingTooDeep] error at the next Token if
+    /// that would exceed the [TokenBuffer]'s depth limit. Every successful call must be matched by
+    /// a call to [Parse::exit]; [Parse::nested] does both.
+    fn enter(&mut self) -> Result<()> {
+        if self.lexer().enter() {
+            return Ok(());
+        }
+        let limit = self.lexer().depth_limit();
+        let location = match self.soft_peek() {
+            Ok(Some(tok)) => tok.location(),
+            _ => self.location(self.cursor()),
+        };
+        Err(NestingTooDeep(limit, location).into())
+    }
+
+    /// Exits a level of nesting entered with [Parse::enter].
+    fn exit(&mut self) {
+        self.lexer().exit();
+    }
+
+    /// Runs the provided sub-parse one level of nesting deeper, failing with [NestingTooDeep]
+    /// instead if that would exceed the [TokenBuffer]'s depth limit. Recursive rules should call
+    /// themselves through this so that pathological input cannot overflow the stack.
+    ///
+    /// With the `stacker` feature, the stack is also grown on demand, allowing a much higher limit.
+    fn nested<R, F>(&mut self, f: F) -> Result<R>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        self.enter()?;
+        #[cfg(feature = "stacker")]
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || f(self));
+        #[cfg(not(feature = "stacker"))]
+        let result = f(self);
+        self.exit();
+        result
+    }
+
+    /// Runs the provided rule through the packrat [super::Memo], so that running it again at the
+    /// same position reuses the stored result (or failure) and skips straight to where it ended.
+    /// `rule` names the rule, and must always be used with the same rule. Results are stored
+    /// separately for each result type, so rules with different result types may share a name.
+    ///
+    /// Like [Parse::attempt], a rule that fails is rewound. Direct left recursion is supported by
+    /// growing a seed: a rule that reaches itself at the same position fails there with
+    /// [LeftRecursion], and once one of its other alternatives succeeds the rule is run again with
+    /// that result stored, for as long as each run consumes more than the last.
+    ///
+    /// Diagnostics reported and syntax tree nodes recorded within the rule are not repeated when a
+    /// stored result is reused. The Tokens the rule consumed are still taken again though, so they
+    /// are recorded in the syntax tree as children of the node around the call, without the nodes
+    /// the rule started. Rules which build a syntax tree should not be reused across a rewind.
+    fn memoize<R, F>(&mut self, rule: &'static str, mut f: F) -> Result<R>
+    where
+        Self: Sized,
+        R: Clone + 'static,
+        F: FnMut(&mut Self) -> Result<R>,
+    {
+        let position = self.lexer().position();
+        match self.lexer().memo_mut().get_mut::<R>(rule, position) {
+            Some(Entry::Pending { recursed }) => {
+                *recursed = true;
+                let start = self.next_tok_boundary();
+                let location = Location::new(self.file_id(), Span::new(start, start));
+                return Err(LeftRecursion(rule.into(), location).into());
+            }
+            Some(entry @ Entry::Done { .. }) => {
+                let (end, result) = entry.result();
+                self.lexer().memo_mut().rule_stats(rule).hits += 1;
+                skip_to(self, end);
+                return result;
+            }
+            None => {}
+        }
+
+        let memo = self.lexer().memo_mut();
+        memo.rule_stats(rule).misses += 1;
+        memo.insert::<R>(rule, position, Entry::Pending { recursed: false });
+        let cursor = self.cursor();
+        let start = End { position, cursor };
+        let mut checkpoint = self.lexer().checkpoint();
+        let mut result = f(self);
+        let recursed = matches!(
+            self.lexer().memo_mut().get_mut::<R>(rule, position),
+            Some(Entry::Pending { recursed: true })
+        );
+        if result.is_err() {
+            backtrack(self, checkpoint, cursor);
+            return self
+                .lexer()
+                .memo_mut()
+                .finish(rule, position, result, start);
+        }
+        if !recursed {
+            self.lexer().release(checkpoint);
+            let end = current_end(self);
+            return self.lexer().memo_mut().finish(rule, position, result, end);
+        }
+
+        loop {
+            let end = current_end(self);
+            let seed = self
+                .lexer()
+                .memo_mut()
+                .finish(rule, position, result, end)?;
+            self.lexer().rewind(checkpoint);
+            self.set_cursor(cursor);
+            checkpoint = self.lexer().checkpoint();
+            result = f(self);
+            if result.is_err() || self.lexer().position() <= end.position {
+                self.lexer().rewind(checkpoint);
+                skip_to(self, end);
+                return Ok(seed);
+            }
+            self.lexer().memo_mut().rule_stats(rule).grown += 1;
+        }
+    }
+
+    /// Reports the provided diagnostic to the [TokenBuffer] rather than returning it, allowing the
+    /// parse to continue.
+    fn report(&mut self, diag: impl Into<DiagBox>) {
+        self.lexer().report(diag.into());
+    }
+
+    /// Skips Tokens until the next one is of a kind in `sync` or the end of the file is reached.
+    /// The synchronization Token itself is not consumed. Lexing errors skipped along the way are
+    /// reported. Returns if a synchronization Token was found.
+    fn synchronize(&mut self, sync: &[K]) -> bool {
+        loop {
+            match self.soft_peek() {
+                Ok(Some(tok)) if sync.contains(tok.kind_ref()) => return true,
+                Ok(Some(_)) => {
+                    self.take().ok();
+                }
+                Ok(None) => return false,
+                Err(err) => self.report(err),
+            }
+        }
+    }
+
+    /// Runs the provided sub-parse, recovering if it fails. The error is reported, Tokens are
+    /// skipped with [Parse::synchronize], and the placeholder is built from the [Location] covering
+    /// everything the failed sub-parse and the skipping consumed.
+    fn recover<R, F, P>(&mut self, sync: &[K], f: F, placeholder: P) -> R
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<R>,
+        P: FnOnce(Location) -> R,
+    {
+        let start = self.next_tok_boundary();
+        match f(self) {
+            Ok(value) => value,
+            Err(err) => {
+                self.report(err);
+                self.synchronize(sync);
+                let end = self.lexer().previous_end().max(start);
+                placeholder(Location::new(self.file_id(), Span::new(start, end)))
+            }
+        }
+    }
+
+    /// Runs the provided parser over the entire input, collecting every diagnostic rather than
+    /// stopping at the first error. If the parser fails its error is reported and the value is
+    /// None, otherwise the value is whatever was built, including any placeholders left by
+    /// [Parse::recover].
+    ///
+    /// The parser is expected to consume the entire input, so if it succeeds with Tokens left over
+    /// the first of them is reported as an [ExpectedOneOf] error (listing whatever was tried at
+    /// that position). Afterwards the rest of the input is consumed so that lexing errors the
+    /// parser never reached are reported too. The diagnostics are those reported to the
+    /// [TokenBuffer] followed by the ones collected by the lexer (see [Lex::take_diagnostics]).
+    fn run<R, F>(&mut self, f: F) -> ParseOutput<R>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        let value = match f(self) {
+            Ok(value) => {
+                let leftover = loop {
+                    match self.soft_peek() {
+                        Ok(tok) => break tok.is_some(),
+                        Err(err) => self.report(err),
+                    }
+                };
+                if leftover {
+                    let err = self.expected_one_of();
+                    self.report(err);
+                }
+                Some(value)
+            }
+            Err(err) => {
+                self.report(err);
+                None
+            }
+        };
+        for err in self.lexer().drain_errors() {
+            self.report(err);
+        }
+        let mut diagnostics = self.lexer().take_diagnostics();
+        diagnostics.extend(self.lexer().lexer_mut().take_diagnostics());
+        ParseOutput::new(value, diagnostics)
+    }
+
+    /// Parses items separated by `separator` up until `close`, which is not consumed. An item that
+    /// fails to parse is reported and skipped up to the next separator or `close`, so the items
+    /// after it are still parsed. Anything between an item and the next separator is reported and
+    /// skipped in the same way.
+    fn recover_list<R, F>(&mut self, separator: K, close: K, mut f: F) -> Vec<R>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self) -> Result<R>,
+    {
+        let sync = [separator.clone(), close.clone()];
+        let mut items = vec![];
+        loop {
+            match self.soft_peek() {
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(err) => {
+                    self.report(err);
+                    continue;
+                }
+            }
+            if self.check(close.clone()) {
+                break;
+            }
+            match f(self) {
+                Ok(item) => items.push(item),
+                Err(err) => {
+                    self.report(err);
+                    self.synchronize(&sync);
+                }
+            }
+            if self.match_take(separator.clone()).is_some() {
+                continue;
+            }
+            if self.check(close.clone()) || !matches!(self.soft_peek(), Ok(Some(_))) {
+                break;
+            }
+            let err = self.expected_one_of();
+            self.report(err);
+            if !self.synchronize(&sync) || self.match_take(separator.clone()).is_none() {
+                break;
+            }
+        }
+        items
+    }
+
+    /// Starts a new node of the provided kind in the concrete syntax tree. Every Token taken until
+    /// the matching [Parse::finish_node] is attached to it. See [TokenBuffer::start_node].
+    fn start_node(&mut self, kind: N) {
+        self.lexer().start_node(kind);
+    }
+
+    /// Returns a [NodeCheckpoint] for [Parse::start_node_at].
+    fn node_checkpoint(&mut self) -> NodeCheckpoint {
+        self.lexer().node_checkpoint()
+    }
+
+    /// Starts a new node of the provided kind wrapping everything recorded since the provided
+    /// [NodeCheckpoint]. See [TokenBuffer::start_node_at].
+    fn start_node_at(&mut self, checkpoint: NodeCheckpoint, kind: N) {
+        self.lexer().start_node_at(checkpoint, kind);
+    }
+
+    /// Finishes the current node in the concrete syntax tree.
+    fn finish_node(&mut self) {
+        self.lexer().finish_node();
+    }
+
+    /// Builds the recorded concrete syntax tree, whose text will be exactly `source`. See
+    /// [TokenBuffer::finish_tree].
+    fn finish_tree(&mut self, source: &str) -> SyntaxNode<N, K>
+    where
+        N: PartialEq,
+    {
+        let file_id = self.file_id();
+        self.lexer().finish_tree(source, file_id)
+    }
+
     /// Creates a [Span] from the given position up until the pilot's current position.
     fn span(&self, start: usize) -> Span {
         Span::new(start, self.cursor())
@@ -118,3 +790,106 @@ where
         Location::new(self.file_id(), self.span(start))
     }
 }
+
+/// The remaining stack below which [Parse::nested] grows the stack.
+#[cfg(feature = "stacker")]
+const STACK_RED_ZONE: usize = 64 * 1024;
+
+/// The size of each new stack segment allocated by [Parse::nested].
+#[cfg(feature = "stacker")]
+const STACK_GROWTH: usize = 1024 * 1024;
+
+/// Parses the operators of the first level of a [Parse::binary] table, with the rest of the table
+/// parsing their operands.
+fn binary_level<'s, P, L, T, K, N, V, F, B>(
+    parser: &mut P,
+    table: &[Precedence<K>],
+    operand: &mut F,
+    build: &mut B,
+) -> Result<V>
+where
+    P: Parse<'s, L, T, K, N>,
+    L: Lex<'s, T, K> + Iterator<Item = Result<T>>,
+    T: Token<K> + Clone,
+    K: TokenKind + 's,
+    F: FnMut(&mut P) -> Result<V>,
+    B: FnMut(&mut P, V, T, V, Location) -> Result<V>,
+{
+    let Some((level, tighter)) = table.split_first() else {
+        return operand(parser);
+    };
+    let start = parser.next_tok_boundary();
+    let mut lhs = binary_level(parser, tighter, operand, build)?;
+    let mut chained = false;
+    loop {
+        let lhs_end = parser.lexer().previous_end();
+        for kind in level.operators {
+            parser.lexer().expect(kind.clone());
+        }
+        let tok = match parser.soft_peek()? {
+            Some(tok) if level.operators.contains(tok.kind_ref()) => parser.take()?,
+            _ => return Ok(lhs),
+        };
+        if chained {
+            let lhs_location = Location::new(parser.file_id(), Span::new(start, lhs_end));
+            let op = tok.kind_ref().to_string();
+            parser.report(ChainedComparison(op, tok.location(), lhs_location));
+        }
+        let rhs = match level.assoc {
+            // Each operator in a right associative chain recurses, so it counts as a level of
+            // nesting.
+            Assoc::Right => parser.nested(|parser| binary_level(parser, table, operand, build))?,
+            Assoc::Left | Assoc::Neither => binary_level(parser, tighter, operand, build)?,
+        };
+        let end = parser.lexer().previous_end();
+        let location = Location::new(parser.file_id(), Span::new(start, end));
+        lhs = build(parser, lhs, tok, rhs, location)?;
+        chained = level.assoc == Assoc::Neither;
+    }
+}
+
+/// Rewinds the parser to the provided [Checkpoint] and cursor after a failed sub-parse, recording
+/// the backtrack if tracing.
+fn backtrack<'s, P, L, T, K, N>(parser: &mut P, checkpoint: Checkpoint, cursor: usize)
+where
+    P: Parse<'s, L, T, K, N>,
+    L: Lex<'s, T, K> + Iterator<Item = Result<T>>,
+    T: Token<K> + Clone,
+    K: TokenKind + 's,
+{
+    let end = parser.lexer().previous_end().max(cursor);
+    parser.lexer().rewind(checkpoint);
+    parser.set_cursor(cursor);
+    if parser.lexer().is_tracing() {
+        let location = Location::new(parser.file_id(), Span::new(cursor, end));
+        parser.lexer().record(TraceEvent::Backtrack { location });
+    }
+}
+
+/// Returns where the parser currently is, for storing in the [super::Memo].
+fn current_end<'s, P, L, T, K, N>(parser: &mut P) -> End
+where
+    P: Parse<'s, L, T, K, N>,
+    L: Lex<'s, T, K> + Iterator<Item = Result<T>>,
+    T: Token<K> + Clone,
+    K: TokenKind + 's,
+{
+    End {
+        position: parser.lexer().position(),
+        cursor: parser.cursor(),
+    }
+}
+
+/// Moves the parser forward to where a memoized rule ended.
+fn skip_to<'s, P, L, T, K, N>(parser: &mut P, end: End)
+where
+    P: Parse<'s, L, T, K, N>,
+    L: Lex<'s, T, K> + Iterator<Item = Result<T>>,
+    T: Token<K> + Clone,
+    K: TokenKind + 's,
+{
+    while parser.lexer().position() < end.position {
+        parser.lexer().take();
+    }
+    parser.set_cursor(end.cursor);
+}
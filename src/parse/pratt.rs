@@ -0,0 +1,330 @@
+use crate::{
+    diagnostics::Result,
+    lex::{Lex, Token, TokenKind},
+    utils::{Location, Span},
+};
+
+use super::{
+    Parse,
+    errors::{ChainedComparison, ExpectedExpression},
+};
+
+/// The associativity of an operator, deciding how a chain of operators with the same precedence
+/// is grouped.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Assoc {
+    /// `a - b - c` is parsed as `(a - b) - c`.
+    Left,
+    /// `a = b = c` is parsed as `a = (b = c)`.
+    Right,
+    /// `a < b < c` is not allowed: a [ChainedComparison] error is reported at the second operator,
+    /// and the chain is parsed as `(a < b) < c`. Only infix operators are checked for chains;
+    /// other operators treat this like [Assoc::Left].
+    Neither,
+}
+
+/// One level of the precedence table given to [Parse::binary]: a group of operators that share a
+/// precedence and an [Assoc].
+#[derive(Debug, Clone, Copy)]
+pub struct Precedence<'a, K> {
+    /// How a chain of this level's operators is grouped.
+    pub assoc: Assoc,
+    /// The kinds of the operators.
+    pub operators: &'a [K],
+}
+
+impl<'a, K> Precedence<'a, K> {
+    /// Creates a level of left-associative operators.
+    pub const fn left(operators: &'a [K]) -> Self {
+        Self {
+            assoc: Assoc::Left,
+            operators,
+        }
+    }
+
+    /// Creates a level of right-associative operators.
+    pub const fn right(operators: &'a [K]) -> Self {
+        Self {
+            assoc: Assoc::Right,
+            operators,
+        }
+    }
+
+    /// Creates a level of non-associative operators, such as comparisons.
+    pub const fn neither(operators: &'a [K]) -> Self {
+        Self {
+            assoc: Assoc::Neither,
+            operators,
+        }
+    }
+}
+
+/// The pieces of a mixfix expression, such as `cond ? a : b`.
+#[derive(Debug)]
+pub struct MixfixParts<T, N> {
+    /// The expression before the opening token.
+    pub lhs: N,
+    /// The opening token, such as `?`.
+    pub open: T,
+    /// The expression between the opening and closing tokens.
+    pub middle: N,
+    /// The closing token, such as `:`.
+    pub close: T,
+    /// The expression after the closing token.
+    pub rhs: N,
+}
+
+type AtomFn<'p, P, T, K, N> = Box<dyn Fn(&Pratt<'p, P, T, K, N>, &mut P) -> Result<Option<N>> + 'p>;
+type PrefixFn<'p, P, T, N> = Box<dyn Fn(&mut P, T, N, Location) -> Result<N> + 'p>;
+type InfixFn<'p, P, T, N> = Box<dyn Fn(&mut P, N, T, N, Location) -> Result<N> + 'p>;
+type PostfixFn<'p, P, T, K, N> =
+    Box<dyn Fn(&Pratt<'p, P, T, K, N>, &mut P, N, T, Location) -> Result<N> + 'p>;
+type MixfixFn<'p, P, T, N> = Box<dyn Fn(&mut P, MixfixParts<T, N>, Location) -> Result<N> + 'p>;
+
+struct Operator<K, F> {
+    kind: K,
+    close: Option<K>,
+    assoc: Assoc,
+    left_bp: u32,
+    right_bp: u32,
+    build: F,
+}
+
+/// A reusable Pratt (top-down operator precedence) expression parser that runs on top of a
+/// [Parse] implementation.
+///
+/// Operators are registered by their [TokenKind] along with a precedence (where higher values bind
+/// tighter) and a callback which builds your AST node. Each callback is given the [Location]
+/// spanning the entire expression it builds, from the start of its first token to the end of its
+/// last.
+///
+/// Callbacks which may need to parse further expressions on their own, such as the atom callback
+/// handling parenthesized groups or a postfix operator handling call arguments, are given the
+/// Pratt parser itself.
+pub struct Pratt<'p, P, T, K, N> {
+    atom: AtomFn<'p, P, T, K, N>,
+    prefix: Vec<Operator<K, PrefixFn<'p, P, T, N>>>,
+    infix: Vec<Operator<K, InfixFn<'p, P, T, N>>>,
+    postfix: Vec<Operator<K, PostfixFn<'p, P, T, K, N>>>,
+    mixfix: Vec<Operator<K, MixfixFn<'p, P, T, N>>>,
+}
+
+impl<'p, P, T, K, N> Pratt<'p, P, T, K, N>
+where
+    K: PartialEq,
+{
+    /// Creates a new Pratt parser. The provided callback parses the atoms of your expressions
+    /// (literals, identifiers, groups, etc), returning None if the next token cannot start one.
+    pub fn new<F>(atom: F) -> Self
+    where
+        F: Fn(&Self, &mut P) -> Result<Option<N>> + 'p,
+    {
+        Self {
+            atom: Box::new(atom),
+            prefix: vec![],
+            infix: vec![],
+            postfix: vec![],
+            mixfix: vec![],
+        }
+    }
+
+    /// Registers a prefix operator, such as `-a`.
+    pub fn prefix<F>(mut self, kind: K, precedence: u16, build: F) -> Self
+    where
+        F: Fn(&mut P, T, N, Location) -> Result<N> + 'p,
+    {
+        let (left_bp, _) = binding_power(precedence, Assoc::Left);
+        self.prefix.push(Operator {
+            kind,
+            close: None,
+            assoc: Assoc::Left,
+            left_bp,
+            right_bp: left_bp + 1,
+            build: Box::new(build),
+        });
+        self
+    }
+
+    /// Registers an infix operator, such as `a + b`.
+    pub fn infix<F>(mut self, kind: K, precedence: u16, assoc: Assoc, build: F) -> Self
+    where
+        F: Fn(&mut P, N, T, N, Location) -> Result<N> + 'p,
+    {
+        let (left_bp, right_bp) = binding_power(precedence, assoc);
+        self.infix.push(Operator {
+            kind,
+            close: None,
+            assoc,
+            left_bp,
+            right_bp,
+            build: Box::new(build),
+        });
+        self
+    }
+
+    /// Registers a postfix operator, such as `a!` or `a(b, c)`. The [Location] handed to the
+    /// callback ends at the operator's token, since anything after it is up to the callback to
+    /// parse.
+    pub fn postfix<F>(mut self, kind: K, precedence: u16, build: F) -> Self
+    where
+        F: Fn(&Self, &mut P, N, T, Location) -> Result<N> + 'p,
+    {
+        let (left_bp, right_bp) = binding_power(precedence, Assoc::Left);
+        self.postfix.push(Operator {
+            kind,
+            close: None,
+            assoc: Assoc::Left,
+            left_bp,
+            right_bp,
+            build: Box::new(build),
+        });
+        self
+    }
+
+    /// Registers a mixfix operator with an opening and closing token, such as `a ? b : c`. The
+    /// expression between the two tokens is parsed without any precedence restrictions.
+    pub fn mixfix<F>(mut self, open: K, close: K, precedence: u16, assoc: Assoc, build: F) -> Self
+    where
+        F: Fn(&mut P, MixfixParts<T, N>, Location) -> Result<N> + 'p,
+    {
+        let (left_bp, right_bp) = binding_power(precedence, assoc);
+        self.mixfix.push(Operator {
+            kind: open,
+            close: Some(close),
+            assoc,
+            left_bp,
+            right_bp,
+            build: Box::new(build),
+        });
+        self
+    }
+
+    /// Parses an entire expression.
+    pub fn parse<'s, L, S>(&self, parser: &mut P) -> Result<N>
+    where
+        P: Parse<'s, L, T, K, S>,
+        L: Lex<'s, T, K> + Iterator<Item = Result<T>>,
+        T: Token<K> + Clone,
+        K: TokenKind + 's,
+    {
+        self.parse_bp(parser, 0)
+    }
+
+    /// Parses an expression, stopping at the first operator that binds looser than `min_bp`. Each
+    /// operand is parsed one level deeper through [Parse::nested].
+    fn parse_bp<'s, L, S>(&self, parser: &mut P, min_bp: u32) -> Result<N>
+    where
+        P: Parse<'s, L, T, K, S>,
+        L: Lex<'s, T, K> + Iterator<Item = Result<T>>,
+        T: Token<K> + Clone,
+        K: TokenKind + 's,
+    {
+        parser.nested(|parser| self.parse_nested(parser, min_bp))
+    }
+
+    fn parse_nested<'s, L, S>(&self, parser: &mut P, min_bp: u32) -> Result<N>
+    where
+        P: Parse<'s, L, T, K, S>,
+        L: Lex<'s, T, K> + Iterator<Item = Result<T>>,
+        T: Token<K> + Clone,
+        K: TokenKind + 's,
+    {
+        let start = parser.next_tok_boundary();
+        let kind = parser.peek()?.kind_ref().clone();
+        let mut lhs = if let Some(op) = find(&self.prefix, &kind) {
+            let tok = parser.take()?;
+            let rhs = self.parse_bp(parser, op.right_bp)?;
+            let location = expression_location(parser, start);
+            (op.build)(parser, tok, rhs, location)?
+        } else if let Some(atom) = (self.atom)(self, parser)? {
+            atom
+        } else {
+            let location = parser.peek()?.location();
+            return Err(ExpectedExpression(location).into());
+        };
+
+        // The binding power of the non-associative operator that built `lhs`, if one did.
+        let mut non_assoc = None;
+        loop {
+            let Some(next) = parser.soft_peek()? else {
+                break;
+            };
+            let kind = next.kind_ref().clone();
+            if let Some(op) = find(&self.postfix, &kind) {
+                if op.left_bp < min_bp {
+                    break;
+                }
+                let tok = parser.take()?;
+                let location = expression_location(parser, start);
+                lhs = (op.build)(self, parser, lhs, tok, location)?;
+                non_assoc = None;
+            } else if let Some(op) = find(&self.infix, &kind) {
+                if op.left_bp < min_bp {
+                    break;
+                }
+                let lhs_location = expression_location(parser, start);
+                let tok = parser.take()?;
+                if op.assoc == Assoc::Neither && non_assoc == Some(op.left_bp) {
+                    let op = tok.kind_ref().to_string();
+                    parser.report(ChainedComparison(op, tok.location(), lhs_location));
+                }
+                let rhs = self.parse_bp(parser, op.right_bp)?;
+                let location = expression_location(parser, start);
+                lhs = (op.build)(parser, lhs, tok, rhs, location)?;
+                non_assoc = (op.assoc == Assoc::Neither).then_some(op.left_bp);
+            } else if let Some(op) = find(&self.mixfix, &kind) {
+                if op.left_bp < min_bp {
+                    break;
+                }
+                let open = parser.take()?;
+                let middle = self.parse_bp(parser, 0)?;
+                let close = parser.require(op.close.clone().unwrap())?;
+                let rhs = self.parse_bp(parser, op.right_bp)?;
+                let location = expression_location(parser, start);
+                let parts = MixfixParts {
+                    lhs,
+                    open,
+                    middle,
+                    close,
+                    rhs,
+                };
+                lhs = (op.build)(parser, parts, location)?;
+                non_assoc = None;
+            } else {
+                break;
+            }
+        }
+
+        Ok(lhs)
+    }
+}
+
+/// Returns the operator registered for the provided kind.
+fn find<'a, K: PartialEq, F>(
+    operators: &'a [Operator<K, F>],
+    kind: &K,
+) -> Option<&'a Operator<K, F>> {
+    operators.iter().find(|op| &op.kind == kind)
+}
+
+/// Converts a precedence into the binding powers used on the left and right of an operator.
+/// Precedence 0 still binds tighter than the minimum binding power of a full expression.
+fn binding_power(precedence: u16, assoc: Assoc) -> (u32, u32) {
+    let base = (precedence as u32 + 1) * 2;
+    match assoc {
+        Assoc::Left | Assoc::Neither => (base, base + 1),
+        Assoc::Right => (base + 1, base),
+    }
+}
+
+/// Creates the [Location] from the provided start to the end of the last token taken.
+fn expression_location<'s, P, L, T, K, S>(parser: &mut P, start: usize) -> Location
+where
+    P: Parse<'s, L, T, K, S>,
+    L: Lex<'s, T, K> + Iterator<Item = Result<T>>,
+    T: Token<K> + Clone,
+    K: TokenKind + 's,
+{
+    let end = parser.lexer().previous_end();
+    Location::new(parser.file_id(), Span::new(start, end))
+}
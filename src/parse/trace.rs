@@ -0,0 +1,268 @@
+use std::fmt::Write;
+
+use crate::{
+    diagnostics::{Builder, Diag, Severity},
+    utils::Location,
+};
+
+/// A log of what a parser did, recorded by a [super::TokenBuffer] once
+/// [super::TokenBuffer::enable_trace] is called.
+///
+/// Displaying a Trace renders it as an indented tree, with each rule's events nested beneath it
+/// and each [Location] written as `file:start..end`. [Trace::to_json] exports the same events for
+/// inspection with other tools.
+#[derive(Debug, Default, PartialEq)]
+pub struct Trace {
+    events: Vec<TraceEvent>,
+}
+
+/// A single step recorded in a [Trace].
+#[derive(Debug, PartialEq, Clone)]
+pub enum TraceEvent {
+    /// A rule run through [super::Parse::trace] was entered, at the start of the next token.
+    Enter {
+        /// The name of the rule.
+        rule: &'static str,
+        /// Where the rule began.
+        location: Location,
+    },
+    /// A rule run through [super::Parse::trace] returned.
+    Exit {
+        /// The name of the rule.
+        rule: &'static str,
+        /// Everything the rule consumed.
+        location: Location,
+        /// The message of the error the rule returned, or None if it succeeded.
+        error: Option<String>,
+    },
+    /// A token was consumed.
+    Token {
+        /// The kind of the token.
+        kind: String,
+        /// Where the token is.
+        location: Location,
+    },
+    /// A speculative parse failed and the parser returned to an earlier position.
+    Backtrack {
+        /// The text that was given back.
+        location: Location,
+    },
+    /// A diagnostic was reported to the [super::TokenBuffer].
+    Diagnostic {
+        /// The severity of the diagnostic.
+        severity: Severity,
+        /// The message of the diagnostic (see [TraceEvent::message]).
+        message: String,
+        /// The location of the diagnostic's first label, if it has any.
+        location: Option<Location>,
+    },
+}
+
+impl TraceEvent {
+    /// Creates a [TraceEvent::Diagnostic] describing the provided diagnostic.
+    pub fn diagnostic(diag: &dyn Diag) -> Self {
+        let (message, location) = describe(diag);
+        Self::Diagnostic {
+            severity: diag.severity(),
+            message,
+            location,
+        }
+    }
+
+    /// Returns the message of the provided diagnostic, as used in [TraceEvent::Exit]. This is its
+    /// title, or the message of its first label if it has no title.
+    pub fn message(diag: &dyn Diag) -> String {
+        describe(diag).0
+    }
+}
+
+impl Trace {
+    /// Creates a new, empty Trace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an event to the end of this Trace.
+    pub fn push(&mut self, event: TraceEvent) {
+        self.events.push(event);
+    }
+
+    /// Returns every event recorded, in order.
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// Removes every event recorded.
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Returns the events of this Trace as a JSON array. Each event is an object whose `event`
+    /// field is one of `enter`, `exit`, `token`, `backtrack` or `diagnostic`, and whose locations
+    /// are objects with `file`, `start` and `end` fields.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+        for (index, event) in self.events.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            json.push('{');
+            match event {
+                TraceEvent::Enter { rule, location } => {
+                    write!(json, r#""event":"enter","rule":{}"#, quote(rule)).unwrap();
+                    write_location(&mut json, Some(location));
+                }
+                TraceEvent::Exit {
+                    rule,
+                    location,
+                    error,
+                } => {
+                    write!(json, r#""event":"exit","rule":{}"#, quote(rule)).unwrap();
+                    write_location(&mut json, Some(location));
+                    match error {
+                        Some(error) => write!(json, r#","error":{}"#, quote(error)).unwrap(),
+                        None => json.push_str(r#","error":null"#),
+                    }
+                }
+                TraceEvent::Token { kind, location } => {
+                    write!(json, r#""event":"token","kind":{}"#, quote(kind)).unwrap();
+                    write_location(&mut json, Some(location));
+                }
+                TraceEvent::Backtrack { location } => {
+                    json.push_str(r#""event":"backtrack""#);
+                    write_location(&mut json, Some(location));
+                }
+                TraceEvent::Diagnostic {
+                    severity,
+                    message,
+                    location,
+                } => {
+                    write!(
+                        json,
+                        r#""event":"diagnostic","severity":"{}","message":{}"#,
+                        severity_name(*severity),
+                        quote(message)
+                    )
+                    .unwrap();
+                    write_location(&mut json, location.as_ref());
+                }
+            }
+            json.push('}');
+        }
+        json.push(']');
+        json
+    }
+}
+
+impl std::fmt::Display for Trace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut depth = 0usize;
+        for event in &self.events {
+            if matches!(event, TraceEvent::Exit { .. }) {
+                depth = depth.saturating_sub(1);
+            }
+            write!(f, "{:indent$}", "", indent = depth * 2)?;
+            match event {
+                TraceEvent::Enter { rule, location } => {
+                    depth += 1;
+                    writeln!(f, "> {rule} @ {}", ShowLocation(location))
+                }
+                TraceEvent::Exit {
+                    rule,
+                    location,
+                    error: None,
+                } => writeln!(f, "< {rule} @ {}", ShowLocation(location)),
+                TraceEvent::Exit {
+                    rule,
+                    location,
+                    error: Some(error),
+                } => writeln!(f, "< {rule} failed @ {}: {error}", ShowLocation(location)),
+                TraceEvent::Token { kind, location } => {
+                    writeln!(f, "token {kind} @ {}", ShowLocation(location))
+                }
+                TraceEvent::Backtrack { location } => {
+                    writeln!(f, "backtrack @ {}", ShowLocation(location))
+                }
+                TraceEvent::Diagnostic {
+                    severity,
+                    message,
+                    location: Some(location),
+                } => writeln!(
+                    f,
+                    "{} @ {}: {message}",
+                    severity_name(*severity),
+                    ShowLocation(location)
+                ),
+                TraceEvent::Diagnostic {
+                    severity,
+                    message,
+                    location: None,
+                } => writeln!(f, "{}: {message}", severity_name(*severity)),
+            }?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the message of a diagnostic and the location of its first label.
+fn describe(diag: &dyn Diag) -> (String, Option<Location>) {
+    let builder = diag.build(Builder::new(diag.severity()));
+    let label = builder.labels.first();
+    let message = match label {
+        Some(label) if builder.message.is_empty() => label.message.clone(),
+        _ => builder.message.clone(),
+    };
+    let location = label.map(|label| Location::new(label.file_id, label.range.clone()));
+    (message, location)
+}
+
+/// Formats a [Location] as `file:start..end`.
+struct ShowLocation<'a>(&'a Location);
+
+impl std::fmt::Display for ShowLocation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = self.0.span();
+        write!(f, "{}:{}..{}", self.0.file_id(), span.start(), span.end())
+    }
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug => "bug",
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    }
+}
+
+fn write_location(json: &mut String, location: Option<&Location>) {
+    match location {
+        Some(location) => write!(
+            json,
+            r#","location":{{"file":{},"start":{},"end":{}}}"#,
+            location.file_id(),
+            location.span().start(),
+            location.span().end()
+        )
+        .unwrap(),
+        None => json.push_str(r#","location":null"#),
+    }
+}
+
+/// Returns the provided text as a JSON string.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for char in text.chars() {
+        match char {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            char if char.is_control() => write!(quoted, "\\u{:04x}", char as u32).unwrap(),
+            char => quoted.push(char),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
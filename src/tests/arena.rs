@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+
+use pretty_assertions::assert_eq;
+
+use crate::{
+    diagnostics::Result,
+    lex::Token,
+    parse::Parse,
+    tests::utils::{
+        Parser,
+        TokKind::{Int, Plus},
+    },
+    utils::{Arena, Location, NodeId, Span},
+};
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+    Literal(i64),
+    Add(NodeId<Expr>, NodeId<Expr>),
+}
+
+fn literal(p: &mut Parser, arena: &mut Arena<Expr>) -> Result<NodeId<Expr>> {
+    let literal = p.spanned(|p| match p.take()?.kind() {
+        Int(int) => Ok(Expr::Literal(int)),
+        _ => Err(p.expected_one_of()),
+    })?;
+    Ok(arena.alloc_spanned(literal))
+}
+
+/// `expr := literal ('+' expr)?`
+fn expr(p: &mut Parser, arena: &mut Arena<Expr>) -> Result<NodeId<Expr>> {
+    let expr = p.spanned(|p| {
+        let lhs = literal(p, arena)?;
+        if p.match_take(Plus).is_none() {
+            return Ok(Err(lhs));
+        }
+        let rhs = expr(p, arena)?;
+        Ok(Ok((lhs, rhs)))
+    })?;
+    let location = expr.location;
+    match expr.into_inner() {
+        Err(literal) => Ok(literal),
+        Ok((lhs, rhs)) => {
+            let add = arena.alloc(Expr::Add(lhs, rhs), location);
+            arena.set_parent(lhs, add);
+            arena.set_parent(rhs, add);
+            Ok(add)
+        }
+    }
+}
+
+fn location(start: usize, end: usize) -> Location {
+    Location::new(0, Span::new(start, end))
+}
+
+#[test]
+fn parse_into_arena() {
+    let mut arena = Arena::new();
+    let mut parser = Parser::new("1 + 2 + 3");
+    let root = expr(&mut parser, &mut arena).unwrap();
+    assert_eq!(5, arena.len());
+    assert_eq!(location(0, 9), arena.location(root));
+    let Expr::Add(one, rest) = arena[root] else {
+        panic!("expected an addition");
+    };
+    assert_eq!(Expr::Literal(1), arena[one]);
+    assert_eq!(location(0, 1), arena.location(one));
+    assert_eq!(location(4, 9), arena.location(rest));
+    let Expr::Add(two, three) = arena[rest] else {
+        panic!("expected an addition");
+    };
+    assert_eq!(Expr::Literal(3), arena[three]);
+
+    assert_eq!(None, arena.parent(root));
+    assert_eq!(Some(root), arena.parent(one));
+    assert_eq!(Some(rest), arena.parent(two));
+    assert_eq!(vec![rest, root], arena.ancestors(three).collect::<Vec<_>>());
+    assert_eq!(vec![root], arena.roots().collect::<Vec<_>>());
+}
+
+#[test]
+fn iteration() {
+    let mut arena = Arena::new();
+    let mut parser = Parser::new("1 + 2");
+    expr(&mut parser, &mut arena).unwrap();
+    let literals: Vec<_> = arena
+        .iter()
+        .filter_map(|(_, expr)| match expr.value {
+            Expr::Literal(int) => Some(int),
+            Expr::Add(..) => None,
+        })
+        .collect();
+    assert_eq!(vec![1, 2], literals);
+
+    for (_, expr) in arena.iter_mut() {
+        if let Expr::Literal(int) = &mut expr.value {
+            *int *= 10;
+        }
+    }
+    let ids: Vec<_> = arena.ids().collect();
+    assert_eq!(
+        vec![0, 1, 2],
+        ids.iter().map(NodeId::index).collect::<Vec<_>>()
+    );
+    assert_eq!(Expr::Literal(20), arena[ids[1]]);
+    assert_eq!(
+        Some(location(4, 5)),
+        arena.get(ids[1]).map(|node| node.location)
+    );
+}
+
+#[test]
+fn ids() {
+    let mut arena = Arena::with_capacity(2);
+    let a = arena.alloc("a", location(0, 1));
+    let b = arena.alloc("b", location(1, 2));
+    let copy = a;
+    assert_eq!(a, copy);
+    assert!(a < b);
+    assert_eq!(2, HashSet::from([a, b, copy]).len());
+    assert_eq!("NodeId(1)", format!("{b:?}"));
+
+    arena[b] = "c";
+    assert_eq!("c", arena[b]);
+    arena.get_mut(a).unwrap().value = "d";
+    assert_eq!("d", arena[a]);
+
+    let other: Arena<&str> = Arena::new();
+    assert!(other.is_empty());
+    assert_eq!(None, other.get(a));
+}
+
+#[test]
+#[should_panic(expected = "NodeId(0) cannot be the parent of NodeId(0)")]
+fn parent_of_itself() {
+    let mut arena = Arena::new();
+    let a = arena.alloc("a", location(0, 1));
+    arena.set_parent(a, a);
+}
+
+#[test]
+#[should_panic(expected = "NodeId(2) cannot be the parent of NodeId(0)")]
+fn parent_cycle() {
+    let mut arena = Arena::new();
+    let a = arena.alloc("a", location(0, 1));
+    let b = arena.alloc("b", location(1, 2));
+    let c = arena.alloc("c", location(2, 3));
+    arena.set_parent(b, a);
+    arena.set_parent(c, b);
+    // Moving a node under a new parent elsewhere in the tree is still allowed.
+    arena.set_parent(c, a);
+    arena.set_parent(c, b);
+    arena.set_parent(a, c);
+}
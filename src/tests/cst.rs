@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use crate::{
+    cst::{GreenBuilder, SyntaxElement, SyntaxNode},
+    diagnostics::Result,
+    parse::Parse,
+    tests::utils::{Parser, TokKind, TokKind::*},
+    utils::{Located, Span},
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Node {
+    Program,
+    Let,
+    Binary,
+    Literal,
+}
+
+type Tree<'s> = SyntaxNode<Node, TokKind<'s>>;
+
+fn literal(p: &mut Parser<Node>) -> Result<()> {
+    p.start_node(Node::Literal);
+    p.take()?;
+    p.finish_node();
+    Ok(())
+}
+
+fn expr(p: &mut Parser<Node>) -> Result<()> {
+    let checkpoint = p.node_checkpoint();
+    literal(p)?;
+    while p.check(Plus) {
+        p.start_node_at(checkpoint, Node::Binary);
+        p.take()?;
+        literal(p)?;
+        p.finish_node();
+    }
+    Ok(())
+}
+
+fn program<'s>(source: &'s str) -> Tree<'s> {
+    let mut parser = Parser::for_tree(source);
+    parser.start_node(Node::Program);
+    while parser.soft_peek().is_ok_and(|tok| tok.is_some()) {
+        parser.start_node(Node::Let);
+        parser.require(Let).unwrap();
+        parser.take().unwrap();
+        parser.require(Equal).unwrap();
+        expr(&mut parser).unwrap();
+        parser.require(SemiColon).unwrap();
+        parser.finish_node();
+    }
+    parser.finish_node();
+    parser.finish_tree(source)
+}
+
+#[test]
+fn lossless() {
+    let source = "  let a =1+ 2 ;\n\nlet   b = 3;  \n";
+    let tree = program(source);
+    assert_eq!(source, tree.text());
+    assert_eq!(Span::new(0, source.len()), tree.span());
+    let tokens = tree.tokens();
+    assert_eq!(
+        source,
+        tokens
+            .iter()
+            .map(|tok| tok.text())
+            .collect::<std::string::String>()
+    );
+    assert!(tokens[0].is_trivia());
+    assert_eq!(Some(&Let), tokens[1].kind());
+}
+
+#[test]
+fn lex_errors_are_trivia() {
+    let source = "a $ b";
+    let mut parser = Parser::for_tree(source);
+    parser.start_node(Node::Program);
+    parser.take().unwrap();
+    assert!(parser.take().is_err());
+    parser.take().unwrap();
+    parser.finish_node();
+    let tree: Tree = parser.finish_tree(source);
+    assert_eq!(source, tree.text());
+    let kinds: Vec<_> = tree
+        .tokens()
+        .iter()
+        .map(|tok| tok.kind().cloned())
+        .collect();
+    assert_eq!(vec![Some(Ident("a")), None, Some(Ident("b"))], kinds);
+}
+
+#[test]
+fn structure() {
+    let tree = program("let a = 1 + 2 + 3;");
+    assert_eq!(&Node::Program, tree.kind());
+    let stmt = tree.child_nodes().next().unwrap();
+    assert_eq!(&Node::Let, stmt.kind());
+    assert_eq!(Some(&tree), stmt.parent());
+    let outer = stmt.child_nodes().next().unwrap();
+    assert_eq!(&Node::Binary, outer.kind());
+    assert_eq!("1 + 2 + 3", outer.text());
+    assert_eq!(Span::new(8, 17), outer.span());
+    let inner = outer.child_nodes().next().unwrap();
+    assert_eq!(&Node::Binary, inner.kind());
+    assert_eq!("1 + 2", inner.text());
+    let kinds: Vec<_> = tree.descendants().iter().map(|node| *node.kind()).collect();
+    use Node::*;
+    assert_eq!(
+        vec![Program, Let, Binary, Binary, Literal, Literal, Literal],
+        kinds
+    );
+    let last = outer.child_nodes().nth(1).unwrap();
+    let ancestors: Vec<_> = last.ancestors().map(|node| *node.kind()).collect();
+    assert_eq!(vec![Literal, Binary, Let, Program], ancestors);
+}
+
+#[test]
+fn siblings() {
+    let tree = program("let a = 1;");
+    let stmt = tree.child_nodes().next().unwrap();
+    let tokens: Vec<_> = stmt.child_tokens().collect();
+    assert_eq!(Some(&Ident("a")), tokens[1].kind());
+    let Some(SyntaxElement::Token(space)) = tokens[1].next_sibling() else {
+        panic!("expected trivia after the identifier");
+    };
+    assert!(space.is_trivia());
+    assert_eq!(Span::new(5, 6), space.span());
+    let Some(SyntaxElement::Token(name)) = space.prev_sibling() else {
+        panic!("expected the identifier before the trivia");
+    };
+    assert_eq!(tokens[1], name);
+    assert!(stmt.next_sibling().is_none());
+}
+
+#[test]
+fn deduplicated() {
+    let tree = program("let a = 1 + 1; let b = 1 + 1;");
+    let exprs: Vec<_> = tree
+        .child_nodes()
+        .map(|stmt| stmt.child_nodes().next().unwrap())
+        .collect();
+    assert!(Arc::ptr_eq(exprs[0].green(), exprs[1].green()));
+    assert_ne!(exprs[0], exprs[1]);
+    let literals: Vec<_> = exprs[0].child_nodes().collect();
+    assert!(Arc::ptr_eq(literals[0].green(), literals[1].green()));
+}
+
+#[test]
+fn attempt_discards_nodes() {
+    let source = "a b";
+    let mut parser = Parser::for_tree(source);
+    parser.start_node(Node::Program);
+    let failed = parser.attempt(|p| {
+        p.start_node(Node::Let);
+        p.take()?;
+        p.require(Comma)
+    });
+    assert!(failed.is_err());
+    parser.take().unwrap();
+    parser.take().unwrap();
+    parser.finish_node();
+    let tree: Tree = parser.finish_tree(source);
+    assert_eq!(0, tree.child_nodes().count());
+    assert_eq!(source, tree.text());
+}
+
+#[test]
+fn green_builder() {
+    let mut builder = GreenBuilder::new();
+    builder.start_node(Node::Program);
+    builder.token(Ident("a"), "a");
+    builder.trivia(" ");
+    builder.token(Ident("b"), "b");
+    builder.finish_node();
+    let green = builder.finish();
+    assert_eq!("a b", green.text());
+    assert_eq!(3, green.children().len());
+    let tree = SyntaxNode::new_root(green, 2);
+    assert_eq!(2, tree.location().file_id());
+}
+
+#[test]
+#[should_panic]
+fn green_builder_requires_root() {
+    let mut builder: GreenBuilder<Node, TokKind> = GreenBuilder::new();
+    builder.token(Ident("a"), "a");
+    builder.finish();
+}
@@ -0,0 +1,126 @@
+use crate::{
+    diagnostics::{Builder, Diag, DiagBox},
+    lex::{Lex, Tok, Token},
+    parse::{MismatchedDelimiter, Parse, UnclosedDelimiter, UnmatchedDelimiter, check_delimiters},
+    tests::utils::{Lexer, Parser, TokKind, TokKind::*, build},
+    utils::{Location, Span},
+};
+
+const PAIRS: [(TokKind<'static>, TokKind<'static>); 3] = [
+    (LeftParen, RightParen),
+    (LeftBracket, RightBracket),
+    (LeftBrace, RightBrace),
+];
+
+fn check(source: &str) -> Vec<DiagBox> {
+    let mut lexer = Lexer::new(source);
+    let mut tokens: Vec<Tok<TokKind>> = vec![];
+    while let Some(tok) = lexer.lex().unwrap() {
+        tokens.push(tok);
+    }
+    check_delimiters(&tokens, &PAIRS)
+}
+
+fn assert_diags(expected: Vec<Box<dyn Diag>>, found: Vec<DiagBox>) {
+    let expected: Vec<Builder> = expected.iter().map(|diag| build(diag.as_ref())).collect();
+    let found: Vec<Builder> = found.iter().map(|diag| build(diag.as_ref())).collect();
+    assert_eq!(expected, found);
+}
+
+fn at(start: usize, end: usize) -> Location {
+    Location::new(0, Span::new(start, end))
+}
+
+#[test]
+fn delimited() {
+    let mut parser = Parser::new("(a)");
+    let inner = parser.delimited(LeftParen, RightParen, |p| p.require(Ident("a")));
+    assert!(inner.is_ok());
+    assert!(parser.soft_peek().unwrap().is_none());
+}
+
+#[test]
+fn delimited_unclosed() {
+    let mut parser = Parser::new("[a ;");
+    let err = parser
+        .delimited(LeftBracket, RightBracket, |p| p.require(Ident("a")))
+        .unwrap_err();
+    assert_diags(
+        vec![Box::new(UnclosedDelimiter("]".into(), at(3, 4), at(0, 1)))],
+        vec![err],
+    );
+    assert_eq!(&SemiColon, parser.peek().unwrap().kind_ref());
+
+    let mut parser = Parser::new("{a");
+    let err = parser
+        .delimited(LeftBrace, RightBrace, |p| p.require(Ident("a")))
+        .unwrap_err();
+    assert_diags(
+        vec![Box::new(UnclosedDelimiter("}".into(), at(2, 2), at(0, 1)))],
+        vec![err],
+    );
+}
+
+#[test]
+fn delimited_ends_inside() {
+    let mut parser = Parser::new("(a +");
+    let err = parser
+        .delimited(LeftParen, RightParen, |p| {
+            p.require(Ident("a"))?;
+            p.require(Plus)?;
+            p.require(Ident("b"))
+        })
+        .unwrap_err();
+    assert_diags(
+        vec![Box::new(UnclosedDelimiter(")".into(), at(4, 4), at(0, 1)))],
+        vec![err],
+    );
+
+    let mut parser = Parser::new("{ ( a");
+    let err = parser
+        .delimited(LeftBrace, RightBrace, |p| {
+            p.delimited(LeftParen, RightParen, |p| p.require(Ident("a")))
+        })
+        .unwrap_err();
+    assert_diags(
+        vec![Box::new(UnclosedDelimiter("}".into(), at(5, 5), at(0, 1)))],
+        vec![err],
+    );
+}
+
+#[test]
+fn balanced() {
+    assert!(check("({[]}) [()] {}").is_empty());
+}
+
+#[test]
+fn mismatched() {
+    assert_diags(
+        vec![Box::new(MismatchedDelimiter(
+            ")".into(),
+            at(1, 2),
+            at(0, 1),
+        ))],
+        check("(]"),
+    );
+}
+
+#[test]
+fn unmatched() {
+    assert_diags(vec![Box::new(UnmatchedDelimiter(at(3, 4)))], check("() )"));
+}
+
+#[test]
+fn unclosed() {
+    assert_diags(
+        vec![Box::new(UnclosedDelimiter("]".into(), at(4, 5), at(1, 2)))],
+        check("([a )"),
+    );
+    assert_diags(
+        vec![
+            Box::new(UnclosedDelimiter(")".into(), at(5, 5), at(2, 3))),
+            Box::new(UnclosedDelimiter("}".into(), at(5, 5), at(0, 1))),
+        ],
+        check("{ ( a"),
+    );
+}
@@ -0,0 +1,100 @@
+use crate::{
+    diagnostics::Result,
+    parse::{NestingTooDeep, Parse},
+    tests::utils::{Parser, TokKind::*, assert_diag},
+    utils::{Location, Span},
+};
+
+/// `group := '(' group ')' | int`
+fn group(p: &mut Parser) -> Result<usize> {
+    p.nested(|p| {
+        if p.match_take(LeftParen).is_some() {
+            let depth = group(p)?;
+            p.require(RightParen)?;
+            Ok(depth + 1)
+        } else {
+            p.require(Int(1)).map(|_| 0)
+        }
+    })
+}
+
+fn nested_source(depth: usize) -> std::string::String {
+    format!("{}1{}", "(".repeat(depth), ")".repeat(depth))
+}
+
+#[test]
+fn within_limit() {
+    let source = nested_source(100);
+    let mut parser = Parser::new(&source);
+    assert_eq!(100, group(&mut parser).unwrap());
+    assert_eq!(0, parser.lexer().depth());
+}
+
+#[test]
+fn too_deep() {
+    let source = nested_source(100_000);
+    let mut parser = Parser::new(&source);
+    let err = group(&mut parser).unwrap_err();
+    let limit = parser.lexer().depth_limit();
+    let expected = NestingTooDeep(limit, Location::new(0, Span::new(limit, limit + 1)));
+    assert_diag(expected, &err);
+    assert_eq!(0, parser.lexer().depth());
+}
+
+#[test]
+fn configurable_limit() {
+    let source = nested_source(10);
+    let mut parser = Parser::new(&source);
+    parser.lexer().set_depth_limit(10);
+    assert!(group(&mut parser).is_err());
+
+    let mut parser = Parser::new(&source);
+    parser.lexer().set_depth_limit(11);
+    assert_eq!(10, group(&mut parser).unwrap());
+}
+
+#[test]
+fn enter_and_exit() {
+    let mut parser = Parser::new("a");
+    parser.lexer().set_depth_limit(2);
+    parser.enter().unwrap();
+    parser.enter().unwrap();
+    assert!(parser.enter().is_err());
+    assert_eq!(2, parser.lexer().depth());
+    parser.exit();
+    parser.exit();
+    assert_eq!(0, parser.lexer().depth());
+}
+
+#[test]
+fn attempt_restores_depth() {
+    let mut parser = Parser::new("a");
+    let result = parser.attempt(|p| {
+        p.enter()?;
+        p.require(Comma)
+    });
+    assert!(result.is_err());
+    assert_eq!(0, parser.lexer().depth());
+}
+
+#[test]
+fn pratt_is_guarded() {
+    use crate::parse::Pratt;
+    let source = format!("{}1", "-".repeat(100_000));
+    let pratt = Pratt::new(|_, p: &mut Parser| Ok(p.match_take(Int(1)).map(|_| ()))).prefix(
+        Minus,
+        0,
+        |_, _, _, _| Ok(()),
+    );
+    let mut parser = Parser::new(&source);
+    assert!(pratt.parse(&mut parser).is_err());
+}
+
+#[cfg(feature = "stacker")]
+#[test]
+fn grows_the_stack() {
+    let source = nested_source(100_000);
+    let mut parser = Parser::new(&source);
+    parser.lexer().set_depth_limit(usize::MAX);
+    assert_eq!(100_000, group(&mut parser).unwrap());
+}
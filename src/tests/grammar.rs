@@ -0,0 +1,162 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    cst::SyntaxNode,
+    parse::{ExpectedOneOf, Parse},
+    tests::utils::{
+        Parser, TokKind,
+        TokKind::{Colon, Equal, Ident, Int, LeftParen, Let, Minus, Plus, RightParen, SemiColon},
+        TokKind::{Slash, Star},
+        assert_diag, build,
+    },
+    utils::{Located, Location, Span},
+};
+
+crate::grammar! {
+    /// Arithmetic over integers.
+    mod calc for Parser<'s, Rule> {
+        /// An expression.
+        pub expr = term ((Plus | Minus) term)*;
+        term = factor ((Star | Slash) factor)*;
+        factor = Int(_) | LeftParen expr RightParen | Minus factor;
+    }
+}
+
+crate::grammar! {
+    #[lookahead(2)]
+    mod statements for Parser<'s, Rule> {
+        pub program = statement*;
+        statement = assign | call | binding;
+        assign = Ident(_) Equal Ident(_) SemiColon;
+        call = Ident(_) SemiColon;
+        binding = Let Ident(_) (Colon Ident(_))? Equal Int(_) SemiColon;
+    }
+}
+
+fn parse<'s, N: PartialEq>(
+    source: &'s str,
+    rule: fn(&mut Parser<'s, N>) -> crate::diagnostics::Result<()>,
+) -> SyntaxNode<N, TokKind<'s>> {
+    let mut parser = Parser::for_tree(source);
+    rule(&mut parser).unwrap();
+    assert!(parser.soft_peek().unwrap().is_none());
+    parser.finish_tree(source)
+}
+
+/// Renders the nodes of a tree as `Kind(children)`.
+fn shape<N: std::fmt::Debug, K>(node: &SyntaxNode<N, K>) -> std::string::String {
+    let children: Vec<_> = node.child_nodes().map(|child| shape(&child)).collect();
+    match children.is_empty() {
+        true => format!("{:?}", node.kind()),
+        false => format!("{:?}({})", node.kind(), children.join(" ")),
+    }
+}
+
+#[test]
+fn builds_tree() {
+    let source = "1 + 2 * (3 - -4)";
+    let tree = parse::<calc::Rule>(source, calc::expr);
+    assert_eq!(source, tree.text());
+    assert_eq!(
+        "Expr(Term(Factor) Term(Factor Factor(Expr(Term(Factor) Term(Factor(Factor))))))",
+        shape(&tree)
+    );
+}
+
+#[test]
+fn expected_one_of() {
+    let mut parser = Parser::for_tree("1 + *");
+    let err = calc::expr(&mut parser).unwrap_err();
+    let expected = ExpectedOneOf(
+        vec!["Int".into(), LeftParen.to_string(), Minus.to_string()],
+        Some(Star.to_string()),
+        Location::new(0, Span::new(4, 5)),
+    );
+    assert_diag(expected, &err);
+}
+
+#[test]
+fn failed_rules_finish_nodes() {
+    let source = "1 + *";
+    let mut parser = Parser::for_tree(source);
+    let output = parser.run(calc::expr);
+    assert!(output.value.is_none());
+    assert_eq!(1, output.errors().count());
+    let tree: SyntaxNode<calc::Rule, _> = parser.finish_tree(source);
+    assert_eq!(source, tree.text());
+    assert_eq!("Expr(Term(Factor) Term(Factor))", shape(&tree));
+}
+
+#[test]
+fn unclosed_group() {
+    let mut parser = Parser::for_tree("(1 + 2");
+    let err = calc::expr(&mut parser).unwrap_err();
+    assert_eq!(
+        Span::new(6, 6),
+        build(err.as_ref()).labels[0].range.clone().into()
+    );
+}
+
+#[test]
+fn first_and_follow() {
+    assert_eq!(1, calc::Rule::LOOKAHEAD);
+    let first: &[&[&str]] = &[&["Int"], &["LeftParen"], &["Minus"]];
+    assert_eq!(first, calc::Rule::Factor.first());
+    assert_eq!(first, calc::Rule::Expr.first());
+    let follow: &[&[&str]] = &[
+        &["Minus"],
+        &["Plus"],
+        &["RightParen"],
+        &["Slash"],
+        &["Star"],
+        &["<end>"],
+    ];
+    assert_eq!(follow, calc::Rule::Factor.follow());
+    let follow: &[&[&str]] = &[&["RightParen"], &["<end>"]];
+    assert_eq!(follow, calc::Rule::Expr.follow());
+    assert_eq!("factor", calc::Rule::Factor.name());
+}
+
+#[test]
+fn lookahead() {
+    let source = "a = b; c; let d: e = 1; let f = 2;";
+    let tree = parse::<statements::Rule>(source, statements::program);
+    assert_eq!(source, tree.text());
+    assert_eq!(
+        "Program(Statement(Assign) Statement(Call) Statement(Binding) Statement(Binding))",
+        shape(&tree)
+    );
+    let first: &[&[&str]] = &[
+        &["Ident", "Equal"],
+        &["Ident", "SemiColon"],
+        &["Let", "Ident"],
+    ];
+    assert_eq!(first, statements::Rule::Statement.first());
+    let first: &[&[&str]] = &[
+        &[],
+        &["Ident", "Equal"],
+        &["Ident", "SemiColon"],
+        &["Let", "Ident"],
+    ];
+    assert_eq!(first, statements::Rule::Program.first());
+    assert_eq!(Span::new(0, 6), tree.child_nodes().next().unwrap().span());
+}
+
+#[test]
+fn traced() {
+    let mut parser = Parser::for_tree("1");
+    parser.lexer().enable_trace();
+    calc::expr(&mut parser).unwrap();
+    assert_eq!(
+        "\
+> expr @ 0:0..0
+  > term @ 0:0..0
+    > factor @ 0:0..0
+      token 1 @ 0:0..1
+    < factor @ 0:0..1
+  < term @ 0:0..1
+< expr @ 0:0..1
+",
+        parser.lexer().trace().unwrap().to_string()
+    );
+}
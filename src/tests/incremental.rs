@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use crate::{
+    cst::{GreenNode, SyntaxNode, relex_and_reparse, reparse},
+    diagnostics::Result,
+    lex::{Tok, relex},
+    parse::Parse,
+    tests::utils::{Lexer, Parser, TokKind, TokKind::*},
+    utils::{Library, Located, Span, TextEdit},
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Node {
+    Program,
+    Block,
+    Let,
+}
+
+type Tree<'s> = SyntaxNode<Node, TokKind<'s>>;
+
+fn item(p: &mut Parser<Node>) -> Result<()> {
+    if p.check(LeftBrace) {
+        block(p)
+    } else {
+        binding(p)
+    }
+}
+
+fn block(p: &mut Parser<Node>) -> Result<()> {
+    p.start_node(Node::Block);
+    p.require(LeftBrace)?;
+    while !p.check(RightBrace) {
+        item(p)?;
+    }
+    p.require(RightBrace)?;
+    p.finish_node();
+    Ok(())
+}
+
+fn binding(p: &mut Parser<Node>) -> Result<()> {
+    p.start_node(Node::Let);
+    p.require(Let)?;
+    p.take()?;
+    p.require(Equal)?;
+    p.take()?;
+    p.require(SemiColon)?;
+    p.finish_node();
+    Ok(())
+}
+
+fn program(source: &str) -> Tree<'_> {
+    let mut parser = Parser::for_tree(source);
+    parser.start_node(Node::Program);
+    while parser.soft_peek().unwrap().is_some() {
+        item(&mut parser).unwrap();
+    }
+    parser.finish_node();
+    parser.finish_tree(source)
+}
+
+fn parse_as<'s>(kind: &Node, text: &'s str) -> Option<Arc<GreenNode<Node, TokKind<'s>>>> {
+    let mut parser = Parser::for_tree(text);
+    match kind {
+        Node::Block => block(&mut parser).ok()?,
+        Node::Let => binding(&mut parser).ok()?,
+        Node::Program => return None,
+    }
+    if !matches!(parser.soft_peek(), Ok(None)) {
+        return None;
+    }
+    let tree: Tree<'s> = parser.finish_tree(text);
+    Some(tree.green().clone())
+}
+
+fn tokens(source: &str) -> Vec<(TokKind<'_>, Span)> {
+    Lexer::new(source)
+        .map(|tok| {
+            let tok = tok.unwrap();
+            (tok.kind.clone(), tok.span())
+        })
+        .collect()
+}
+
+fn spans<'s>(tokens: &[Tok<TokKind<'s>>]) -> Vec<(TokKind<'s>, Span)> {
+    tokens
+        .iter()
+        .map(|tok| (tok.kind.clone(), tok.span()))
+        .collect()
+}
+
+const SOURCE: &str = "let a = 1;\n{ let b = 2; { let c = 3; } }\n{ let d = 4; }\n";
+
+/// Applies each edit incrementally and checks the result against parsing the edited source from
+/// scratch.
+fn assert_equivalent(edit: TextEdit) -> Option<Span> {
+    let old = program(SOURCE);
+    let source = edit.apply(SOURCE);
+    let full = program(&source);
+    let reparsed = reparse(&old, &edit, &source, parse_as)?;
+    assert_eq!(source, reparsed.tree.text());
+    assert_eq!(full.green(), reparsed.tree.green());
+    Some(reparsed.changed)
+}
+
+#[test]
+fn reparse_innermost_node() {
+    let changed = assert_equivalent(TextEdit::new(Span::new(34, 35), "30")).unwrap();
+    assert_eq!(Span::new(26, 37), changed);
+}
+
+#[test]
+fn reparse_adds_statements() {
+    let changed = assert_equivalent(TextEdit::insert(53, " let e = 5;")).unwrap();
+    assert_eq!(Span::new(41, 66), changed);
+}
+
+#[test]
+fn reparse_falls_back_outward() {
+    // The innermost `let` becomes two statements, so it can only be parsed again as part of the
+    // block around it.
+    let changed = assert_equivalent(TextEdit::new(Span::new(32, 33), "= 3; let f =")).unwrap();
+    assert_eq!(Span::new(24, 49), changed);
+}
+
+#[test]
+fn reparse_unbalanced() {
+    let old = program(SOURCE);
+    let edit = TextEdit::delete(Span::new(37, 39));
+    let source = edit.apply(SOURCE);
+    assert!(reparse(&old, &edit, &source, parse_as).is_none());
+}
+
+#[test]
+fn reparse_outside_any_node() {
+    assert!(assert_equivalent(TextEdit::insert(10, "\n")).is_none());
+}
+
+#[test]
+fn reparse_reuses_subtrees() {
+    let old = program(SOURCE);
+    let edit = TextEdit::new(Span::new(34, 35), "30");
+    let source = edit.apply(SOURCE);
+    let new = reparse(&old, &edit, &source, parse_as).unwrap().tree;
+    let old_items: Vec<_> = old.child_nodes().collect();
+    let new_items: Vec<_> = new.child_nodes().collect();
+    assert!(Arc::ptr_eq(old_items[0].green(), new_items[0].green()));
+    assert!(!Arc::ptr_eq(old_items[1].green(), new_items[1].green()));
+    assert!(Arc::ptr_eq(old_items[2].green(), new_items[2].green()));
+    assert_eq!(Span::new(42, 56), new_items[2].span());
+}
+
+#[test]
+fn relex_window() {
+    let old: Vec<_> = Lexer::new(SOURCE).map(|tok| tok.unwrap()).collect();
+    for edit in [
+        TextEdit::new(Span::new(34, 35), "30"),
+        TextEdit::insert(5, "bc"),
+        TextEdit::insert(4, "x "),
+        TextEdit::delete(Span::new(11, 29)),
+        TextEdit::insert(SOURCE.len(), "let e = 5;"),
+        TextEdit::new(Span::new(0, 3), "lets"),
+    ] {
+        let source = edit.apply(SOURCE);
+        let relexed = relex(&old, &edit, |offset| Lexer::new(&source[offset..])).unwrap();
+        assert_eq!(tokens(&source), spans(&relexed.tokens), "{edit:?}");
+        assert!(relexed.relexed <= 6, "{edit:?} relexed {}", relexed.relexed);
+    }
+}
+
+#[test]
+fn relex_merges_tokens() {
+    let source = "ab = 1";
+    let old: Vec<_> = Lexer::new(source).map(|tok| tok.unwrap()).collect();
+    let edit = TextEdit::delete(Span::new(2, 5));
+    let new_source = edit.apply(source);
+    let relexed = relex(&old, &edit, |offset| Lexer::new(&new_source[offset..])).unwrap();
+    assert_eq!(
+        vec![(Ident("ab1"), Span::new(0, 3))],
+        spans(&relexed.tokens)
+    );
+    assert_eq!(Span::new(0, 3), relexed.changed);
+}
+
+#[test]
+fn relex_then_reparse() {
+    let old = program(SOURCE);
+    let old_tokens: Vec<_> = Lexer::new(SOURCE).map(|tok| tok.unwrap()).collect();
+    for (edit, expected) in [
+        (TextEdit::new(Span::new(34, 35), "30"), Span::new(26, 37)),
+        // Lexing again starts from the `{` before the edit, so the node parsed again must contain
+        // it as well.
+        (TextEdit::insert(26, "let x = 0; "), Span::new(11, 51)),
+    ] {
+        let source = edit.apply(SOURCE);
+        let lexer_at = |offset| Lexer::new(&source[offset..]);
+        let updated =
+            relex_and_reparse(&old, &old_tokens, &edit, &source, lexer_at, parse_as).unwrap();
+        assert_eq!(tokens(&source), spans(&updated.tokens.tokens), "{edit:?}");
+        let reparsed = updated.tree.unwrap();
+        assert_eq!(program(&source).green(), reparsed.tree.green(), "{edit:?}");
+        assert_eq!(expected, reparsed.changed, "{edit:?}");
+    }
+}
+
+#[test]
+fn library_edit() {
+    let mut library = Library::new();
+    let file_id = library.add("test".into(), "let a = 1;");
+    let edited = library
+        .edit(file_id, &TextEdit::new(Span::new(4, 5), "abc"))
+        .unwrap();
+    assert_eq!("let abc = 1;", edited);
+    assert_eq!(
+        "let abc = 1;",
+        library.get(file_id).unwrap().source().as_ref()
+    );
+    assert_eq!(7, TextEdit::insert(2, "xyz").map_offset(4));
+}
@@ -0,0 +1,71 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::{
+    lex::Lex,
+    tests::utils::Lexer,
+    utils::{Interner, Symbol},
+};
+
+#[test]
+fn intern_dedup() {
+    let interner = Interner::new();
+    let foo = interner.intern("foo");
+    let bar = interner.intern("bar");
+    assert_ne!(foo, bar);
+    assert_eq!(foo, interner.intern("foo"));
+    assert_eq!(Some(bar), interner.get("bar"));
+    assert_eq!(None, interner.get("baz"));
+    assert_eq!("foo", &*interner.resolve(foo));
+    assert_eq!(2, interner.len());
+}
+
+#[test]
+fn intern_outlives_source() {
+    let interner = Interner::new();
+    let symbols: Vec<Symbol> = {
+        let source = String::from("alpha 'beta' alpha");
+        let mut lexer = Lexer::new(&source);
+        let alpha = lexer.construct_ident_symbol(&interner).unwrap();
+        lexer.chomp();
+        let beta = lexer
+            .construct_string_symbol(&interner, &['\''], &['\\'])
+            .unwrap()
+            .unwrap();
+        lexer.chomp();
+        vec![
+            alpha,
+            beta,
+            lexer.construct_ident_symbol(&interner).unwrap(),
+        ]
+    };
+    assert_eq!(symbols[0], symbols[2]);
+    assert_eq!("beta", &*interner.resolve(symbols[1]));
+}
+
+#[test]
+fn intern_threaded() {
+    let interner = Arc::new(Interner::new());
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let interner = interner.clone();
+            std::thread::spawn(move || {
+                (0..100)
+                    .map(|i| interner.intern(&format!("sym{i}")))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let results: Vec<Vec<Symbol>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert!(results.iter().all(|symbols| symbols == &results[0]));
+    assert_eq!(100, results[0].iter().collect::<HashSet<_>>().len());
+    assert_eq!("sym42", &*interner.resolve(results[0][42]));
+}
+
+#[test]
+fn resolved_outlives_interner() {
+    let interner = Interner::new();
+    let symbol = interner.intern("kept");
+    let resolved = interner.resolve(symbol);
+    drop(interner);
+    assert_eq!("kept", &*resolved);
+}
@@ -0,0 +1,167 @@
+use crate::{
+    lex::{Lex, Tok, TokenKind},
+    tests::utils,
+    utils::{Location, Span},
+};
+
+crate::lexer! {
+    /// A lexer equivalent to the one in the test utils.
+    pub struct Lexer;
+
+    #[derive(PartialEq, Clone, Debug)]
+    pub enum TokKind<'s> {
+        #[keyword("let")]
+        Let,
+        #[keyword("const")]
+        Const,
+        #[literal("=")]
+        Equal,
+        #[literal("==")]
+        DoubleEqual,
+        #[literal(";")]
+        SemiColon,
+        #[hex("0x")]
+        Hex(&'s str),
+        #[float]
+        Float(f64),
+        #[integer]
+        Int(i64),
+        #[string(['"', '\''], ['\\'])]
+        String(&'s str),
+        #[comment("//")]
+        Comment(&'s str),
+        /// Identifiers are checked after everything else.
+        #[ident]
+        Ident(&'s str),
+    }
+}
+
+crate::lexer! {
+    struct OwnedLexer;
+
+    #[derive(PartialEq, Clone, Debug)]
+    enum OwnedKind {
+        #[literal("+")]
+        Plus,
+        #[ident]
+        Ident(String),
+        #[string('"', '\\')]
+        String(Box<str>),
+    }
+}
+
+crate::lexer! {
+    struct MisorderedLexer;
+
+    #[derive(PartialEq, Clone, Debug)]
+    enum MisorderedKind {
+        #[literal(".")]
+        Dot,
+        #[integer]
+        Int(i64),
+        #[float]
+        Float(f64),
+    }
+}
+
+/// Asserts that the generated lexer produces the same kinds as the handwritten one.
+fn assert_equivalent(source: &str) {
+    let mut expected = vec![];
+    let mut handwritten = utils::Lexer::new(source);
+    while let Some(tok) = handwritten.lex().unwrap() {
+        expected.push(tok.kind.to_string());
+    }
+    let generated: Vec<String> = Lexer::new(source, 0)
+        .map(|tok| tok.unwrap().kind.to_string())
+        .collect();
+    pretty_assertions::assert_eq!(expected, generated);
+}
+
+#[test]
+fn macro_equivalence() {
+    assert_equivalent("let foo = 0xff;");
+    assert_equivalent("const bar == 1_000 1.5e+02 'hi' \"there\"");
+    assert_equivalent("// comment\nlet letter = constant;");
+}
+
+#[test]
+fn macro_kinds() {
+    let kinds: Vec<TokKind> = Lexer::new("let x == 'y' 0x1", 0)
+        .map(|tok| tok.unwrap().kind)
+        .collect();
+    pretty_assertions::assert_eq!(
+        vec![
+            TokKind::Let,
+            TokKind::Ident("x"),
+            TokKind::DoubleEqual,
+            TokKind::String("y"),
+            TokKind::Hex("1"),
+        ],
+        kinds
+    );
+}
+
+#[test]
+fn macro_file_ids() {
+    let toks: Vec<Tok<TokKind>> = Lexer::new("a =", 4).map(|tok| tok.unwrap()).collect();
+    assert_eq!(Location::new(4, Span::new(0, 1)), toks[0].location);
+    assert_eq!(Location::new(4, Span::new(2, 3)), toks[1].location);
+}
+
+#[test]
+fn macro_errors() {
+    let mut lexer = Lexer::new("a $", 2);
+    assert!(lexer.next().unwrap().is_ok());
+    let err = lexer.next().unwrap().unwrap_err();
+    assert_eq!(
+        format!("{:?}", crate::lex::UnexpectedChar(Location::new(2, 2..3))),
+        format!("{err:?}")
+    );
+    assert!(Lexer::new("'oops", 0).next().unwrap().is_err());
+    assert!(lexer.next().is_none());
+}
+
+#[test]
+fn macro_owned_payloads() {
+    let kinds: Vec<OwnedKind> = OwnedLexer::new("a + \"b\"", 0)
+        .map(|tok| tok.unwrap().kind)
+        .collect();
+    pretty_assertions::assert_eq!(
+        vec![
+            OwnedKind::Ident("a".into()),
+            OwnedKind::Plus,
+            OwnedKind::String("b".into()),
+        ],
+        kinds
+    );
+}
+
+#[test]
+fn macro_idents() {
+    assert_eq!(Some("x"), TokKind::Ident("x").ident());
+    assert_eq!(None, TokKind::Let.ident());
+    assert_eq!(None, TokKind::String("x").ident());
+    assert_eq!(Some("a"), OwnedKind::Ident("a".into()).ident());
+    assert_eq!(None, OwnedKind::Plus.ident());
+}
+
+#[test]
+fn macro_rule_order() {
+    let kinds: Vec<TokKind> = Lexer::new("1.5 2", 0)
+        .map(|tok| tok.unwrap().kind)
+        .collect();
+    pretty_assertions::assert_eq!(vec![TokKind::Float(1.5), TokKind::Int(2)], kinds);
+
+    // The integer rule is declared first, so it claims the start of the float.
+    let kinds: Vec<MisorderedKind> = MisorderedLexer::new("1.5", 0)
+        .map(|tok| tok.unwrap().kind)
+        .collect();
+    pretty_assertions::assert_eq!(
+        vec![
+            MisorderedKind::Int(1),
+            MisorderedKind::Dot,
+            MisorderedKind::Int(5),
+        ],
+        kinds
+    );
+}
@@ -0,0 +1,111 @@
+use crate::{
+    lex::{CharStream, Lex, Token},
+    tests::utils::{Lexer, TokKind},
+    utils::{ColumnUnit, LineColumn, LineIndex, Location, Span},
+};
+
+#[test]
+fn line_index_ascii() {
+    let index = LineIndex::new("let a\nlet b\n\nc");
+    assert_eq!(4, index.line_count());
+    assert_eq!(
+        LineColumn::new(0, 0),
+        index.line_column(0, ColumnUnit::Char)
+    );
+    assert_eq!(
+        LineColumn::new(0, 5),
+        index.line_column(5, ColumnUnit::Char)
+    );
+    assert_eq!(
+        LineColumn::new(1, 4),
+        index.line_column(10, ColumnUnit::Char)
+    );
+    assert_eq!(
+        LineColumn::new(2, 0),
+        index.line_column(12, ColumnUnit::Char)
+    );
+    assert_eq!(
+        LineColumn::new(3, 1),
+        index.line_column(100, ColumnUnit::Char)
+    );
+}
+
+#[test]
+fn line_index_units() {
+    // 'é' is two bytes and one utf-16 unit, '𝄞' is four bytes and two utf-16 units
+    let source = "x\né𝄞 = 1";
+    let index = LineIndex::new(source);
+    let offset = source.find('=').unwrap();
+    assert_eq!(
+        LineColumn::new(1, 3),
+        index.line_column(offset, ColumnUnit::Char)
+    );
+    assert_eq!(
+        LineColumn::new(1, 7),
+        index.line_column(offset, ColumnUnit::Byte)
+    );
+    assert_eq!(
+        LineColumn::new(1, 4),
+        index.line_column(offset, ColumnUnit::Utf16)
+    );
+}
+
+#[test]
+fn line_index_inside_char() {
+    // '€' is three bytes and one utf-16 unit, '𝄞' is four bytes and two utf-16 units
+    let source = "x\n€𝄞";
+    let index = LineIndex::new(source);
+    for (offset, char, utf16) in [(3, 0, 0), (4, 0, 0), (5, 1, 1), (7, 1, 1), (9, 2, 3)] {
+        assert_eq!(
+            LineColumn::new(1, char),
+            index.line_column(offset, ColumnUnit::Char)
+        );
+        assert_eq!(
+            LineColumn::new(1, utf16),
+            index.line_column(offset, ColumnUnit::Utf16)
+        );
+    }
+    assert_eq!(
+        LineColumn::new(1, 1),
+        index.line_column(3, ColumnUnit::Byte)
+    );
+}
+
+#[test]
+fn line_index_location() {
+    let index = LineIndex::new("a\nbb cc");
+    let location = Location::new(7, Span::new(5, 7));
+    assert_eq!(
+        (LineColumn::new(1, 3), LineColumn::new(1, 5)),
+        index.locate(&location, ColumnUnit::Char)
+    );
+    assert_eq!("2:4", LineColumn::new(1, 3).to_string());
+}
+
+#[test]
+fn char_stream_tracking() {
+    let mut stream = CharStream::with_line_tracking("ab\ncé\nd");
+    assert_eq!(Some(LineColumn::new(0, 0)), stream.line_column());
+    stream.chomp();
+    stream.chomp();
+    stream.chomp();
+    assert_eq!(Some(LineColumn::new(1, 0)), stream.line_column());
+    stream.peek_while(|c| c != 'd');
+    stream.chomp_peeks();
+    assert_eq!(Some(LineColumn::new(2, 0)), stream.line_column());
+    assert_eq!(7, stream.position());
+    assert_eq!(None, CharStream::new("abc").line_column());
+}
+
+#[test]
+fn multibyte_spans() {
+    let source = "é = 'ü'";
+    let mut lexer = Lexer::new(source);
+    let ident = lexer.lex().unwrap().unwrap();
+    assert_eq!(&TokKind::Ident("é"), ident.kind_ref());
+    assert_eq!(Span::new(0, 2), ident.location.span());
+    lexer.lex().unwrap();
+    let string = lexer.lex().unwrap().unwrap();
+    assert_eq!(&TokKind::String("ü"), string.kind_ref());
+    assert_eq!(Span::new(5, 9), string.location.span());
+}
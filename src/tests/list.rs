@@ -0,0 +1,111 @@
+use crate::{
+    diagnostics::Result,
+    lex::Token,
+    parse::{ExpectedOneOf, Parse, Trailing, TrailingSeparator},
+    tests::utils::{Parser, TokKind::*, assert_diag},
+    utils::{Located, Location, Span},
+};
+
+fn ident<'s>(p: &mut Parser<'s>) -> Result<&'s str> {
+    match p.take()?.kind() {
+        Ident(ident) => Ok(ident),
+        _ => Err(p.expected_one_of()),
+    }
+}
+
+#[test]
+fn sep_by() {
+    let mut parser = Parser::new("(a, b, c)");
+    parser.require(LeftParen).unwrap();
+    let list = parser
+        .sep_by(Comma, RightParen, Trailing::Allow, ident)
+        .unwrap();
+    assert_eq!(vec!["a", "b", "c"], list.items);
+    assert_eq!(Span::new(1, 8), list.span());
+    assert!(parser.require(RightParen).is_ok());
+}
+
+#[test]
+fn sep_by_empty() {
+    let mut parser = Parser::new("( )");
+    parser.require(LeftParen).unwrap();
+    let list = parser
+        .sep_by(Comma, RightParen, Trailing::Allow, ident)
+        .unwrap();
+    assert!(list.items.is_empty());
+    assert_eq!(Span::new(2, 2), list.span());
+    assert!(
+        parser
+            .sep_by1(Comma, RightParen, Trailing::Allow, ident)
+            .is_err()
+    );
+}
+
+#[test]
+fn trailing_allowed() {
+    let mut parser = Parser::new("a, b,)");
+    let list = parser
+        .sep_by(Comma, RightParen, Trailing::Allow, ident)
+        .unwrap();
+    assert_eq!(vec!["a", "b"], list.items);
+    assert_eq!(Span::new(0, 5), list.span());
+    assert!(parser.lexer().diagnostics().is_empty());
+}
+
+#[test]
+fn trailing_forbidden() {
+    let mut parser = Parser::new("a, b,");
+    let list = parser
+        .sep_by(Comma, RightParen, Trailing::Forbid, ident)
+        .unwrap();
+    assert_eq!(vec!["a", "b"], list.items);
+    let diags = parser.lexer().take_diagnostics();
+    assert_eq!(1, diags.len());
+    let expected = TrailingSeparator(Location::new(0, Span::new(4, 5)));
+    assert_diag(expected, &diags[0]);
+}
+
+#[test]
+fn trailing_required() {
+    let mut parser = Parser::new("a | b |;");
+    let list = parser
+        .sep_by1(Bar, SemiColon, Trailing::Require, ident)
+        .unwrap();
+    assert_eq!(vec!["a", "b"], list.items);
+
+    let mut parser = Parser::new("a | b;");
+    let err = parser
+        .sep_by1(Bar, SemiColon, Trailing::Require, ident)
+        .unwrap_err();
+    let expected = ExpectedOneOf(
+        vec!["|".into()],
+        Some(";".into()),
+        Location::new(0, Span::new(5, 6)),
+    );
+    assert_diag(expected, &err);
+}
+
+#[test]
+fn terminated_by() {
+    let mut parser = Parser::new("{ a; b; }");
+    parser.require(LeftBrace).unwrap();
+    let list = parser.terminated_by(SemiColon, RightBrace, ident).unwrap();
+    assert_eq!(vec!["a", "b"], list.items);
+    assert_eq!(Span::new(2, 7), list.span());
+    assert!(parser.require(RightBrace).is_ok());
+
+    let mut parser = Parser::new("a; b }");
+    assert!(parser.terminated_by(SemiColon, RightBrace, ident).is_err());
+}
+
+#[test]
+fn many_until() {
+    let mut parser = Parser::new("a b c ;");
+    let list = parser.many_until(SemiColon, ident).unwrap();
+    assert_eq!(vec!["a", "b", "c"], list.items);
+    assert_eq!(Span::new(0, 5), list.span());
+    assert_eq!(&SemiColon, parser.peek().unwrap().kind_ref());
+
+    let mut parser = Parser::new("a b");
+    assert_eq!(2, parser.many_until(SemiColon, ident).unwrap().items.len());
+}
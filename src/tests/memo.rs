@@ -0,0 +1,161 @@
+use crate::{
+    cst::SyntaxNode,
+    diagnostics::Result,
+    lex::Token,
+    parse::{LeftRecursion, Parse},
+    tests::utils::{
+        Parser, TokKind,
+        TokKind::{Bang, Comma, Ident, Int, LeftParen, Minus, Plus, RightParen, SemiColon},
+        assert_diag,
+    },
+    utils::{Location, Span},
+};
+
+fn int(p: &mut Parser) -> Result<String> {
+    let tok = p.take()?;
+    match tok.kind_ref() {
+        Int(int) => Ok(int.to_string()),
+        _ => Err(p.expected_one_of()),
+    }
+}
+
+/// `expr := expr '-' int | int`
+fn expr(p: &mut Parser) -> Result<String> {
+    p.memoize("expr", |p| {
+        let binary = p.attempt(|p| {
+            let lhs = expr(p)?;
+            p.require(Minus)?;
+            let rhs = int(p)?;
+            Ok(format!("({lhs} - {rhs})"))
+        });
+        binary.or_else(|_| int(p))
+    })
+}
+
+/// `group := '(' group ')' '!' | '(' group ')' | int`, which backtracks over every group twice.
+fn group(p: &mut Parser) -> Result<usize> {
+    p.memoize("group", |p| {
+        let inner = |p: &mut Parser| {
+            p.require(LeftParen)?;
+            let depth = group(p)?;
+            p.require(RightParen)?;
+            Ok(depth + 1)
+        };
+        let bang = p.attempt(|p| {
+            let depth = inner(p)?;
+            p.require(Bang)?;
+            Ok(depth)
+        });
+        bang.or_else(|_| p.attempt(inner))
+            .or_else(|_| int(p).map(|_| 0))
+    })
+}
+
+#[test]
+fn left_recursion() {
+    let mut parser = Parser::new("1 - 2 - 3;");
+    assert_eq!("((1 - 2) - 3)", expr(&mut parser).unwrap());
+    assert_eq!(&SemiColon, parser.peek().unwrap().kind_ref());
+    assert_eq!(9, parser.cursor());
+    let stats = parser.lexer().memo().stats("expr").unwrap();
+    assert_eq!(2, stats.grown);
+
+    let mut parser = Parser::new("7");
+    assert_eq!("7", expr(&mut parser).unwrap());
+    assert!(parser.soft_peek().unwrap().is_none());
+}
+
+#[test]
+fn reuses_results() {
+    let mut parser = Parser::new("((((((((((1))))))))))");
+    assert_eq!(10, group(&mut parser).unwrap());
+    assert!(parser.soft_peek().unwrap().is_none());
+    let stats = *parser.lexer().memo().stats("group").unwrap();
+    assert_eq!(11, stats.misses);
+    assert!(stats.hits >= 10);
+    assert!(stats.hit_rate() > 0.4);
+    assert_eq!(stats, parser.lexer().memo().total_stats());
+}
+
+#[test]
+fn reuses_failures() {
+    let mut parser = Parser::new("a");
+    assert!(parser.memoize("int", int).is_err());
+    assert_eq!(0, parser.cursor());
+    assert!(parser.memoize("int", int).is_err());
+    let stats = parser.lexer().memo().stats("int").unwrap();
+    assert_eq!((1, 1), (stats.hits, stats.misses));
+    assert_eq!(&Ident("a"), parser.take().unwrap().kind_ref());
+}
+
+#[test]
+fn skips_to_stored_end() {
+    let mut parser = Parser::new("1 - 2 ;");
+    let first = parser.attempt(|p| {
+        expr(p)?;
+        p.require(Comma)
+    });
+    assert!(first.is_err());
+    assert_eq!(0, parser.cursor());
+    let hits = parser.lexer().memo().stats("expr").unwrap().hits;
+    assert_eq!("(1 - 2)", expr(&mut parser).unwrap());
+    assert_eq!(6, parser.cursor());
+    assert_eq!(5, parser.lexer().previous_end());
+    assert_eq!(hits + 1, parser.lexer().memo().stats("expr").unwrap().hits);
+    parser.lexer().memo_mut().clear();
+    assert!(parser.lexer().memo().is_empty());
+}
+
+#[test]
+fn left_recursion_without_alternative() {
+    fn endless(p: &mut Parser) -> Result<()> {
+        p.memoize("endless", |p| {
+            endless(p)?;
+            p.require(Plus).map(|_| ())
+        })
+    }
+    let mut parser = Parser::new("1 +");
+    let err = endless(&mut parser).unwrap_err();
+    let expected = LeftRecursion("endless".into(), Location::new(0, Span::new(0, 0)));
+    assert_diag(expected, &err);
+}
+
+#[test]
+fn result_types_kept_apart() {
+    let mut parser = Parser::new("1");
+    let checkpoint = parser.lexer().checkpoint();
+    assert_eq!("1", parser.memoize("rule", int).unwrap());
+    parser.lexer().rewind(checkpoint);
+    parser.set_cursor(0);
+    let len = parser.memoize("rule", |p| int(p).map(|int| int.len()));
+    assert_eq!(1, len.unwrap());
+    let stats = parser.lexer().memo().stats("rule").unwrap();
+    assert_eq!((0, 2), (stats.hits, stats.misses));
+}
+
+#[test]
+fn reused_tokens_recorded() {
+    let source = "1 2";
+    let mut parser = Parser::new(source);
+    parser.start_node(());
+    let checkpoint = parser.lexer().checkpoint();
+    let node = |p: &mut Parser| {
+        p.memoize("node", |p| {
+            p.start_node(());
+            let int = int(p)?;
+            p.finish_node();
+            Ok(int)
+        })
+    };
+    node(&mut parser).unwrap();
+    parser.lexer().rewind(checkpoint);
+    parser.set_cursor(0);
+    assert_eq!("1", node(&mut parser).unwrap());
+    parser.take().unwrap();
+    parser.finish_node();
+    // The node is not recorded again, but the token it consumed is.
+    let tree: SyntaxNode<(), TokKind> = parser.finish_tree(source);
+    assert_eq!(0, tree.child_nodes().count());
+    assert_eq!(2, tree.child_tokens().count());
+    assert_eq!(source, tree.text());
+}
@@ -0,0 +1,152 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    cst::SyntaxNode,
+    diagnostics::Result,
+    lex::{Token, UnexpectedChar},
+    parse::{ExpectedOneOf, ExpectedToken, Parse, Trailing, TrailingSeparator},
+    tests::utils::{Parser, TokKind, TokKind::*, assert_diag},
+    utils::{Located, Location, Span},
+};
+
+#[derive(Debug, PartialEq)]
+enum Stmt<'s> {
+    Let(&'s str),
+    Error(Span),
+}
+
+fn stmt<'s>(p: &mut Parser<'s>) -> Result<Stmt<'s>> {
+    p.require(Let)?;
+    let tok = p.take()?;
+    let location = tok.location();
+    let Ident(name) = tok.kind() else {
+        return Err(ExpectedToken("identifier".into(), location).into());
+    };
+    Ok(Stmt::Let(name))
+}
+
+fn program<'s>(p: &mut Parser<'s>) -> Result<Vec<Stmt<'s>>> {
+    let mut stmts = vec![];
+    while p.soft_peek()?.is_some() {
+        stmts.push(p.recover(&[SemiColon], stmt, |location| Stmt::Error(location.span())));
+        p.require(SemiColon)?;
+    }
+    Ok(stmts)
+}
+
+fn location(start: usize, end: usize) -> Location {
+    Location::new(0, Span::new(start, end))
+}
+
+#[test]
+fn clean_parse() {
+    let mut parser = Parser::new("let a; let b;");
+    let output = parser.run(program);
+    assert_eq!(Some(vec![Stmt::Let("a"), Stmt::Let("b")]), output.value);
+    assert!(output.diagnostics.is_empty());
+    assert!(!output.has_errors());
+    assert_eq!(
+        vec![Stmt::Let("a"), Stmt::Let("b")],
+        output.into_result().unwrap()
+    );
+}
+
+#[test]
+fn partial_value() {
+    let mut parser = Parser::new("let a; let 1; let b;");
+    let output = parser.run(program);
+    assert_eq!(
+        Some(vec![
+            Stmt::Let("a"),
+            Stmt::Error(Span::new(7, 12)),
+            Stmt::Let("b"),
+        ]),
+        output.value
+    );
+    assert!(output.has_errors());
+    let expected = ExpectedToken("identifier".into(), location(11, 12));
+    assert_eq!(1, output.errors().count());
+    assert_diag(expected, &output.diagnostics[0]);
+    assert!(output.into_result().is_err());
+}
+
+#[test]
+fn failed_parse() {
+    let mut parser = Parser::new("let a let b; $");
+    let output = parser.run(program);
+    assert!(output.value.is_none());
+    // The lexing error after where the parse failed is still reported.
+    assert_eq!(2, output.errors().count());
+    let expected = UnexpectedChar(location(13, 14));
+    assert_diag(expected, &output.diagnostics[1]);
+}
+
+#[test]
+fn lex_errors() {
+    let mut parser = Parser::new("let a; $ let b;");
+    let output = parser.run(program);
+    assert!(output.value.is_none());
+    assert_eq!(1, output.diagnostics.len());
+
+    let mut parser = Parser::new("let a; $");
+    let output = parser.run(|p| stmt(p));
+    assert_eq!(Some(Stmt::Let("a")), output.value);
+    assert_eq!(2, output.diagnostics.len());
+    let expected = UnexpectedChar(location(7, 8));
+    assert_diag(expected, &output.diagnostics[1]);
+}
+
+#[test]
+fn leftover_input() {
+    let mut parser = Parser::new("let a; let b;");
+    let output = parser.run(|p| stmt(p));
+    assert_eq!(Some(Stmt::Let("a")), output.value);
+    assert_eq!(1, output.errors().count());
+    let expected = ExpectedOneOf(vec![], Some(";".into()), location(5, 6));
+    assert_diag(expected, &output.diagnostics[0]);
+
+    // Lexing errors before the leftover Token are reported first.
+    let mut parser = Parser::new("let a $ = 1");
+    let output = parser.run(|p| stmt(p));
+    assert_eq!(2, output.errors().count());
+    assert_diag(UnexpectedChar(location(6, 7)), &output.diagnostics[0]);
+    let expected = ExpectedOneOf(vec![], Some("=".into()), location(8, 9));
+    assert_diag(expected, &output.diagnostics[1]);
+}
+
+#[test]
+fn warnings() {
+    let mut parser = Parser::new("a, b,");
+    let output = parser
+        .run(|p| {
+            p.sep_by(Comma, RightParen, Trailing::Forbid, |p| {
+                match p.take()?.kind() {
+                    Ident(name) => Ok(name),
+                    _ => Err(p.expected_one_of()),
+                }
+            })
+        })
+        .map(|list| list.items);
+    assert!(!output.has_errors());
+    assert_eq!(1, output.warnings().count());
+    let expected = TrailingSeparator(location(4, 5));
+    assert_diag(expected, &output.diagnostics[0]);
+    assert_eq!(vec!["a", "b"], output.into_result().unwrap());
+}
+
+#[test]
+fn syntax_tree_unaffected() {
+    let source = "let a; $ let b;";
+    let mut parser = Parser::new(source);
+    parser.start_node(());
+    let output = parser.run(|p| {
+        p.require(Let)?;
+        p.take()
+    });
+    parser.finish_node();
+    // The `;` left over and the lexing error after it.
+    assert_eq!(2, output.diagnostics.len());
+    let tree: SyntaxNode<(), TokKind> = parser.finish_tree(source);
+    assert_eq!(source, tree.text());
+    assert_eq!(2, tree.child_tokens().count());
+}
@@ -0,0 +1,195 @@
+use crate::{
+    lex::Token,
+    parse::{ExpectedKeyword, ExpectedOneOf, Parse},
+    tests::utils::{Parser, TokKind::*, assert_diag},
+    utils::{Located, Location, Span},
+};
+
+#[test]
+fn peek_nth() {
+    let mut parser = Parser::new("foo : bar = 1");
+    assert_eq!(
+        &Ident("foo"),
+        parser.peek_nth(0).unwrap().unwrap().kind_ref()
+    );
+    assert_eq!(&Equal, parser.peek_nth(3).unwrap().unwrap().kind_ref());
+    assert_eq!(&Int(1), parser.peek_nth(4).unwrap().unwrap().kind_ref());
+    assert!(parser.peek_nth(5).unwrap().is_none());
+    assert_eq!(&Ident("foo"), parser.take().unwrap().kind_ref());
+    assert_eq!(
+        &Ident("bar"),
+        parser.peek_nth(1).unwrap().unwrap().kind_ref()
+    );
+}
+
+#[test]
+fn match_sequence() {
+    let mut parser = Parser::new("foo(bar)");
+    assert!(parser.match_sequence(&[Ident("foo"), LeftParen]));
+    assert!(!parser.match_sequence(&[Ident("foo"), Colon]));
+    assert!(!parser.match_sequence(&[Ident("foo"), LeftParen, Ident("bar"), RightParen, Comma]));
+    assert!(parser.match_take(Ident("foo")).is_some());
+    assert!(parser.match_sequence(&[LeftParen, Ident("bar"), RightParen]));
+}
+
+#[test]
+fn cursor_bookkeeping() {
+    let mut parser = Parser::new("let  foo = 1");
+    let start = parser.next_tok_boundary();
+    parser.require(Let).unwrap();
+    assert_eq!(5, parser.cursor());
+    parser.peek_nth(2).unwrap();
+    assert_eq!(5, parser.cursor());
+    parser.take().unwrap();
+    parser.take().unwrap();
+    let one = parser.take().unwrap();
+    assert_eq!(Span::new(11, 12), one.span());
+    assert_eq!(Span::new(0, 12), parser.location(start).span());
+    assert!(parser.take().is_err());
+}
+
+#[test]
+fn lex_errors_in_lookahead() {
+    let mut parser = Parser::new("a $ b");
+    assert!(parser.peek_nth(1).is_err());
+    assert!(!parser.match_sequence(&[Ident("a"), Ident("b")]));
+    // The error was not consumed by looking past it.
+    assert_eq!(&Ident("a"), parser.peek().unwrap().kind_ref());
+    parser.take().unwrap();
+    assert!(parser.peek().is_err());
+    assert_eq!(&Ident("b"), parser.take().unwrap().kind_ref());
+}
+
+#[test]
+fn attempt_rewinds() {
+    let mut parser = Parser::new("foo(bar)");
+    let failed = parser.attempt(|p| {
+        p.require(Ident("foo"))?;
+        p.require(Colon)
+    });
+    assert!(failed.is_err());
+    assert_eq!(0, parser.cursor());
+    let call = parser.attempt(|p| {
+        let name = p.require(Ident("foo"))?;
+        p.require(LeftParen)?;
+        let arg = p.require(Ident("bar"))?;
+        p.require(RightParen)?;
+        Ok((name, arg))
+    });
+    assert_eq!(&Ident("bar"), call.unwrap().1.kind_ref());
+    assert!(parser.soft_peek().unwrap().is_none());
+}
+
+#[test]
+fn attempt_nested() {
+    let mut parser = Parser::new("a b c");
+    let result = parser.attempt(|p| {
+        p.take()?;
+        assert!(p.attempt(|p| p.require(Ident("c"))).is_err());
+        assert_eq!(&Ident("b"), p.peek()?.kind_ref());
+        p.take()?;
+        p.require(Ident("d"))
+    });
+    assert!(result.is_err());
+    assert_eq!(&Ident("a"), parser.take().unwrap().kind_ref());
+}
+
+#[test]
+fn attempt_resurfaces_lex_errors() {
+    let mut parser = Parser::new("a $ b");
+    let result = parser.attempt(|p| {
+        p.take()?;
+        p.take()
+    });
+    assert!(result.is_err());
+    parser.take().unwrap();
+    assert!(parser.take().is_err());
+    assert_eq!(&Ident("b"), parser.take().unwrap().kind_ref());
+}
+
+#[test]
+fn expected_one_of() {
+    let mut parser = Parser::new("a )");
+    parser.take().unwrap();
+    assert!(parser.match_take(SemiColon).is_none());
+    assert!(!parser.check(RightBrace));
+    assert!(parser.match_take(SemiColon).is_none());
+    let expected = ExpectedOneOf(
+        vec![";".into(), "}".into()],
+        Some(")".into()),
+        Location::new(0, Span::new(2, 3)),
+    );
+    assert_diag(expected, &parser.expected_one_of());
+    let err = parser.require(Comma).unwrap_err();
+    let expected = ExpectedOneOf(
+        vec![";".into(), "}".into(), ",".into()],
+        Some(")".into()),
+        Location::new(0, Span::new(2, 3)),
+    );
+    assert_diag(expected, &err);
+}
+
+#[test]
+fn expected_resets_between_positions() {
+    let mut parser = Parser::new("a b");
+    assert!(parser.match_take(Comma).is_none());
+    assert!(parser.match_take(Ident("a")).is_some());
+    assert!(parser.lexer().expected().is_empty());
+    assert!(parser.match_take(Colon).is_none());
+    assert_eq!(vec![":".to_string()], parser.lexer().expected());
+    parser.take().unwrap();
+    let expected = ExpectedOneOf(vec![], None, Location::new(0, Span::new(3, 3)));
+    assert_diag(expected, &parser.expected_one_of());
+}
+
+#[test]
+fn expected_survives_attempt() {
+    let mut parser = Parser::new("foo)");
+    assert!(parser.attempt(|p| p.require(LeftParen)).is_err());
+    assert!(parser.attempt(|p| p.require(LeftBracket)).is_err());
+    let expected = ExpectedOneOf(
+        vec!["(".into(), "[".into()],
+        Some("foo".into()),
+        Location::new(0, Span::new(0, 3)),
+    );
+    assert_diag(expected, &parser.expected_one_of());
+}
+
+#[test]
+fn contextual_keywords() {
+    let mut parser = Parser::new("union union = let");
+    assert!(parser.match_contextual("struct").is_none());
+    assert!(parser.check_contextual("union"));
+    let keyword = parser.match_contextual("union").unwrap();
+    assert_eq!(Span::new(0, 5), keyword.span());
+    // Elsewhere, the keyword is still an identifier.
+    assert_eq!(
+        &Ident("union"),
+        parser.require(Ident("union")).unwrap().kind_ref()
+    );
+    assert!(!parser.check_contextual("union"));
+    parser.take().unwrap();
+    // Real keywords are not identifiers.
+    assert!(parser.match_contextual("let").is_none());
+}
+
+#[test]
+fn require_contextual() {
+    let mut parser = Parser::new("async fn");
+    assert!(parser.require_contextual("async").is_ok());
+    let err = parser.require_contextual("await").unwrap_err();
+    assert_diag(
+        ExpectedKeyword("await".into(), Location::new(0, Span::new(6, 8))),
+        &err,
+    );
+
+    let mut parser = Parser::new("fn");
+    assert!(parser.match_take(Let).is_none());
+    let err = parser.require_contextual("async").unwrap_err();
+    let expected = ExpectedOneOf(
+        vec!["let".into(), "async".into()],
+        Some("fn".into()),
+        Location::new(0, Span::new(0, 2)),
+    );
+    assert_diag(expected, &err);
+}
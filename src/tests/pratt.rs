@@ -0,0 +1,250 @@
+use crate::{
+    diagnostics::Result,
+    lex::{Tok, Token},
+    parse::{
+        Assoc, ChainedComparison, ExpectedExpression, NestingTooDeep, Parse, Pratt, Precedence,
+    },
+    tests::utils::{
+        Parser, TokKind,
+        TokKind::{
+            Bang, Colon, Comma, DoubleEqual, Equal, Greater, Ident, Int, LeftParen, Less, Minus,
+            Plus, Question, RightParen, SemiColon, Slash, Star,
+        },
+        assert_diag,
+    },
+    utils::{Located, Location, Span},
+};
+
+/// Expressions are built as s-expressions so they can be compared as strings.
+type Expr<'s> = Pratt<'s, Parser<'s>, Tok<TokKind<'s>>, TokKind<'s>, (String, Location)>;
+
+fn expr<'s>() -> Expr<'s> {
+    Pratt::new(|pratt: &Expr<'s>, p: &mut Parser<'s>| {
+        let tok = p.peek()?;
+        let location = tok.location();
+        Ok(match tok.kind_ref().clone() {
+            Int(int) => {
+                p.take()?;
+                Some((int.to_string(), location))
+            }
+            Ident(ident) => {
+                p.take()?;
+                Some((ident.to_string(), location))
+            }
+            LeftParen => {
+                p.take()?;
+                let inner = pratt.parse(p)?;
+                p.require(RightParen)?;
+                Some(inner)
+            }
+            _ => None,
+        })
+    })
+    .prefix(Minus, 3, |_, op, rhs, location| {
+        Ok((format!("({} {})", op.kind(), rhs.0), location))
+    })
+    .infix(Plus, 1, Assoc::Left, binary)
+    .infix(Minus, 1, Assoc::Left, binary)
+    .infix(Star, 2, Assoc::Left, binary)
+    .infix(Slash, 2, Assoc::Left, binary)
+    .infix(Equal, 0, Assoc::Right, binary)
+    .postfix(Bang, 4, |_, _, lhs, op, location| {
+        Ok((format!("({} {})", op.kind(), lhs.0), location))
+    })
+    .postfix(LeftParen, 4, |pratt, p, lhs, _, _| {
+        let mut call = format!("(call {}", lhs.0);
+        while p.match_take(RightParen).is_none() {
+            call.push(' ');
+            call.push_str(&pratt.parse(p)?.0);
+            p.match_take(Comma);
+        }
+        call.push(')');
+        let span = Span::new(lhs.1.span().start(), p.lexer().previous_end());
+        Ok((call, Location::new(lhs.1.file_id(), span)))
+    })
+    .mixfix(Question, Colon, 0, Assoc::Right, |_, parts, location| {
+        Ok((
+            format!("(? {} {} {})", parts.lhs.0, parts.middle.0, parts.rhs.0),
+            location,
+        ))
+    })
+}
+
+fn binary<'s>(
+    _: &mut Parser<'s>,
+    lhs: (String, Location),
+    op: Tok<TokKind<'s>>,
+    rhs: (String, Location),
+    location: Location,
+) -> Result<(String, Location)> {
+    Ok((format!("({} {} {})", op.kind(), lhs.0, rhs.0), location))
+}
+
+fn parse(source: &str) -> (String, Location) {
+    let mut parser = Parser::new(source);
+    let expr = expr().parse(&mut parser).unwrap();
+    assert!(parser.soft_peek().unwrap().is_none());
+    expr
+}
+
+#[test]
+fn precedence() {
+    assert_eq!("(+ 1 (* 2 3))", parse("1 + 2 * 3").0);
+    assert_eq!("(* (+ 1 2) 3)", parse("(1 + 2) * 3").0);
+    assert_eq!("(- (/ 1 2) (* 3 4))", parse("1 / 2 - 3 * 4").0);
+}
+
+#[test]
+fn associativity() {
+    assert_eq!("(- (- a b) c)", parse("a - b - c").0);
+    assert_eq!("(= a (= b c))", parse("a = b = c").0);
+}
+
+#[test]
+fn prefix_and_postfix() {
+    assert_eq!("(+ (- 1) 2)", parse("-1 + 2").0);
+    assert_eq!("(- (! a))", parse("-a!").0);
+    assert_eq!("(* (! a) b)", parse("a! * b").0);
+    assert_eq!("(call f a (+ b 1))", parse("f(a, b + 1)").0);
+    assert_eq!("(! (call (call f) a))", parse("f()(a)!").0);
+}
+
+#[test]
+fn mixfix() {
+    assert_eq!("(? a (= b c) d)", parse("a ? b = c : d").0);
+    assert_eq!("(? a b (? c d e))", parse("a ? b : c ? d : e").0);
+    assert_eq!("(= x (? a b c))", parse("x = a ? b : c").0);
+}
+
+#[test]
+fn locations() {
+    assert_eq!(Span::new(0, 9), parse("1 + 2 * 3").1.span());
+    assert_eq!(Span::new(0, 11), parse("(1 + 2) * 3").1.span());
+    let mut parser = Parser::new("a + -b! ;");
+    let pratt = expr();
+    let expr = pratt.parse(&mut parser).unwrap();
+    assert_eq!(Span::new(0, 7), expr.1.span());
+    assert_eq!(&SemiColon, parser.peek().unwrap().kind_ref());
+}
+
+#[test]
+fn expected_expression() {
+    let mut parser = Parser::new("1 + ;");
+    let err = expr().parse(&mut parser).unwrap_err();
+    let expected = ExpectedExpression(Location::new(0, Span::new(4, 5)));
+    assert_diag(expected, &err);
+    assert!(expr().parse(&mut Parser::new("1 +")).is_err());
+}
+
+/// Asserts that the parser reported a single [ChainedComparison] at the provided operator.
+fn assert_chained(parser: &mut Parser, op: &str, at: Span, lhs: Span) {
+    let diagnostics = parser.lexer().take_diagnostics();
+    assert_eq!(1, diagnostics.len());
+    let expected = ChainedComparison(op.into(), Location::new(0, at), Location::new(0, lhs));
+    assert_diag(expected, &diagnostics[0]);
+}
+
+#[test]
+fn non_associative() {
+    let pratt = expr().infix(Less, 0, Assoc::Neither, binary);
+    let mut parser = Parser::new("a < b + 1");
+    assert_eq!("(< a (+ b 1))", pratt.parse(&mut parser).unwrap().0);
+    assert!(parser.lexer().diagnostics().is_empty());
+
+    let mut parser = Parser::new("a < b < c");
+    assert_eq!("(< (< a b) c)", pratt.parse(&mut parser).unwrap().0);
+    assert_chained(&mut parser, "<", Span::new(6, 7), Span::new(0, 5));
+
+    let mut parser = Parser::new("(a < b) < c");
+    assert_eq!("(< (< a b) c)", pratt.parse(&mut parser).unwrap().0);
+    assert!(parser.lexer().diagnostics().is_empty());
+}
+
+/// `=` < `==`, `<`, `>` < `+`, `-` < `*`, `/`
+const TABLE: &[Precedence<TokKind<'static>>] = &[
+    Precedence::right(&[Equal]),
+    Precedence::neither(&[DoubleEqual, Less, Greater]),
+    Precedence::left(&[Plus, Minus]),
+    Precedence::left(&[Star, Slash]),
+];
+
+fn table_expr<'s>(p: &mut Parser<'s>) -> Result<(String, Location)> {
+    p.binary(TABLE, table_operand, binary)
+}
+
+fn table_operand<'s>(p: &mut Parser<'s>) -> Result<(String, Location)> {
+    let tok = p.take()?;
+    let location = tok.location();
+    match tok.kind() {
+        Int(int) => Ok((int.to_string(), location)),
+        Ident(ident) => Ok((ident.to_string(), location)),
+        LeftParen => {
+            let inner = table_expr(p)?;
+            p.require(RightParen)?;
+            Ok(inner)
+        }
+        _ => Err(ExpectedExpression(location).into()),
+    }
+}
+
+fn parse_table(source: &str) -> (String, Location) {
+    let mut parser = Parser::new(source);
+    let expr = table_expr(&mut parser).unwrap();
+    assert!(parser.soft_peek().unwrap().is_none());
+    assert!(parser.lexer().diagnostics().is_empty());
+    expr
+}
+
+#[test]
+fn precedence_table() {
+    assert_eq!("(+ 1 (* 2 3))", parse_table("1 + 2 * 3").0);
+    assert_eq!("(* (+ 1 2) 3)", parse_table("(1 + 2) * 3").0);
+    assert_eq!("(- (- a b) c)", parse_table("a - b - c").0);
+    assert_eq!("(= a (= b c))", parse_table("a = b = c").0);
+    assert_eq!(
+        "(= x (== (+ a 1) (* b 2)))",
+        parse_table("x = a + 1 == b * 2").0
+    );
+    assert_eq!("a", parse_table("a").0);
+}
+
+#[test]
+fn precedence_table_locations() {
+    assert_eq!(Span::new(0, 9), parse_table("1 + 2 * 3").1.span());
+    assert_eq!(Span::new(2, 13), parse_table("  (1 + 2) * 3").1.span());
+    let mut parser = Parser::new("a + b * c ;");
+    let expr = table_expr(&mut parser).unwrap();
+    assert_eq!(Span::new(0, 9), expr.1.span());
+    assert_eq!(&SemiColon, parser.peek().unwrap().kind_ref());
+}
+
+#[test]
+fn precedence_table_chained() {
+    let mut parser = Parser::new("a < b == c");
+    assert_eq!("(== (< a b) c)", table_expr(&mut parser).unwrap().0);
+    assert_chained(&mut parser, "==", Span::new(6, 8), Span::new(0, 5));
+
+    let mut parser = Parser::new("a < (b == c)");
+    assert_eq!("(< a (== b c))", table_expr(&mut parser).unwrap().0);
+    assert!(parser.lexer().diagnostics().is_empty());
+}
+
+#[test]
+fn precedence_table_errors() {
+    let mut parser = Parser::new("1 + ;");
+    let err = table_expr(&mut parser).unwrap_err();
+    let expected = ExpectedExpression(Location::new(0, Span::new(4, 5)));
+    assert_diag(expected, &err);
+}
+
+#[test]
+fn precedence_table_right_chain_depth() {
+    let source = format!("{}a", "a = ".repeat(100_000));
+    let mut parser = Parser::new(&source);
+    let err = table_expr(&mut parser).unwrap_err();
+    let limit = parser.lexer().depth_limit();
+    let at = Span::new(4 * limit, 4 * limit + 1);
+    let expected = NestingTooDeep(limit, Location::new(0, at));
+    assert_diag(expected, &err);
+    assert_eq!(0, parser.lexer().depth());
+}
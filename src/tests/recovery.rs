@@ -0,0 +1,129 @@
+use crate::{
+    diagnostics::{Builder, Result},
+    lex::Token,
+    parse::{ExpectedOneOf, ExpectedToken, Parse},
+    tests::utils::{Parser, TokKind::*, build},
+    utils::{Located, Location, Span},
+};
+
+#[derive(Debug, PartialEq)]
+enum Stmt<'s> {
+    Let(&'s str, i64),
+    Error(Span),
+}
+
+fn stmt<'s>(p: &mut Parser<'s>) -> Result<Stmt<'s>> {
+    p.require(Let)?;
+    let Ident(name) = p.take()?.kind() else {
+        return Err(ExpectedToken("identifier".into(), p.location(p.cursor())).into());
+    };
+    p.require(Equal)?;
+    let value = p.take()?;
+    let Int(value) = value.kind_ref() else {
+        return Err(ExpectedToken("integer".into(), value.location()).into());
+    };
+    Ok(Stmt::Let(name, *value))
+}
+
+fn program<'s>(p: &mut Parser<'s>) -> Vec<Stmt<'s>> {
+    let mut stmts = vec![];
+    while p.soft_peek().unwrap().is_some() {
+        stmts.push(p.recover(&[SemiColon], stmt, |location| Stmt::Error(location.span())));
+        p.match_take(SemiColon);
+    }
+    stmts
+}
+
+fn labels(p: &mut Parser) -> Vec<Builder> {
+    p.lexer()
+        .take_diagnostics()
+        .into_iter()
+        .map(|diag| build(diag.as_ref()))
+        .collect()
+}
+
+#[test]
+fn synchronize() {
+    let mut parser = Parser::new("a b ; c");
+    assert!(parser.synchronize(&[SemiColon]));
+    assert_eq!(&SemiColon, parser.take().unwrap().kind_ref());
+    assert!(!parser.synchronize(&[SemiColon]));
+    assert!(parser.soft_peek().unwrap().is_none());
+}
+
+#[test]
+fn synchronize_reports_lex_errors() {
+    let mut parser = Parser::new("a $ b ;");
+    assert!(parser.synchronize(&[SemiColon]));
+    assert_eq!(1, parser.lexer().diagnostics().len());
+}
+
+#[test]
+fn recover_statements() {
+    let mut parser = Parser::new("let a = 1; let = 2; let c 3 4; let d = 4;");
+    let stmts = program(&mut parser);
+    assert_eq!(
+        vec![
+            Stmt::Let("a", 1),
+            Stmt::Error(Span::new(11, 18)),
+            Stmt::Error(Span::new(20, 29)),
+            Stmt::Let("d", 4),
+        ],
+        stmts
+    );
+    let expected = ExpectedToken("=".into(), Location::new(0, Span::new(26, 27)));
+    let labels = labels(&mut parser);
+    assert_eq!(2, labels.len());
+    assert_eq!(build(&expected), labels[1]);
+}
+
+#[test]
+fn recover_list() {
+    let mut parser = Parser::new("(1, +, 3 4, 5)");
+    parser.require(LeftParen).unwrap();
+    let items = parser.recover_list(Comma, RightParen, |p| {
+        let tok = p.take()?;
+        match tok.kind_ref() {
+            Int(int) => Ok(*int),
+            _ => Err(ExpectedToken("integer".into(), tok.location()).into()),
+        }
+    });
+    assert_eq!(vec![1, 3, 5], items);
+    assert!(parser.require(RightParen).is_ok());
+    let expected = ExpectedOneOf(
+        vec![",".into(), ")".into()],
+        Some("4".into()),
+        Location::new(0, Span::new(9, 10)),
+    );
+    let labels = labels(&mut parser);
+    assert_eq!(2, labels.len());
+    assert_eq!(build(&expected), labels[1]);
+}
+
+#[test]
+fn recover_list_stops_at_end() {
+    let mut parser = Parser::new("[1, 2");
+    parser.require(LeftBracket).unwrap();
+    let items = parser.recover_list(Comma, RightBracket, |p| p.require(Int(1)));
+    assert_eq!(1, items.len());
+    assert_eq!(1, labels(&mut parser).len());
+    assert!(parser.require(RightBracket).is_err());
+}
+
+#[test]
+fn attempt_discards_reports() {
+    let mut parser = Parser::new("a b");
+    let result = parser.attempt(|p| {
+        p.report(ExpectedToken("c".into(), p.location(0)));
+        p.require(Ident("c"))
+    });
+    assert!(result.is_err());
+    assert!(parser.lexer().diagnostics().is_empty());
+    parser
+        .attempt(|p| {
+            p.report(ExpectedToken("c".into(), p.location(0)));
+            p.require(Ident("a"))
+        })
+        .unwrap();
+    assert_eq!(1, parser.lexer().diagnostics().len());
+}
@@ -0,0 +1,59 @@
+use crate::{
+    lex::{Lex, Token, TokenRegex},
+    tests::utils::{Lexer, TokKind},
+    utils::Span,
+};
+
+#[test]
+fn regex_match() {
+    let date = TokenRegex::new(r"^\d{4}-\d{2}-\d{2}").unwrap();
+    let mut lexer = Lexer::new("2024-01-31 foo");
+    assert_eq!(Some("2024-01-31"), lexer.construct_regex(&date));
+    assert_eq!(10, lexer.char_stream().position());
+    let tok = lexer.lex().unwrap().unwrap();
+    assert_eq!(&TokKind::Ident("foo"), tok.kind_ref());
+    assert_eq!(Span::new(11, 14), tok.location.span());
+}
+
+#[test]
+fn regex_anchored_to_cursor() {
+    let version = TokenRegex::new(r"\d+\.\d+\.\d+").unwrap();
+    let mut lexer = Lexer::new("v 1.2.3");
+    assert_eq!(None, lexer.construct_regex(&version));
+    assert_eq!(0, lexer.char_stream().position());
+    lexer.chomp();
+    lexer.chomp();
+    assert_eq!(Some("1.2.3"), lexer.construct_regex(&version));
+    assert!(lexer.char_stream().at_end());
+}
+
+#[test]
+fn regex_multibyte() {
+    let word = TokenRegex::new(r"^\w+").unwrap();
+    let mut lexer = Lexer::new("ünïcode!");
+    assert_eq!(Some("ünïcode"), lexer.construct_regex(&word));
+    assert_eq!(Some('!'), lexer.chomp());
+}
+
+#[test]
+fn regex_sees_left_context() {
+    let word_start = TokenRegex::new(r"\b\w+").unwrap();
+    let mut lexer = Lexer::new("ab cd");
+    lexer.chomp();
+    // The cursor is in the middle of a word, so there is no boundary here.
+    assert_eq!(None, lexer.construct_regex(&word_start));
+    lexer.chomp();
+    lexer.chomp();
+    assert_eq!(Some("cd"), lexer.construct_regex(&word_start));
+
+    let start = TokenRegex::new(r"^\w+").unwrap();
+    let mut lexer = Lexer::new(" ab");
+    lexer.chomp();
+    assert_eq!(None, lexer.construct_regex(&start));
+}
+
+#[test]
+fn regex_invalid_pattern() {
+    let err = TokenRegex::new("(unclosed").unwrap_err();
+    assert!(!err.to_string().is_empty(), "{err}");
+}
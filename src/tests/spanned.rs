@@ -0,0 +1,96 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    diagnostics::Result,
+    lex::Token,
+    parse::Parse,
+    tests::utils::{
+        Parser,
+        TokKind::{Int, Plus},
+    },
+    utils::{Located, Location, Span, Spanned},
+};
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+    Literal(i64),
+    Add(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
+}
+
+fn literal(p: &mut Parser) -> Result<Spanned<Expr>> {
+    p.spanned(|p| match p.take()?.kind() {
+        Int(int) => Ok(Expr::Literal(int)),
+        _ => Err(p.expected_one_of()),
+    })
+}
+
+/// `expr := literal ('+' expr)?`
+fn expr(p: &mut Parser) -> Result<Spanned<Expr>> {
+    p.spanned(|p| {
+        let lhs = literal(p)?;
+        if p.match_take(Plus).is_none() {
+            return Ok(lhs.into_inner());
+        }
+        Ok(Expr::Add(Box::new(lhs), Box::new(expr(p)?)))
+    })
+}
+
+fn location(start: usize, end: usize) -> Location {
+    Location::new(0, Span::new(start, end))
+}
+
+#[test]
+fn parse_locations() {
+    let mut parser = Parser::new("  1 +  2+3 ");
+    let expr = expr(&mut parser).unwrap();
+    assert_eq!(location(2, 10), expr.location());
+    let Expr::Add(lhs, rhs) = &*expr else {
+        panic!("expected an addition");
+    };
+    assert_eq!(location(2, 3), lhs.location());
+    assert_eq!(location(7, 10), rhs.location());
+}
+
+#[test]
+fn ignores_location_in_eq() {
+    let mut parser = Parser::new("1 + 2");
+    let expected = Spanned::new(
+        Expr::Add(
+            Box::new(Spanned::new(Expr::Literal(1), Location::default())),
+            Box::new(Spanned::new(Expr::Literal(2), Location::default())),
+        ),
+        Location::default(),
+    );
+    assert_eq!(expected, expr(&mut parser).unwrap());
+}
+
+#[test]
+fn empty_sub_parse() {
+    let mut parser = Parser::new("  1");
+    let empty = parser.spanned(|_| Ok(())).unwrap();
+    assert_eq!(location(2, 2), empty.location());
+}
+
+#[test]
+fn helpers() {
+    let mut spanned = Spanned::new(1, location(3, 4));
+    *spanned += 1;
+    assert_eq!(2, *spanned.as_spanned_ref().value);
+    *spanned.as_spanned_mut().value += 1;
+    assert_eq!(location(3, 4), spanned.as_spanned_mut().location);
+    let mapped = spanned.map(|value| value.to_string());
+    assert_eq!("3", mapped.as_str());
+    let text: &str = mapped.as_ref();
+    assert_eq!("3", text);
+    assert_eq!(location(3, 4), mapped.location());
+    assert_eq!("3", mapped.to_string());
+
+    let some = Spanned::new(Some(5), location(0, 1)).transpose().unwrap();
+    assert_eq!(location(0, 1), some.location);
+    assert_eq!(None, Spanned::new(None::<i32>, location(0, 1)).transpose());
+
+    let ok: Spanned<std::result::Result<i32, ()>> = Spanned::new(Ok(5), location(1, 2));
+    assert_eq!(location(1, 2), ok.transpose().unwrap().location);
+    let err: Spanned<std::result::Result<i32, ()>> = Spanned::new(Err(()), location(1, 2));
+    assert_eq!(Err(()), err.transpose());
+}
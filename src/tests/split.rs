@@ -0,0 +1,140 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    diagnostics::Result,
+    lex::Token,
+    parse::Parse,
+    tests::utils::{
+        Parser,
+        TokKind::{Comma, Greater, Ident, Less, ShiftRight},
+    },
+    utils::{Located, Span},
+};
+
+/// `ty := Ident ('<' ty (',' ty)* '>')?`, where a `>>` closes two lists of arguments.
+fn ty(p: &mut Parser) -> Result<std::string::String> {
+    let mut ty = match p.take()?.kind() {
+        Ident(name) => name.to_string(),
+        _ => return Err(p.expected_one_of()),
+    };
+    if p.match_take(Less).is_some() {
+        ty.push('<');
+        ty.push_str(&self::ty(p)?);
+        while p.match_take(Comma).is_some() {
+            ty.push_str(", ");
+            ty.push_str(&self::ty(p)?);
+        }
+        if p.check(ShiftRight) {
+            p.split_next(&[(Greater, 1), (Greater, 1)])?;
+        }
+        p.require(Greater)?;
+        ty.push('>');
+    }
+    Ok(ty)
+}
+
+#[test]
+fn split_nested_generics() {
+    let mut parser = Parser::new("Vec<Vec<T>>");
+    assert_eq!("Vec<Vec<T>>", ty(&mut parser).unwrap());
+    assert!(parser.soft_peek().unwrap().is_none());
+
+    let mut parser = Parser::new("Map<K, Vec<V>> >> x");
+    assert_eq!("Map<K, Vec<V>>", ty(&mut parser).unwrap());
+    // Only the `>>` closing the generics was split.
+    assert_eq!(&ShiftRight, parser.take().unwrap().kind_ref());
+}
+
+#[test]
+fn split_spans() {
+    let mut parser = Parser::new("a >> b");
+    parser.take().unwrap();
+    assert!(parser.split_next(&[(Greater, 1), (Greater, 1)]).unwrap());
+    let first = parser.take().unwrap();
+    let second = parser.take().unwrap();
+    assert_eq!(
+        (&Greater, Span::new(2, 3)),
+        (first.kind_ref(), first.span())
+    );
+    assert_eq!(
+        (&Greater, Span::new(3, 4)),
+        (second.kind_ref(), second.span())
+    );
+    assert_eq!(4, parser.lexer().previous_end());
+    assert_eq!(&Ident("b"), parser.take().unwrap().kind_ref());
+    assert!(!parser.split_next(&[(Greater, 1)]).unwrap());
+}
+
+#[test]
+#[should_panic(expected = "the parts of a split token must cover all of it")]
+fn split_must_cover_token() {
+    let mut parser = Parser::new(">>");
+    let _ = parser.split_next(&[(Greater, 1)]);
+}
+
+#[test]
+fn rewind_restores_split() {
+    let mut parser = Parser::new("a >> b");
+    parser.take().unwrap();
+    let result = parser.attempt(|p| {
+        p.split_next(&[(Greater, 1), (Greater, 1)])?;
+        p.require(Greater)?;
+        p.require(Comma)
+    });
+    assert!(result.is_err());
+    let tok = parser.take().unwrap();
+    assert_eq!((&ShiftRight, Span::new(2, 4)), (tok.kind_ref(), tok.span()));
+
+    // A split within a successful attempt is kept.
+    let mut parser = Parser::new(">>");
+    parser
+        .attempt(|p| p.split_next(&[(Greater, 1), (Greater, 1)]))
+        .unwrap();
+    assert_eq!(&Greater, parser.take().unwrap().kind_ref());
+    assert_eq!(&Greater, parser.take().unwrap().kind_ref());
+}
+
+#[test]
+fn glue() {
+    let mut parser = Parser::new(">> > >");
+    parser.split_next(&[(Greater, 1), (Greater, 1)]).unwrap();
+    assert!(parser.glue_next(&[Greater, Greater], ShiftRight).unwrap());
+    let tok = parser.take().unwrap();
+    assert_eq!((&ShiftRight, Span::new(0, 2)), (tok.kind_ref(), tok.span()));
+    // These aren't joint.
+    assert!(!parser.glue_next(&[Greater, Greater], ShiftRight).unwrap());
+    assert!(!parser.glue_next(&[Greater, Less], ShiftRight).unwrap());
+    assert_eq!(&Greater, parser.take().unwrap().kind_ref());
+}
+
+#[test]
+fn jointness() {
+    let mut parser = Parser::new("a>> b");
+    assert!(parser.is_joint(0).unwrap());
+    assert!(!parser.is_joint(1).unwrap());
+    assert!(!parser.is_joint(2).unwrap());
+    parser.take().unwrap();
+    parser.split_next(&[(Greater, 1), (Greater, 1)]).unwrap();
+    assert!(parser.is_joint(0).unwrap());
+    assert!(!parser.is_joint(1).unwrap());
+}
+
+#[test]
+fn glue_nothing() {
+    let mut parser = Parser::new(">");
+    assert!(!parser.glue_next(&[], ShiftRight).unwrap());
+    let tok = parser.take().unwrap();
+    assert_eq!((&Greater, Span::new(0, 1)), (tok.kind_ref(), tok.span()));
+    assert!(parser.soft_peek().unwrap().is_none());
+}
+
+#[test]
+fn lex_errors_break_jointness() {
+    let mut parser = Parser::new(">$>");
+    assert!(!parser.is_joint(0).unwrap());
+    assert!(!parser.glue_next(&[Greater, Greater], ShiftRight).unwrap());
+    assert_eq!(&Greater, parser.take().unwrap().kind_ref());
+    // The error is still reported once it is reached.
+    assert!(parser.is_joint(0).is_err());
+    assert_eq!(&Greater, parser.take().unwrap().kind_ref());
+}
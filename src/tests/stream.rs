@@ -0,0 +1,137 @@
+use std::io::Read;
+
+use crate::{
+    diagnostics::Result,
+    lex::{ReadStream, StreamLex, Tok, TokenKind, UnexpectedChar},
+    utils::{FileId, Location, Span},
+};
+
+#[derive(Debug, PartialEq, Clone)]
+enum Kind {
+    Ident(String),
+    Float(f64),
+    String(String),
+    Hex(String),
+    Comment(String),
+    Equal,
+}
+
+impl TokenKind for Kind {}
+
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(&format!("{self:?}"))
+    }
+}
+
+struct StreamLexer<R: Read> {
+    char_stream: ReadStream<R>,
+}
+
+impl<R: Read> StreamLex<R, Tok<Kind>, Kind> for StreamLexer<R> {
+    fn char_stream(&mut self) -> &mut ReadStream<R> {
+        &mut self.char_stream
+    }
+
+    fn file_id(&self) -> FileId {
+        3
+    }
+
+    fn lex(&mut self) -> Result<Option<Tok<Kind>>> {
+        while self.char_stream.match_chomp_with(|c| c.is_whitespace()) {}
+        let start_pos = self.char_stream.position();
+        let kind = if let Some(float) = self.construct_float(true, true) {
+            Kind::Float(float)
+        } else if let Some(string) = self.construct_string(&['"', '§'], &['\\']) {
+            Kind::String(string?)
+        } else if let Some(hex) = self.construct_hex("#") {
+            Kind::Hex(hex?)
+        } else if let Some(comment) = self.construct_comment(&["//"]) {
+            Kind::Comment(comment)
+        } else if let Some(ident) = self.construct_ident() {
+            Kind::Ident(ident)
+        } else if self.match_chomp('=') {
+            Kind::Equal
+        } else if self.chomp().is_some() {
+            let location = Location::new(3, start_pos..self.char_stream.position());
+            return Err(UnexpectedChar(location).into());
+        } else {
+            self.check_read()?;
+            return Ok(None);
+        };
+        let location = Location::new(3, start_pos..self.char_stream.position());
+        Ok(Some(Tok::new(kind, location)))
+    }
+}
+
+/// A reader which hands out a single byte at a time, splitting every multi-byte char.
+struct Trickle<'a>(&'a [u8]);
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.split_first() {
+            Some((first, rest)) if !buf.is_empty() => {
+                buf[0] = *first;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+fn lex_all<R: Read>(reader: R, chunk_size: usize) -> Result<Vec<Tok<Kind>>> {
+    let mut lexer = StreamLexer {
+        char_stream: ReadStream::with_chunk_size(reader, chunk_size),
+    };
+    let mut toks = vec![];
+    while let Some(tok) = lexer.lex()? {
+        toks.push(tok);
+    }
+    Ok(toks)
+}
+
+#[test]
+fn stream_tokens() {
+    let source = "naïve = \"héllo\" // comment\n1.5e+02";
+    let toks = lex_all(Trickle(source.as_bytes()), 4).unwrap();
+    let kinds: Vec<Kind> = toks.iter().map(|tok| tok.kind.clone()).collect();
+    pretty_assertions::assert_eq!(
+        vec![
+            Kind::Ident("naïve".into()),
+            Kind::Equal,
+            Kind::String("héllo".into()),
+            Kind::Comment("// comment\n".into()),
+            Kind::Float(150.0),
+        ],
+        kinds
+    );
+    assert_eq!(Span::new(0, 6), toks[0].location.span());
+    assert_eq!(Location::new(3, 9..17), toks[2].location);
+    assert_eq!(Span::new(29, 36), toks[4].location.span());
+}
+
+#[test]
+fn stream_rolls_window() {
+    let source = "abc ".repeat(10_000);
+    let toks = lex_all(source.as_bytes(), 16).unwrap();
+    assert_eq!(10_000, toks.len());
+    assert_eq!(Span::new(39_996, 39_999), toks[9_999].location.span());
+}
+
+#[test]
+fn stream_invalid_utf8() {
+    let err = lex_all(&b"abc \xff"[..], 16).unwrap_err();
+    assert!(format!("{err:?}").contains("failed to read the source"));
+}
+
+#[test]
+fn stream_multibyte_delimiters() {
+    let toks = lex_all(Trickle("§héllo§ #ff".as_bytes()), 4).unwrap();
+    let kinds: Vec<Kind> = toks.iter().map(|tok| tok.kind.clone()).collect();
+    pretty_assertions::assert_eq!(
+        vec![Kind::String("héllo".into()), Kind::Hex("ff".into())],
+        kinds
+    );
+    assert_eq!(Span::new(0, 10), toks[0].location.span());
+    assert_eq!(Span::new(11, 14), toks[1].location.span());
+}
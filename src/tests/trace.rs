@@ -0,0 +1,124 @@
+use pretty_assertions::assert_eq;
+
+use crate::{
+    diagnostics::Result,
+    parse::{Parse, TraceEvent, TrailingSeparator},
+    tests::utils::{
+        Parser,
+        TokKind::{Comma, Equal, Ident, Let, SemiColon},
+    },
+    utils::{Located, Location, Span},
+};
+
+/// `stmt := 'let' ident '=' ident ';' | ident ';'`
+fn stmt(p: &mut Parser) -> Result<()> {
+    p.trace("stmt", |p| {
+        let binding = p.attempt(|p| {
+            p.trace("let", |p| {
+                p.require(Let)?;
+                p.require(Ident("a"))?;
+                p.require(Equal)?;
+                p.require(Ident("b"))?;
+                p.require(SemiColon).map(|_| ())
+            })
+        });
+        binding.or_else(|_| {
+            p.trace("expr", |p| {
+                p.require(Ident("a"))?;
+                if let Some(tok) = p.match_take(Comma) {
+                    p.report(TrailingSeparator(tok.location()));
+                }
+                p.require(SemiColon).map(|_| ())
+            })
+        })
+    })
+}
+
+#[test]
+fn disabled_by_default() {
+    let mut parser = Parser::new("a;");
+    stmt(&mut parser).unwrap();
+    assert!(parser.lexer().trace().is_none());
+}
+
+#[test]
+fn events() {
+    let mut parser = Parser::new("let a = a");
+    parser.lexer().enable_trace();
+    assert!(stmt(&mut parser).is_err());
+    let trace = parser.lexer().take_trace().unwrap();
+    let location = |start, end| Location::new(0, Span::new(start, end));
+    let events = trace.events();
+    assert_eq!(
+        events[0],
+        TraceEvent::Enter {
+            rule: "stmt",
+            location: location(0, 0)
+        }
+    );
+    assert!(events.contains(&TraceEvent::Backtrack {
+        location: location(0, 9)
+    }));
+    assert!(matches!(
+        events.last(),
+        Some(TraceEvent::Exit {
+            rule: "stmt",
+            error: Some(_),
+            ..
+        })
+    ));
+    assert!(parser.lexer().trace().is_none());
+}
+
+#[test]
+fn render() {
+    let mut parser = Parser::new("let a = b; a,;");
+    parser.lexer().enable_trace();
+    stmt(&mut parser).unwrap();
+    stmt(&mut parser).unwrap();
+    let trace = parser.lexer().trace().unwrap();
+    assert_eq!(
+        trace.to_string(),
+        "\
+> stmt @ 0:0..0
+  > let @ 0:0..0
+    token let @ 0:0..3
+    token a @ 0:4..5
+    token = @ 0:6..7
+    token b @ 0:8..9
+    token ; @ 0:9..10
+  < let @ 0:0..10
+< stmt @ 0:0..10
+> stmt @ 0:11..11
+  > let @ 0:11..11
+    token a @ 0:11..12
+  < let failed @ 0:11..12: expected this to be 'let'
+  backtrack @ 0:11..12
+  > expr @ 0:11..11
+    token a @ 0:11..12
+    token , @ 0:12..13
+    warning @ 0:12..13: trailing separator is not allowed here
+    token ; @ 0:13..14
+  < expr @ 0:11..14
+< stmt @ 0:11..14
+"
+    );
+}
+
+#[test]
+fn json() {
+    let mut parser = Parser::new("a,;");
+    parser.lexer().enable_trace();
+    stmt(&mut parser).unwrap();
+    let json = parser.lexer().trace().unwrap().to_json();
+    assert!(json.starts_with(
+        r#"[{"event":"enter","rule":"stmt","location":{"file":0,"start":0,"end":0}},"#
+    ));
+    assert!(
+        json.contains(r#"{"event":"token","kind":"a","location":{"file":0,"start":0,"end":1}}"#)
+    );
+    assert!(json.contains(r#"{"event":"diagnostic","severity":"warning","message":"#));
+    assert!(json.ends_with(
+        r#"{"event":"exit","rule":"stmt","location":{"file":0,"start":0,"end":3},"error":null}]"#
+    ));
+}
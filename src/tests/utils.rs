@@ -1,7 +1,8 @@
 use crate::{
-    diagnostics::Result,
+    diagnostics::{Builder, Diag, DiagBox, Result},
     lex::{CharStream, Lex, Tok, TokenKind, UnexpectedChar},
-    utils::{Location, Span},
+    parse::{Parse, TokenBuffer},
+    utils::{FileId, Location, Span},
 };
 
 /// Test macro for token lexing.
@@ -66,6 +67,16 @@ macro_rules! test_lex_err {
     };
 }
 
+/// Builds a diagnostic so that it can be compared, since DiagBox is not PartialEq.
+pub fn build(diag: &dyn Diag) -> Builder {
+    diag.build(Builder::new(diag.severity()))
+}
+
+/// Asserts that `found` builds the same diagnostic as the expected one.
+pub fn assert_diag(expected: impl Diag, found: &DiagBox) {
+    pretty_assertions::assert_eq!(build(&expected), build(found.as_ref()));
+}
+
 pub struct Lexer<'s> {
     source: &'s str,
     char_stream: CharStream<'s>,
@@ -122,6 +133,29 @@ impl<'s> Lex<'s, Tok<TokKind<'s>>, TokKind<'s>> for Lexer<'s> {
                     }
                 }
                 ';' => TokKind::SemiColon,
+                ',' => TokKind::Comma,
+                ':' => TokKind::Colon,
+                '(' => TokKind::LeftParen,
+                ')' => TokKind::RightParen,
+                '[' => TokKind::LeftBracket,
+                ']' => TokKind::RightBracket,
+                '{' => TokKind::LeftBrace,
+                '}' => TokKind::RightBrace,
+                '+' => TokKind::Plus,
+                '-' => TokKind::Minus,
+                '*' => TokKind::Star,
+                '/' => TokKind::Slash,
+                '<' => TokKind::Less,
+                '>' => {
+                    if self.match_chomp('>') {
+                        TokKind::ShiftRight
+                    } else {
+                        TokKind::Greater
+                    }
+                }
+                '?' => TokKind::Question,
+                '!' => TokKind::Bang,
+                '|' => TokKind::Bar,
                 _ => {
                     return Err(UnexpectedChar(Location::new(
                         self.file_id,
@@ -145,6 +179,53 @@ impl<'s> Lex<'s, Tok<TokKind<'s>>, TokKind<'s>> for Lexer<'s> {
     }
 }
 
+impl<'s> Iterator for Lexer<'s> {
+    type Item = Result<Tok<TokKind<'s>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lex().transpose()
+    }
+}
+
+pub struct Parser<'s, N = ()> {
+    lexer: TokenBuffer<Lexer<'s>, Tok<TokKind<'s>>, TokKind<'s>, N>,
+    cursor: usize,
+}
+
+impl<'s> Parser<'s> {
+    pub fn new(source: &'s str) -> Self {
+        Self::for_tree(source)
+    }
+}
+
+impl<'s, N> Parser<'s, N> {
+    /// Creates a Parser which records a syntax tree with nodes of kind `N`.
+    pub fn for_tree(source: &'s str) -> Self {
+        Self {
+            lexer: TokenBuffer::new(Lexer::new(source)),
+            cursor: 0,
+        }
+    }
+}
+
+impl<'s, N> Parse<'s, Lexer<'s>, Tok<TokKind<'s>>, TokKind<'s>, N> for Parser<'s, N> {
+    fn lexer(&mut self) -> &mut TokenBuffer<Lexer<'s>, Tok<TokKind<'s>>, TokKind<'s>, N> {
+        &mut self.lexer
+    }
+
+    fn file_id(&self) -> FileId {
+        0
+    }
+
+    fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    fn set_cursor(&mut self, target: usize) {
+        self.cursor = target;
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub enum TokKind<'s> {
     Let,
@@ -152,6 +233,24 @@ pub enum TokKind<'s> {
     Equal,
     DoubleEqual,
     SemiColon,
+    Comma,
+    Colon,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Less,
+    Greater,
+    ShiftRight,
+    Question,
+    Bang,
+    Bar,
     Ident(&'s str),
     Int(i64),
     Float(f64),
@@ -160,7 +259,14 @@ pub enum TokKind<'s> {
     Comment(&'s str),
 }
 
-impl TokenKind for TokKind<'_> {}
+impl TokenKind for TokKind<'_> {
+    fn ident(&self) -> Option<&str> {
+        match self {
+            TokKind::Ident(ident) => Some(ident),
+            _ => None,
+        }
+    }
+}
 
 impl std::fmt::Display for TokKind<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -170,6 +276,24 @@ impl std::fmt::Display for TokKind<'_> {
             TokKind::Equal => f.pad("="),
             TokKind::DoubleEqual => f.pad("=="),
             TokKind::SemiColon => f.pad(";"),
+            TokKind::Comma => f.pad(","),
+            TokKind::Colon => f.pad(":"),
+            TokKind::LeftParen => f.pad("("),
+            TokKind::RightParen => f.pad(")"),
+            TokKind::LeftBracket => f.pad("["),
+            TokKind::RightBracket => f.pad("]"),
+            TokKind::LeftBrace => f.pad("{"),
+            TokKind::RightBrace => f.pad("}"),
+            TokKind::Plus => f.pad("+"),
+            TokKind::Minus => f.pad("-"),
+            TokKind::Star => f.pad("*"),
+            TokKind::Slash => f.pad("/"),
+            TokKind::Less => f.pad("<"),
+            TokKind::Greater => f.pad(">"),
+            TokKind::ShiftRight => f.pad(">>"),
+            TokKind::Question => f.pad("?"),
+            TokKind::Bang => f.pad("!"),
+            TokKind::Bar => f.pad("|"),
             TokKind::Ident(iden) => f.pad(iden),
             TokKind::Int(r) => f.pad(&r.to_string()),
             TokKind::Float(r) => f.pad(&r.to_string()),
@@ -0,0 +1,285 @@
+use pretty_assertions::assert_eq;
+
+use crate::utils::{Location, Span, Spanned};
+
+#[crate::visitors]
+mod ast {
+    use crate::{
+        Visit,
+        utils::{Location, Spanned},
+    };
+
+    #[derive(Debug, PartialEq, Visit)]
+    pub struct Program {
+        pub items: Vec<Spanned<Item>>,
+        pub end: Location,
+    }
+
+    #[derive(Debug, PartialEq, Visit)]
+    pub enum Item {
+        Let {
+            name: std::string::String,
+            value: Spanned<Expr>,
+            #[visit(skip)]
+            annotation: Option<Spanned<Expr>>,
+        },
+        Expr(Spanned<Expr>),
+        Empty,
+    }
+
+    #[derive(Debug, PartialEq, Visit)]
+    pub enum Expr {
+        Int(i64),
+        Neg(Box<Spanned<Expr>>),
+        Binary(Box<Spanned<Expr>>, BinaryOp, Box<Spanned<Expr>>),
+        Call(
+            std::string::String,
+            Vec<Spanned<Expr>>,
+            Option<Box<Spanned<Expr>>>,
+        ),
+    }
+
+    #[derive(Debug, PartialEq, Clone, Copy, Visit)]
+    pub enum BinaryOp {
+        Add,
+        Mul,
+    }
+}
+
+use ast::{BinaryOp, Expr, Fold, Item, Program, Visit, VisitMut};
+
+fn loc(start: usize, end: usize) -> Location {
+    Location::new(0, Span::new(start, end))
+}
+
+fn spanned<T>(value: T, start: usize, end: usize) -> Spanned<T> {
+    Spanned::new(value, loc(start, end))
+}
+
+fn int(value: i64, at: usize) -> Box<Spanned<Expr>> {
+    Box::new(spanned(Expr::Int(value), at, at + 1))
+}
+
+/// `let x = -1 + 2; f(3, 4 * 5);`, with an annotation on the `let` that is skipped.
+fn program() -> Program {
+    Program {
+        items: vec![
+            spanned(
+                Item::Let {
+                    name: "x".into(),
+                    value: spanned(
+                        Expr::Binary(
+                            Box::new(spanned(Expr::Neg(int(1, 9)), 8, 10)),
+                            BinaryOp::Add,
+                            int(2, 13),
+                        ),
+                        8,
+                        14,
+                    ),
+                    annotation: Some(spanned(Expr::Int(100), 0, 0)),
+                },
+                0,
+                15,
+            ),
+            spanned(Item::Empty, 15, 16),
+            spanned(
+                Item::Expr(spanned(
+                    Expr::Call(
+                        "f".into(),
+                        vec![
+                            *int(3, 19),
+                            spanned(Expr::Binary(int(4, 22), BinaryOp::Mul, int(5, 26)), 22, 27),
+                        ],
+                        None,
+                    ),
+                    17,
+                    28,
+                )),
+                17,
+                29,
+            ),
+        ],
+        end: loc(29, 29),
+    }
+}
+
+#[derive(Default)]
+struct Collect<'ast> {
+    ints: Vec<i64>,
+    ops: Vec<&'ast BinaryOp>,
+    locations: usize,
+}
+
+impl<'ast> Visit<'ast> for Collect<'ast> {
+    fn visit_expr(&mut self, node: &'ast Expr) {
+        if let Expr::Int(value) = node {
+            self.ints.push(*value);
+        }
+        ast::walk_expr(self, node);
+    }
+
+    fn visit_binary_op(&mut self, node: &'ast BinaryOp) {
+        self.ops.push(node);
+    }
+
+    fn visit_location(&mut self, _: &'ast Location) {
+        self.locations += 1;
+    }
+}
+
+#[test]
+fn visit() {
+    let program = program();
+    let mut collect = Collect::default();
+    collect.visit_program(&program);
+    assert_eq!(vec![1, 2, 3, 4, 5], collect.ints);
+    assert_eq!(vec![&BinaryOp::Add, &BinaryOp::Mul], collect.ops);
+    // Three items, nine expressions, and the end of the program.
+    assert_eq!(13, collect.locations);
+}
+
+#[test]
+fn overriding_stops_the_walk() {
+    struct Shallow(usize);
+    impl Visit<'_> for Shallow {
+        fn visit_item(&mut self, _: &Item) {
+            self.0 += 1;
+        }
+
+        fn visit_expr(&mut self, _: &Expr) {
+            unreachable!("items are not walked into");
+        }
+    }
+
+    let mut shallow = Shallow(0);
+    shallow.visit_program(&program());
+    assert_eq!(3, shallow.0);
+}
+
+#[test]
+fn visit_mut() {
+    struct Double;
+    impl VisitMut for Double {
+        fn visit_expr_mut(&mut self, node: &mut Expr) {
+            if let Expr::Int(value) = node {
+                *value *= 2;
+            }
+            ast::walk_expr_mut(self, node);
+        }
+
+        fn visit_location_mut(&mut self, location: &mut Location) {
+            *location = Location::new(1, location.span());
+        }
+    }
+
+    let mut program = program();
+    Double.visit_program_mut(&mut program);
+    let mut collect = Collect::default();
+    collect.visit_program(&program);
+    assert_eq!(vec![2, 4, 6, 8, 10], collect.ints);
+    assert_eq!(1, program.end.file_id());
+    assert_eq!(1, program.items[2].location.file_id());
+    // The skipped annotation is left alone.
+    let Item::Let { annotation, .. } = &*program.items[0] else {
+        unreachable!()
+    };
+    assert_eq!(
+        Some(0),
+        annotation.as_ref().map(|expr| expr.location.file_id())
+    );
+}
+
+#[test]
+fn fold() {
+    /// Evaluates constant expressions.
+    struct Evaluate;
+    impl Fold for Evaluate {
+        fn fold_expr(&mut self, node: Expr) -> Expr {
+            match ast::walk_fold_expr(self, node) {
+                Expr::Neg(expr) => match expr.value {
+                    Expr::Int(value) => Expr::Int(-value),
+                    value => Expr::Neg(Box::new(Spanned::new(value, expr.location))),
+                },
+                Expr::Binary(lhs, op, rhs) => match (&lhs.value, &rhs.value) {
+                    (Expr::Int(lhs), Expr::Int(rhs)) => Expr::Int(match op {
+                        BinaryOp::Add => lhs + rhs,
+                        BinaryOp::Mul => lhs * rhs,
+                    }),
+                    _ => Expr::Binary(lhs, op, rhs),
+                },
+                node => node,
+            }
+        }
+    }
+
+    let program = Evaluate.fold_program(program());
+    let Item::Let { value, .. } = &*program.items[0] else {
+        unreachable!()
+    };
+    assert_eq!(Expr::Int(1), value.value);
+    assert_eq!(loc(8, 14), value.location);
+    let Item::Expr(call) = &*program.items[2] else {
+        unreachable!()
+    };
+    assert_eq!(
+        Expr::Call(
+            "f".into(),
+            vec![*int(3, 19), spanned(Expr::Int(20), 22, 27)],
+            None
+        ),
+        call.value
+    );
+    assert_eq!(loc(17, 29), program.items[2].location);
+}
+
+#[crate::visitors]
+mod borrowed {
+    use crate::{Visit, utils::Spanned};
+
+    #[derive(Debug, PartialEq, Visit)]
+    pub enum Expr<'s> {
+        Ident(&'s str),
+        Call(&'s str, Vec<Spanned<Expr<'s>>>),
+    }
+}
+
+#[test]
+fn borrowed_nodes() {
+    use borrowed::{Expr, Fold, Visit};
+
+    struct Idents<'ast, 's>(Vec<&'ast &'s str>);
+    impl<'ast, 's> Visit<'ast, 's> for Idents<'ast, 's> {
+        fn visit_expr(&mut self, node: &'ast Expr<'s>) {
+            if let Expr::Ident(name) = node {
+                self.0.push(name);
+            }
+            borrowed::walk_expr(self, node);
+        }
+    }
+
+    struct Rename;
+    impl<'s> Fold<'s> for Rename {
+        fn fold_expr(&mut self, node: Expr<'s>) -> Expr<'s> {
+            match borrowed::walk_fold_expr(self, node) {
+                Expr::Ident("a") => Expr::Ident("b"),
+                node => node,
+            }
+        }
+    }
+
+    let source = std::string::String::from("f(a, c)");
+    let expr = Expr::Call(
+        &source[0..1],
+        vec![
+            spanned(Expr::Ident(&source[2..3]), 2, 3),
+            spanned(Expr::Ident(&source[5..6]), 5, 6),
+        ],
+    );
+    let mut idents = Idents(vec![]);
+    idents.visit_expr(&expr);
+    assert_eq!(vec![&"a", &"c"], idents.0);
+    let Expr::Call(_, args) = Rename.fold_expr(expr) else {
+        unreachable!()
+    };
+    assert_eq!(Expr::Ident("b"), args[0].value);
+}
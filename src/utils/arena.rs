@@ -0,0 +1,248 @@
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+use super::{Location, Spanned};
+
+/// A handle to a node stored within an [Arena]. Ids are cheap to copy, compare and hash, so
+/// nodes can refer to each other (and passes can refer to nodes) without borrowing the Arena.
+///
+/// A NodeId is only meaningful to the Arena that created it.
+pub struct NodeId<T> {
+    index: u32,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> NodeId<T> {
+    fn new(index: usize) -> Self {
+        Self {
+            index: u32::try_from(index).expect("an arena cannot hold more than u32::MAX nodes"),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the index of this NodeId within its [Arena]. Nodes are indexed in the order they
+    /// were allocated.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+// These are implemented by hand so that they don't require anything of `T`.
+impl<T> Clone for NodeId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for NodeId<T> {}
+
+impl<T> PartialEq for NodeId<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for NodeId<T> {}
+
+impl<T> PartialOrd for NodeId<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for NodeId<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+impl<T> Hash for NodeId<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<T> Debug for NodeId<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("NodeId").field(&self.index).finish()
+    }
+}
+
+/// A dense store of nodes of a single type, such as the expressions of an AST, each kept with the
+/// [Location] it was parsed from and an optional link to its parent.
+///
+/// Nodes are allocated into one contiguous buffer and are dropped all at once along with the
+/// Arena, rather than one allocation at a time as with a tree of [Box]es. Nodes refer to their
+/// children by [NodeId]:
+///
+/// ```
+/// use chompy::utils::{Arena, Location, NodeId};
+///
+/// enum Expr {
+///     Int(i64),
+///     Add(NodeId<Expr>, NodeId<Expr>),
+/// }
+///
+/// let mut arena = Arena::new();
+/// let lhs = arena.alloc(Expr::Int(1), Location::default());
+/// let rhs = arena.alloc(Expr::Int(2), Location::default());
+/// let add = arena.alloc(Expr::Add(lhs, rhs), Location::default());
+/// arena.set_parent(lhs, add);
+/// arena.set_parent(rhs, add);
+/// assert_eq!(Some(add), arena.parent(lhs));
+/// ```
+///
+/// Since children are usually parsed before their parent, parent links are set afterwards with
+/// [Arena::set_parent]. Nodes cannot be removed from an Arena.
+#[derive(Debug, Clone)]
+pub struct Arena<T> {
+    nodes: Vec<Spanned<T>>,
+    parents: Vec<Option<NodeId<T>>>,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self {
+            nodes: vec![],
+            parents: vec![],
+        }
+    }
+}
+
+impl<T> Arena<T> {
+    /// Creates a new, empty Arena.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new, empty Arena with room for the provided amount of nodes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            nodes: Vec::with_capacity(capacity),
+            parents: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Stores a node parsed from the provided [Location], returning its [NodeId].
+    pub fn alloc(&mut self, value: T, location: Location) -> NodeId<T> {
+        self.alloc_spanned(Spanned::new(value, location))
+    }
+
+    /// Stores a node along with its [Location], such as one returned by
+    /// [crate::parse::Parse::spanned], returning its [NodeId].
+    pub fn alloc_spanned(&mut self, node: Spanned<T>) -> NodeId<T> {
+        let id = NodeId::new(self.nodes.len());
+        self.nodes.push(node);
+        self.parents.push(None);
+        id
+    }
+
+    /// Returns the node with the provided [NodeId] along with its [Location], or None if the id is
+    /// past the end of this Arena. NodeIds are only indices, so an id from another Arena may
+    /// return an unrelated node.
+    pub fn get(&self, id: NodeId<T>) -> Option<&Spanned<T>> {
+        self.nodes.get(id.index())
+    }
+
+    /// Mutably returns the node with the provided [NodeId] along with its [Location], or None if
+    /// the id is past the end of this Arena. See [Arena::get].
+    pub fn get_mut(&mut self, id: NodeId<T>) -> Option<&mut Spanned<T>> {
+        self.nodes.get_mut(id.index())
+    }
+
+    /// Returns the [Location] of the node with the provided [NodeId].
+    ///
+    /// ### Panics
+    /// Panics if the NodeId does not belong to this Arena.
+    pub fn location(&self, id: NodeId<T>) -> Location {
+        self.nodes[id.index()].location
+    }
+
+    /// Returns the parent of the node with the provided [NodeId], if one has been set.
+    ///
+    /// ### Panics
+    /// Panics if the NodeId does not belong to this Arena.
+    pub fn parent(&self, id: NodeId<T>) -> Option<NodeId<T>> {
+        self.parents[id.index()]
+    }
+
+    /// Sets the parent of the `child` node, replacing any parent it had before.
+    ///
+    /// ### Panics
+    /// Panics if either NodeId does not belong to this Arena, or if `parent` is `child` itself or
+    /// one of its descendants, as that would create a cycle.
+    pub fn set_parent(&mut self, child: NodeId<T>, parent: NodeId<T>) {
+        assert!(
+            parent.index() < self.nodes.len(),
+            "{parent:?} is not in this arena"
+        );
+        assert!(
+            parent != child && self.ancestors(parent).all(|id| id != child),
+            "{parent:?} cannot be the parent of {child:?}, as it would create a cycle"
+        );
+        self.parents[child.index()] = Some(parent);
+    }
+
+    /// Returns an iterator over the parent of the provided node, its parent's parent, and so on.
+    ///
+    /// ### Panics
+    /// Panics if the NodeId does not belong to this Arena.
+    pub fn ancestors(&self, id: NodeId<T>) -> impl Iterator<Item = NodeId<T>> + '_ {
+        std::iter::successors(self.parent(id), |id| self.parent(*id))
+    }
+
+    /// Returns an iterator over every node without a parent, in the order they were allocated.
+    pub fn roots(&self) -> impl Iterator<Item = NodeId<T>> + '_ {
+        self.ids().filter(|id| self.parent(*id).is_none())
+    }
+
+    /// Returns an iterator over the [NodeId] of every node, in the order they were allocated.
+    pub fn ids(&self) -> impl Iterator<Item = NodeId<T>> + use<T> {
+        (0..self.nodes.len()).map(NodeId::new)
+    }
+
+    /// Returns an iterator over every node and its [NodeId], in the order they were allocated.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId<T>, &Spanned<T>)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (NodeId::new(index), node))
+    }
+
+    /// Returns an iterator mutably over every node and its [NodeId], in the order they were
+    /// allocated.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (NodeId<T>, &mut Spanned<T>)> {
+        self.nodes
+            .iter_mut()
+            .enumerate()
+            .map(|(index, node)| (NodeId::new(index), node))
+    }
+
+    /// Returns the amount of nodes in this Arena.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns if this Arena has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl<T> Index<NodeId<T>> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, id: NodeId<T>) -> &Self::Output {
+        &self.nodes[id.index()].value
+    }
+}
+
+impl<T> IndexMut<NodeId<T>> for Arena<T> {
+    fn index_mut(&mut self, id: NodeId<T>) -> &mut Self::Output {
+        &mut self.nodes[id.index()].value
+    }
+}
@@ -0,0 +1,59 @@
+use super::Span;
+
+/// A change to a source's text: the text within `span` is replaced with `text`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TextEdit {
+    /// The span of the original text being replaced.
+    pub span: Span,
+    /// The text replacing it.
+    pub text: String,
+}
+
+impl TextEdit {
+    /// Creates a new TextEdit.
+    pub fn new(span: Span, text: impl Into<String>) -> Self {
+        Self {
+            span,
+            text: text.into(),
+        }
+    }
+
+    /// Creates a TextEdit inserting `text` at the provided offset.
+    pub fn insert(offset: usize, text: impl Into<String>) -> Self {
+        Self::new(Span::new(offset, offset), text)
+    }
+
+    /// Creates a TextEdit removing the text within `span`.
+    pub fn delete(span: Span) -> Self {
+        Self::new(span, "")
+    }
+
+    /// Returns the source with this edit applied.
+    ///
+    /// ### Panics
+    /// Panics if the span is out of bounds or does not lie on char boundaries.
+    pub fn apply(&self, source: &str) -> String {
+        let mut edited = String::with_capacity(source.len() + self.text.len());
+        edited.push_str(&source[..self.span.start()]);
+        edited.push_str(&self.text);
+        edited.push_str(&source[self.span.end()..]);
+        edited
+    }
+
+    /// Returns the span the replacement text occupies once this edit is applied.
+    pub fn new_span(&self) -> Span {
+        Span::new(self.span.start(), self.span.start() + self.text.len())
+    }
+
+    /// Returns where an offset of the original text is after this edit is applied. Offsets within
+    /// the replaced text are moved to the end of the replacement.
+    pub fn map_offset(&self, offset: usize) -> usize {
+        if offset < self.span.start() {
+            offset
+        } else if offset < self.span.end() {
+            self.new_span().end()
+        } else {
+            offset - self.span.end() + self.new_span().end()
+        }
+    }
+}
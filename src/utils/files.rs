@@ -1,6 +1,8 @@
 use codespan_reporting::files::{Error, Files, SimpleFile};
 use std::ops::Range;
 
+use super::TextEdit;
+
 /// Alias around `usize`, which codespan uses as an id for files.
 pub type FileId = usize;
 
@@ -29,6 +31,17 @@ impl Library {
     pub fn get(&self, file_id: usize) -> Result<&SimpleFile<String, Box<str>>, Error> {
         self.0.get(file_id).ok_or(Error::FileMissing)
     }
+
+    /// Applies the provided [TextEdit] to a file, returning its new source.
+    ///
+    /// ### Errors
+    /// Returns an error if the file is not found.
+    pub fn edit(&mut self, file_id: usize, edit: &TextEdit) -> Result<&str, Error> {
+        let file = self.0.get_mut(file_id).ok_or(Error::FileMissing)?;
+        let source = edit.apply(file.source());
+        *file = SimpleFile::new(file.name().clone(), source.into());
+        Ok(file.source())
+    }
 }
 impl<'a> Files<'a> for Library {
     type FileId = FileId;
@@ -0,0 +1,93 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+/// A handle to a string stored within an [Interner]. Symbols are cheap to copy, compare and hash,
+/// and do not borrow from the source they were created from.
+///
+/// A Symbol is only meaningful to the Interner that created it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Hash)]
+pub struct Symbol(u32);
+impl Symbol {
+    /// Returns the index of this Symbol within its [Interner].
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A thread-safe store of strings that hands out a [Symbol] for each unique string, allowing the
+/// string to later be resolved back from the Symbol.
+///
+/// Strings are never removed from an Interner. Each is stored once and shared between the
+/// Interner and everything that resolved it.
+#[derive(Debug, Default)]
+pub struct Interner(RwLock<Strings>);
+
+#[derive(Debug, Default)]
+struct Strings {
+    map: HashMap<Arc<str>, Symbol>,
+    strings: Vec<Arc<str>>,
+}
+
+impl Interner {
+    /// Creates a new, empty Interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns an Interner shared by the entire program.
+    pub fn global() -> &'static Interner {
+        static GLOBAL: OnceLock<Interner> = OnceLock::new();
+        GLOBAL.get_or_init(Interner::new)
+    }
+
+    /// Returns the [Symbol] for the provided string, storing it if it has not been seen before.
+    pub fn intern(&self, string: &str) -> Symbol {
+        if let Some(symbol) = self.get(string) {
+            return symbol;
+        }
+        let mut strings = self.0.write().unwrap_or_else(|err| err.into_inner());
+        if let Some(symbol) = strings.map.get(string) {
+            return *symbol;
+        }
+        let index = u32::try_from(strings.strings.len())
+            .expect("an interner cannot hold more than u32::MAX strings");
+        let symbol = Symbol(index);
+        let string: Arc<str> = string.into();
+        strings.strings.push(string.clone());
+        strings.map.insert(string, symbol);
+        symbol
+    }
+
+    /// Returns the [Symbol] for the provided string if it has already been interned.
+    pub fn get(&self, string: &str) -> Option<Symbol> {
+        let strings = self.0.read().unwrap_or_else(|err| err.into_inner());
+        strings.map.get(string).copied()
+    }
+
+    /// Returns the string the provided [Symbol] refers to.
+    ///
+    /// A Symbol created by a different Interner may resolve to an unrelated string.
+    ///
+    /// ### Panics
+    /// Panics if the Symbol's index is beyond the strings stored in this Interner.
+    pub fn resolve(&self, symbol: Symbol) -> Arc<str> {
+        let strings = self.0.read().unwrap_or_else(|err| err.into_inner());
+        strings.strings[symbol.index()].clone()
+    }
+
+    /// Returns the amount of unique strings that have been interned.
+    pub fn len(&self) -> usize {
+        self.0
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .strings
+            .len()
+    }
+
+    /// Returns if no strings have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
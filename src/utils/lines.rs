@@ -0,0 +1,130 @@
+use super::{Located, Span};
+
+/// A zero-based line and column pair. The unit the column is measured in depends on how it was
+/// created (see [ColumnUnit]).
+///
+/// The [std::fmt::Display] implementation presents the pair one-based (`line:column`), as is
+/// expected by humans reading logs.
+#[derive(Debug, PartialEq, Eq, Default, Copy, Clone, Hash, PartialOrd, Ord)]
+pub struct LineColumn {
+    /// The zero-based line.
+    pub line: usize,
+    /// The zero-based column.
+    pub column: usize,
+}
+impl LineColumn {
+    /// Creates a new LineColumn.
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+impl std::fmt::Display for LineColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(&format!("{}:{}", self.line + 1, self.column + 1))
+    }
+}
+
+/// The unit a column is measured in.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum ColumnUnit {
+    /// Columns count chars (unicode scalar values).
+    Char,
+    /// Columns count bytes of UTF-8.
+    Byte,
+    /// Columns count UTF-16 code units, as used by the Language Server Protocol.
+    Utf16,
+}
+
+/// A char on a line that takes more than one byte to encode.
+#[derive(Debug, Copy, Clone)]
+struct WideChar {
+    offset: usize,
+    len_utf8: usize,
+    len_utf16: usize,
+}
+
+/// Maps byte offsets into a source to [LineColumn]s. Unlike [super::Library], this does not hold
+/// onto the source itself and does not care about [super::FileId]s, so it can be used with any
+/// [super::Location] that points into the source it was built from.
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    wide_chars: Vec<Vec<WideChar>>,
+    len: usize,
+}
+impl LineIndex {
+    /// Creates a new LineIndex over the provided source.
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide_chars = vec![vec![]];
+        for (offset, chr) in source.char_indices() {
+            if chr == '\n' {
+                line_starts.push(offset + 1);
+                wide_chars.push(vec![]);
+            } else if !chr.is_ascii() {
+                let line_start = line_starts[line_starts.len() - 1];
+                wide_chars.last_mut().unwrap().push(WideChar {
+                    offset: offset - line_start,
+                    len_utf8: chr.len_utf8(),
+                    len_utf16: chr.len_utf16(),
+                });
+            }
+        }
+        Self {
+            line_starts,
+            wide_chars,
+            len: source.len(),
+        }
+    }
+
+    /// Returns the number of lines in the source.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the byte offset the given line starts at, if it exists.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.line_starts.get(line).copied()
+    }
+
+    /// Returns the [LineColumn] of the provided byte offset, with the column measured in the
+    /// provided unit. Offsets beyond the end of the source are clamped to the end, and for the
+    /// [ColumnUnit::Char] and [ColumnUnit::Utf16] units, offsets inside a char are clamped to
+    /// the start of that char.
+    pub fn line_column(&self, offset: usize, unit: ColumnUnit) -> LineColumn {
+        let offset = std::cmp::min(offset, self.len);
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let byte_column = offset - self.line_starts[line];
+        let wide_chars = &self.wide_chars[line];
+        let mut before = wide_chars.partition_point(|wide| wide.offset < byte_column);
+        let mut char_start = byte_column;
+        // An offset inside a char counts from the start of that char.
+        if let Some(wide) = before.checked_sub(1).map(|index| wide_chars[index])
+            && wide.offset + wide.len_utf8 > byte_column
+        {
+            char_start = wide.offset;
+            before -= 1;
+        }
+        let wide = wide_chars[..before].iter();
+        let column = match unit {
+            ColumnUnit::Byte => byte_column,
+            ColumnUnit::Char => char_start - wide.map(|w| w.len_utf8 - 1).sum::<usize>(),
+            ColumnUnit::Utf16 => char_start - wide.map(|w| w.len_utf8 - w.len_utf16).sum::<usize>(),
+        };
+        LineColumn::new(line, column)
+    }
+
+    /// Returns the [LineColumn]s of the start and end of the provided [Span].
+    pub fn span(&self, span: Span, unit: ColumnUnit) -> (LineColumn, LineColumn) {
+        (
+            self.line_column(span.start(), unit),
+            self.line_column(span.end(), unit),
+        )
+    }
+
+    /// Returns the [LineColumn]s of the start and end of the provided item's [Span].
+    pub fn locate(&self, item: &impl Located, unit: ColumnUnit) -> (LineColumn, LineColumn) {
+        self.span(item.span(), unit)
+    }
+}
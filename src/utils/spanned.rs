@@ -0,0 +1,96 @@
+use std::{
+    fmt::Display,
+    ops::{Deref, DerefMut},
+};
+
+use super::{Located, Location};
+
+/// A structure combining any value, such as a node of an AST, with the [Location] it was parsed
+/// from. [crate::parse::Parse::spanned] creates these from a sub-parse.
+///
+/// Like [crate::lex::Tok], the [PartialEq] implementation of this struct *ignores* the contained
+/// [Location], so that tests can compare trees without writing out every Location. Compare the
+/// Locations directly when they matter.
+#[derive(Debug, Eq, Clone, Copy, Default)]
+pub struct Spanned<T> {
+    /// The inner value.
+    pub value: T,
+    /// The [Location] the value comes from.
+    pub location: Location,
+}
+
+impl<T> Spanned<T> {
+    /// Creates a new Spanned with the provided value and location.
+    pub fn new(value: T, location: Location) -> Self {
+        Self { value, location }
+    }
+
+    /// Returns the inner value, discarding the Location.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Maps the inner value with the provided function, keeping the Location.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned::new(f(self.value), self.location)
+    }
+
+    /// Returns a Spanned referencing the inner value. This isn't named `as_ref`, so that the inner
+    /// value's own `as_ref` is still reached through [Deref].
+    pub fn as_spanned_ref(&self) -> Spanned<&T> {
+        Spanned::new(&self.value, self.location)
+    }
+
+    /// Returns a Spanned mutably referencing the inner value.
+    pub fn as_spanned_mut(&mut self) -> Spanned<&mut T> {
+        Spanned::new(&mut self.value, self.location)
+    }
+}
+
+impl<T> Spanned<Option<T>> {
+    /// Turns a Spanned Option into an Option of a Spanned value.
+    pub fn transpose(self) -> Option<Spanned<T>> {
+        let location = self.location;
+        self.value.map(|value| Spanned::new(value, location))
+    }
+}
+
+impl<T, E> Spanned<Result<T, E>> {
+    /// Turns a Spanned Result into a Result of a Spanned value. The error loses the Location.
+    pub fn transpose(self) -> Result<Spanned<T>, E> {
+        let location = self.location;
+        self.value.map(|value| Spanned::new(value, location))
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T> Located for Spanned<T> {
+    fn location(&self) -> Location {
+        self.location
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Spanned<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T: Display> Display for Spanned<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.value.fmt(f)
+    }
+}